@@ -0,0 +1,28 @@
+#!/bin/sh
+# Copy the complete src/mon/MonCommands.h of every release from a ceph git
+# checkout into fixtures/full/<release>/ for cargo test to parse, with the tag
+# and commit it came from in PROVENANCE.  The golden files next to them are
+# written by UPDATE_GOLDEN=1 cargo test -- --ignored full_headers.
+#
+#   fixtures/fetch-full.sh ~/src/ceph
+set -e
+
+if [ $# -ne 1 ]; then
+    echo "usage: $0 CEPH_CHECKOUT" >&2
+    exit 1
+fi
+ceph=$1
+full=$(dirname "$0")/full
+
+git -C "$ceph" fetch --tags origin
+
+for release in hammer:v0.94.10 jewel:v10.2.11 kraken:v11.2.1 luminous:v12.2.13 mimic:v13.2.10 \
+               nautilus:v14.2.22 octopus:v15.2.17 pacific:v16.2.15 quincy:v17.2.7 reef:v18.2.4 squid:v19.2.0; do
+    name=${release%%:*}
+    tag=${release#*:}
+    mkdir -p "$full/$name"
+    commit=$(git -C "$ceph" rev-parse "$tag^{commit}")
+    git -C "$ceph" show "$commit:src/mon/MonCommands.h" > "$full/$name/MonCommands.h"
+    echo "$tag $commit" > "$full/$name/PROVENANCE"
+    echo "$name: $tag $commit"
+done
@@ -0,0 +1,86 @@
+/*
+ * Excerpt of src/mon/MonCommands.h from Ceph hammer (v0.94).
+ */
+
+/*
+ * PG commands PGMonitor.cc
+ */
+
+COMMAND("pg stat", "show placement group status.", "pg", "r", "cli,rest")
+COMMAND("pg getmap", "get binary pg map to -o/stdout", "pg", "r", "cli,rest")
+COMMAND("pg send_pg_creates", "trigger pg creates to be issued",\
+	"pg", "rw", "cli,rest")
+COMMAND("pg dump " \
+	"name=dumpcontents,type=CephChoices,strings=all|summary|sum|delta|pools|osds|pgs|pgs_brief,n=N,req=false", \
+	"show human-readable versions of pg map (only 'all' valid with plain)", "pg", "r", "cli,rest")
+COMMAND("pg dump_stuck " \
+	"name=stuckops,type=CephChoices,strings=inactive|unclean|stale,n=N,req=false " \
+	"name=threshold,type=CephInt,req=false",
+	"show information about stuck pgs",\
+	"pg", "r", "cli,rest")
+COMMAND("pg map name=pgid,type=CephPgid", "show mapping of pg to osds", \
+	"pg", "r", "cli,rest")
+COMMAND("pg scrub name=pgid,type=CephPgid", "start scrub on <pgid>", \
+	"pg", "rw", "cli,rest")
+COMMAND("pg set_full_ratio name=ratio,type=CephFloat,range=0.0|1.0", \
+	"set ratio at which pgs are considered full", "pg", "rw", "cli,rest")
+
+/*
+ * auth commands AuthMonitor.cc
+ */
+
+COMMAND("auth get " \
+	"name=entity,type=CephString", \
+	"write keyring file with requested key", "auth", "rx", "cli,rest")
+COMMAND("auth get-or-create " \
+	"name=entity,type=CephString " \
+	"name=caps,type=CephString,n=N,req=false", \
+	"add auth info for <entity> from input file, or random key if no input given, and/or any caps specified in the command", \
+	"auth", "rwx", "cli,rest")
+
+/*
+ * Monitor commands (Monitor.cc)
+ */
+COMMAND_WITH_FLAG("compact", "cause compaction of monitor's leveldb storage", \
+	     "mon", "rw", "cli,rest", NOFORWARD)
+COMMAND("log name=logtext,type=CephString,n=N", \
+	"log supplied text to the monitor log", "mon", "rw", "cli,rest")
+COMMAND("mon remove " \
+	"name=name,type=CephString", \
+	"remove monitor named <name>", "mon", "rw", "cli,rest")
+
+/*
+ * OSD commands
+ */
+COMMAND("osd tree " \
+	"name=epoch,type=CephInt,range=0,req=false", \
+	"print OSD tree", "osd", "r", "cli,rest")
+COMMAND("osd reweight " \
+	"name=id,type=CephInt,range=0 " \
+	"type=CephFloat,name=weight,range=0.0|1.0", \
+	"reweight osd to 0.0 < <weight> < 1.0", "osd", "rw", "cli,rest")
+COMMAND("osd blacklist " \
+	"name=blacklistop,type=CephChoices,strings=add|rm " \
+	"name=addr,type=CephEntityAddr " \
+	"name=expire,type=CephFloat,range=0.0,req=false", \
+	"add (optionally until <expire> seconds from now) or remove <addr> from blacklist", \
+	"osd", "rw", "cli,rest")
+COMMAND("osd pool create " \
+	"name=pool,type=CephPoolname " \
+	"name=pg_num,type=CephInt,range=0 " \
+	"name=pgp_num,type=CephInt,range=0,req=false " \
+	"name=pool_type,type=CephChoices,strings=replicated|erasure,req=false " \
+	"name=erasure_code_profile,type=CephString,req=false,goodchars=[A-Za-z0-9-_.] " \
+	"name=ruleset,type=CephString,req=false", \
+	"create pool", "osd", "rw", "cli,rest")
+
+/*
+ * config-key commands
+ */
+COMMAND("config-key get " \
+	"name=key,type=CephString", \
+	"get <key>", "config-key", "r", "cli,rest")
+COMMAND("config-key put " \
+	"name=key,type=CephString " \
+	"name=val,type=CephString,req=false", \
+	"put <key>, value <val>", "config-key", "rw", "cli,rest")
@@ -0,0 +1,453 @@
+import json
+import os
+
+import ceph_argparse
+import rados
+import six
+
+
+class CephError(Exception):
+    """Exception raised for errors with running a Ceph command
+
+        :param cmd: cmd in which the error occurred
+        :param msg: explanation of the error
+    """
+
+    def __init__(self, cmd, msg):
+        self.cmd = cmd
+        self.msg = msg
+
+
+def run_ceph_command(conffile, cmd, inbuf, target=('mon', '')):
+    """Run a ceph command and return the results
+
+    :param conffile: The ceph.conf configuration location
+    :param cmd: The json command to run
+    :param inbuf:
+    :param target: (daemon type, daemon id) to send the command to.
+        daemon type is one of mon, mgr, osd or mds
+    :return: (string outbuf, string outs)
+    :raise rados.Error: Raises on rados errors
+    """
+    cluster = rados.Rados(conffile=conffile)
+    try:
+        cluster.connect()
+        if target[0] == 'mgr':
+            result = cluster.mgr_command(json.dumps(cmd), inbuf=inbuf)
+        elif target[0] == 'osd':
+            result = cluster.osd_command(int(target[1]), json.dumps(cmd),
+                                         inbuf=inbuf)
+        elif target[0] == 'mds':
+            import cephfs
+            fs = cephfs.LibCephFS(rados_inst=cluster)
+            fs.init()
+            result = fs.mds_command(str(target[1]), [json.dumps(cmd)], inbuf)
+        else:
+            result = cluster.mon_command(json.dumps(cmd), inbuf=inbuf)
+        if result[0] != 0:
+            raise CephError(cmd=cmd, msg=os.strerror(abs(result[0])))
+        return result[1], result[2]
+    except rados.Error as e:
+        raise e
+
+
+class PlacementGroupCommand:
+    def __init__(self, rados_config_file):
+        self.rados_config_file = rados_config_file
+
+    def pg_stat(self):
+        """
+        show placement group status.
+
+        :return: (string outbuf, string outs)
+        :raise CephError: Raises CephError on command execution errors
+        :raise rados.Error: Raises on rados errors
+        """
+
+        cmd = {'prefix': 'pg stat'}
+        return run_ceph_command(self.rados_config_file, cmd, inbuf='',
+                                target=('mon', ''))
+
+    def pg_getmap(self):
+        """
+        get binary pg map to -o/stdout
+
+        :return: (string outbuf, string outs)
+        :raise CephError: Raises CephError on command execution errors
+        :raise rados.Error: Raises on rados errors
+        """
+
+        cmd = {'prefix': 'pg getmap'}
+        return run_ceph_command(self.rados_config_file, cmd, inbuf='',
+                                target=('mon', ''))
+
+    def pg_send_pg_creates(self):
+        """
+        trigger pg creates to be issued
+
+        :return: (string outbuf, string outs)
+        :raise CephError: Raises CephError on command execution errors
+        :raise rados.Error: Raises on rados errors
+        """
+
+        cmd = {'prefix': 'pg send_pg_creates'}
+        return run_ceph_command(self.rados_config_file, cmd, inbuf='',
+                                target=('mon', ''))
+
+    def pg_dump(self, dumpcontents=None):
+        """
+        show human-readable versions of pg map (only 'all' valid with plain)
+
+        :param dumpcontents: list valid_range=["all", "summary", "sum",
+            "delta", "pools", "osds", "pgs", "pgs_brief"] allowed repeats=many
+        :return: (string outbuf, string outs)
+        :raise CephError: Raises CephError on command execution errors
+        :raise rados.Error: Raises on rados errors
+        """
+
+        cmd = {'prefix': 'pg dump'}
+
+        if dumpcontents is not None:
+            dumpcontents_validator = ceph_argparse.CephChoices(
+                strings="all|summary|sum|delta|pools|osds|pgs|pgs_brief")
+            for s in dumpcontents:
+                dumpcontents_validator.valid(s)
+            cmd['dumpcontents'] = dumpcontents
+        return run_ceph_command(self.rados_config_file, cmd, inbuf='',
+                                target=('mon', ''))
+
+    def pg_dump_stuck(self, stuckops=None, threshold=None):
+        """
+        show information about stuck pgs
+
+        :param stuckops: list valid_range=["inactive", "unclean", "stale"]
+            allowed repeats=many
+        :param threshold: int
+        :return: (string outbuf, string outs)
+        :raise CephError: Raises CephError on command execution errors
+        :raise rados.Error: Raises on rados errors
+        """
+
+        cmd = {'prefix': 'pg dump_stuck'}
+
+        if stuckops is not None:
+            stuckops_validator = ceph_argparse.CephChoices(
+                strings="inactive|unclean|stale")
+            for s in stuckops:
+                stuckops_validator.valid(s)
+            cmd['stuckops'] = stuckops
+
+        if threshold is not None:
+            threshold_validator = ceph_argparse.CephInt(range='')
+            threshold_validator.valid(threshold)
+            cmd['threshold'] = threshold
+        return run_ceph_command(self.rados_config_file, cmd, inbuf='',
+                                target=('mon', ''))
+
+    def pg_map(self, pgid):
+        """
+        show mapping of pg to osds
+
+        :param pgid: six.string_types
+        :return: (string outbuf, string outs)
+        :raise CephError: Raises CephError on command execution errors
+        :raise rados.Error: Raises on rados errors
+        """
+
+        pgid_validator = ceph_argparse.CephPgid()
+        pgid_validator.valid(pgid)
+        cmd = {'prefix': 'pg map', 'pgid': pgid}
+        return run_ceph_command(self.rados_config_file, cmd, inbuf='',
+                                target=('mon', ''))
+
+    def pg_scrub(self, pgid):
+        """
+        start scrub on <pgid>
+
+        :param pgid: six.string_types
+        :return: (string outbuf, string outs)
+        :raise CephError: Raises CephError on command execution errors
+        :raise rados.Error: Raises on rados errors
+        """
+
+        pgid_validator = ceph_argparse.CephPgid()
+        pgid_validator.valid(pgid)
+        cmd = {'prefix': 'pg scrub', 'pgid': pgid}
+        return run_ceph_command(self.rados_config_file, cmd, inbuf='',
+                                target=('mon', ''))
+
+    def pg_set_full_ratio(self, ratio):
+        """
+        set ratio at which pgs are considered full
+
+        :param ratio: float min=0 max=1
+        :return: (string outbuf, string outs)
+        :raise CephError: Raises CephError on command execution errors
+        :raise rados.Error: Raises on rados errors
+        """
+
+        ratio_validator = ceph_argparse.CephFloat(range='0|1')
+        ratio_validator.valid(ratio)
+        cmd = {'prefix': 'pg set_full_ratio', 'ratio': ratio}
+        return run_ceph_command(self.rados_config_file, cmd, inbuf='',
+                                target=('mon', ''))
+
+
+class OsdCommand:
+    def __init__(self, rados_config_file):
+        self.rados_config_file = rados_config_file
+
+    def osd_tree(self, epoch=None):
+        """
+        print OSD tree
+
+        :param epoch: int min=0
+        :return: (string outbuf, string outs)
+        :raise CephError: Raises CephError on command execution errors
+        :raise rados.Error: Raises on rados errors
+        """
+
+        cmd = {'prefix': 'osd tree'}
+
+        if epoch is not None:
+            epoch_validator = ceph_argparse.CephInt(range='0')
+            epoch_validator.valid(epoch)
+            cmd['epoch'] = epoch
+        return run_ceph_command(self.rados_config_file, cmd, inbuf='',
+                                target=('mon', ''))
+
+    def osd_reweight(self, id, weight):
+        """
+        reweight osd to 0.0 < <weight> < 1.0
+
+        :param id: int min=0
+        :param weight: float min=0 max=1
+        :return: (string outbuf, string outs)
+        :raise CephError: Raises CephError on command execution errors
+        :raise rados.Error: Raises on rados errors
+        """
+
+        id_validator = ceph_argparse.CephInt(range='0')
+        id_validator.valid(id)
+        weight_validator = ceph_argparse.CephFloat(range='0|1')
+        weight_validator.valid(weight)
+        cmd = {'prefix': 'osd reweight', 'id': id, 'weight': weight}
+        return run_ceph_command(self.rados_config_file, cmd, inbuf='',
+                                target=('mon', ''))
+
+    def osd_blacklist(self, blacklistop, addr, expire=None):
+        """
+        add (optionally until <expire> seconds from now) or remove <addr> from
+        blacklist
+
+        :param blacklistop: list valid_range=["add", "rm"] allowed repeats=one
+        :param addr: CephIPAddr + optional '/nonce'
+        :param expire: float min=0
+        :return: (string outbuf, string outs)
+        :raise CephError: Raises CephError on command execution errors
+        :raise rados.Error: Raises on rados errors
+        """
+
+        blacklistop_validator = ceph_argparse.CephChoices(strings="add|rm")
+        blacklistop_validator.valid(blacklistop)
+        addr_validator = ceph_argparse.CephEntityAddr()
+        addr_validator.valid(addr)
+        cmd = {
+            'prefix': 'osd blacklist',
+            'blacklistop': blacklistop,
+            'addr': addr,
+        }
+
+        if expire is not None:
+            expire_validator = ceph_argparse.CephFloat(range='0')
+            expire_validator.valid(expire)
+            cmd['expire'] = expire
+        return run_ceph_command(self.rados_config_file, cmd, inbuf='',
+                                target=('mon', ''))
+
+    def osd_pool_create(self, pool, pg_num, pgp_num=None, pool_type=None,
+                        erasure_code_profile=None, ruleset=None):
+        """
+        create pool
+
+        :param pool: six.string_types
+        :param pg_num: int min=0
+        :param pgp_num: int min=0
+        :param pool_type: list valid_range=["replicated", "erasure"] allowed
+            repeats=one
+        :param erasure_code_profile: six.string_types
+            valid_characters=[A-Za-z0-9-_.] allowed repeats=one
+        :param ruleset: six.string_types allowed repeats=one
+        :return: (string outbuf, string outs)
+        :raise CephError: Raises CephError on command execution errors
+        :raise rados.Error: Raises on rados errors
+        """
+
+        if not isinstance(pool, six.string_types):
+            raise TypeError("pool is not a String")
+        pg_num_validator = ceph_argparse.CephInt(range='0')
+        pg_num_validator.valid(pg_num)
+        cmd = {'prefix': 'osd pool create', 'pool': pool, 'pg_num': pg_num}
+
+        if pgp_num is not None:
+            pgp_num_validator = ceph_argparse.CephInt(range='0')
+            pgp_num_validator.valid(pgp_num)
+            cmd['pgp_num'] = pgp_num
+
+        if pool_type is not None:
+            pool_type_validator = ceph_argparse.CephChoices(
+                strings="replicated|erasure")
+            pool_type_validator.valid(pool_type)
+            cmd['pool_type'] = pool_type
+
+        if erasure_code_profile is not None:
+            erasure_code_profile_validator = ceph_argparse.CephString(
+                goodchars="[A-Za-z0-9-_.]")
+            erasure_code_profile_validator.valid(erasure_code_profile)
+            cmd['erasure_code_profile'] = erasure_code_profile
+
+        if ruleset is not None:
+            ruleset_validator = ceph_argparse.CephString(goodchars="")
+            ruleset_validator.valid(ruleset)
+            cmd['ruleset'] = ruleset
+        return run_ceph_command(self.rados_config_file, cmd, inbuf='',
+                                target=('mon', ''))
+
+
+class MonitorCommand:
+    def __init__(self, rados_config_file):
+        self.rados_config_file = rados_config_file
+
+    def compact(self):
+        """
+        cause compaction of monitor's leveldb storage
+
+        :return: (string outbuf, string outs)
+        :raise CephError: Raises CephError on command execution errors
+        :raise rados.Error: Raises on rados errors
+        """
+
+        cmd = {'prefix': 'compact'}
+        return run_ceph_command(self.rados_config_file, cmd, inbuf='',
+                                target=('mon', ''))
+
+    def log(self, logtext):
+        """
+        log supplied text to the monitor log
+
+        :param logtext: six.string_types allowed repeats=many
+        :return: (string outbuf, string outs)
+        :raise CephError: Raises CephError on command execution errors
+        :raise rados.Error: Raises on rados errors
+        """
+
+        logtext_validator = ceph_argparse.CephString(goodchars="")
+        for s in logtext:
+            logtext_validator.valid(s)
+        cmd = {'prefix': 'log', 'logtext': logtext}
+        return run_ceph_command(self.rados_config_file, cmd, inbuf='',
+                                target=('mon', ''))
+
+    def mon_remove(self, name):
+        """
+        remove monitor named <name>
+
+        :param name: six.string_types allowed repeats=one
+        :return: (string outbuf, string outs)
+        :raise CephError: Raises CephError on command execution errors
+        :raise rados.Error: Raises on rados errors
+        """
+
+        name_validator = ceph_argparse.CephString(goodchars="")
+        name_validator.valid(name)
+        cmd = {'prefix': 'mon remove', 'name': name}
+        return run_ceph_command(self.rados_config_file, cmd, inbuf='',
+                                target=('mon', ''))
+
+
+class AuthCommand:
+    def __init__(self, rados_config_file):
+        self.rados_config_file = rados_config_file
+
+    def auth_get(self, entity):
+        """
+        write keyring file with requested key
+
+        :param entity: six.string_types allowed repeats=one
+        :return: (string outbuf, string outs)
+        :raise CephError: Raises CephError on command execution errors
+        :raise rados.Error: Raises on rados errors
+        """
+
+        entity_validator = ceph_argparse.CephString(goodchars="")
+        entity_validator.valid(entity)
+        cmd = {'prefix': 'auth get', 'entity': entity}
+        return run_ceph_command(self.rados_config_file, cmd, inbuf='',
+                                target=('mon', ''))
+
+    def auth_get_or_create(self, entity, caps=None):
+        """
+        add auth info for <entity> from input file, or random key if no input
+        given, and/or any caps specified in the command
+
+        :param entity: six.string_types allowed repeats=one
+        :param caps: six.string_types allowed repeats=many
+        :return: (string outbuf, string outs)
+        :raise CephError: Raises CephError on command execution errors
+        :raise rados.Error: Raises on rados errors
+        """
+
+        entity_validator = ceph_argparse.CephString(goodchars="")
+        entity_validator.valid(entity)
+        cmd = {'prefix': 'auth get-or-create', 'entity': entity}
+
+        if caps is not None:
+            caps_validator = ceph_argparse.CephString(goodchars="")
+            for s in caps:
+                caps_validator.valid(s)
+            cmd['caps'] = caps
+        return run_ceph_command(self.rados_config_file, cmd, inbuf='',
+                                target=('mon', ''))
+
+
+class ConfigKeyCommand:
+    def __init__(self, rados_config_file):
+        self.rados_config_file = rados_config_file
+
+    def config_key_get(self, key):
+        """
+        get <key>
+
+        :param key: six.string_types allowed repeats=one
+        :return: (string outbuf, string outs)
+        :raise CephError: Raises CephError on command execution errors
+        :raise rados.Error: Raises on rados errors
+        """
+
+        key_validator = ceph_argparse.CephString(goodchars="")
+        key_validator.valid(key)
+        cmd = {'prefix': 'config-key get', 'key': key}
+        return run_ceph_command(self.rados_config_file, cmd, inbuf='',
+                                target=('mon', ''))
+
+    def config_key_put(self, key, val=None):
+        """
+        put <key>, value <val>
+
+        :param key: six.string_types allowed repeats=one
+        :param val: six.string_types allowed repeats=one
+        :return: (string outbuf, string outs)
+        :raise CephError: Raises CephError on command execution errors
+        :raise rados.Error: Raises on rados errors
+        """
+
+        key_validator = ceph_argparse.CephString(goodchars="")
+        key_validator.valid(key)
+        cmd = {'prefix': 'config-key put', 'key': key}
+
+        if val is not None:
+            val_validator = ceph_argparse.CephString(goodchars="")
+            val_validator.valid(val)
+            cmd['val'] = val
+        return run_ceph_command(self.rados_config_file, cmd, inbuf='',
+                                target=('mon', ''))
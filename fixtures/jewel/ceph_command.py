@@ -0,0 +1,399 @@
+import json
+import os
+
+import ceph_argparse
+import rados
+import six
+
+
+class CephError(Exception):
+    """Exception raised for errors with running a Ceph command
+
+        :param cmd: cmd in which the error occurred
+        :param msg: explanation of the error
+    """
+
+    def __init__(self, cmd, msg):
+        self.cmd = cmd
+        self.msg = msg
+
+
+def run_ceph_command(conffile, cmd, inbuf, target=('mon', '')):
+    """Run a ceph command and return the results
+
+    :param conffile: The ceph.conf configuration location
+    :param cmd: The json command to run
+    :param inbuf:
+    :param target: (daemon type, daemon id) to send the command to.
+        daemon type is one of mon, mgr, osd or mds
+    :return: (string outbuf, string outs)
+    :raise rados.Error: Raises on rados errors
+    """
+    cluster = rados.Rados(conffile=conffile)
+    try:
+        cluster.connect()
+        if target[0] == 'mgr':
+            result = cluster.mgr_command(json.dumps(cmd), inbuf=inbuf)
+        elif target[0] == 'osd':
+            result = cluster.osd_command(int(target[1]), json.dumps(cmd),
+                                         inbuf=inbuf)
+        elif target[0] == 'mds':
+            import cephfs
+            fs = cephfs.LibCephFS(rados_inst=cluster)
+            fs.init()
+            result = fs.mds_command(str(target[1]), [json.dumps(cmd)], inbuf)
+        else:
+            result = cluster.mon_command(json.dumps(cmd), inbuf=inbuf)
+        if result[0] != 0:
+            raise CephError(cmd=cmd, msg=os.strerror(abs(result[0])))
+        return result[1], result[2]
+    except rados.Error as e:
+        raise e
+
+
+class PlacementGroupCommand:
+    def __init__(self, rados_config_file):
+        self.rados_config_file = rados_config_file
+
+    def pg_stat(self):
+        """
+        show placement group status.
+
+        :return: (string outbuf, string outs)
+        :raise CephError: Raises CephError on command execution errors
+        :raise rados.Error: Raises on rados errors
+        """
+
+        cmd = {'prefix': 'pg stat'}
+        return run_ceph_command(self.rados_config_file, cmd, inbuf='',
+                                target=('mon', ''))
+
+    def pg_dump_pools_json(self):
+        """
+        show pg pools info in json only
+
+        :return: (string outbuf, string outs)
+        :raise CephError: Raises CephError on command execution errors
+        :raise rados.Error: Raises on rados errors
+        """
+
+        cmd = {'prefix': 'pg dump_pools_json'}
+        return run_ceph_command(self.rados_config_file, cmd, inbuf='',
+                                target=('mon', ''))
+
+    def pg_ls_by_osd(self, osd, pool=None, states=None):
+        """
+        list pg on osd [osd]
+
+        :param osd: six.string_types
+        :param pool: int
+        :param states: list valid_range=["active", "clean", "down", "replay",
+            "splitting", "scrubbing", "scrubq", "degraded", "inconsistent",
+            "peering", "repair", "recovering", "backfill_wait", "incomplete",
+            "stale", "remapped", "deep_scrub", "backfill", "backfill_toofull",
+            "recovery_wait", "undersized", "activating", "peered"] allowed
+            repeats=many
+        :return: (string outbuf, string outs)
+        :raise CephError: Raises CephError on command execution errors
+        :raise rados.Error: Raises on rados errors
+        """
+
+        osd_validator = ceph_argparse.CephOsdName()
+        osd_validator.valid(osd)
+        cmd = {'prefix': 'pg ls-by-osd', 'osd': osd}
+
+        if pool is not None:
+            pool_validator = ceph_argparse.CephInt(range='')
+            pool_validator.valid(pool)
+            cmd['pool'] = pool
+
+        if states is not None:
+            states_validator = ceph_argparse.CephChoices(
+                strings="active|clean|down|replay|splitting|scrubbing|"
+                        "scrubq|degraded|inconsistent|peering|repair|"
+                        "recovering|backfill_wait|incomplete|stale|remapped|"
+                        "deep_scrub|backfill|backfill_toofull|recovery_wait|"
+                        "undersized|activating|peered")
+            for s in states:
+                states_validator.valid(s)
+            cmd['states'] = states
+        return run_ceph_command(self.rados_config_file, cmd, inbuf='',
+                                target=('mon', ''))
+
+    def pg_deep_scrub(self, pgid):
+        """
+        start deep-scrub on <pgid>
+
+        :param pgid: six.string_types
+        :return: (string outbuf, string outs)
+        :raise CephError: Raises CephError on command execution errors
+        :raise rados.Error: Raises on rados errors
+        """
+
+        pgid_validator = ceph_argparse.CephPgid()
+        pgid_validator.valid(pgid)
+        cmd = {'prefix': 'pg deep-scrub', 'pgid': pgid}
+        return run_ceph_command(self.rados_config_file, cmd, inbuf='',
+                                target=('mon', ''))
+
+
+class MdsCommand:
+    def __init__(self, rados_config_file):
+        self.rados_config_file = rados_config_file
+
+    def mds_set_max_mds(self, maxmds):
+        """
+        set max MDS index
+
+        :param maxmds: int min=0
+        :return: (string outbuf, string outs)
+        :raise CephError: Raises CephError on command execution errors
+        :raise rados.Error: Raises on rados errors
+        """
+
+        maxmds_validator = ceph_argparse.CephInt(range='0')
+        maxmds_validator.valid(maxmds)
+        cmd = {'prefix': 'mds set_max_mds', 'maxmds': maxmds}
+        return run_ceph_command(self.rados_config_file, cmd, inbuf='',
+                                target=('mon', ''))
+
+
+class OsdCommand:
+    def __init__(self, rados_config_file):
+        self.rados_config_file = rados_config_file
+
+    def osd_crush_add(self, id, weight, args):
+        """
+        add or update crushmap position and weight for <name> with <weight> and
+        location <args>
+
+        :param id: six.string_types
+        :param weight: float min=0
+        :param args: six.string_types valid_characters=[A-Za-z0-9-_.=] allowed
+            repeats=many
+        :return: (string outbuf, string outs)
+        :raise CephError: Raises CephError on command execution errors
+        :raise rados.Error: Raises on rados errors
+        """
+
+        id_validator = ceph_argparse.CephOsdName()
+        id_validator.valid(id)
+        weight_validator = ceph_argparse.CephFloat(range='0')
+        weight_validator.valid(weight)
+        args_validator = ceph_argparse.CephString(goodchars="[A-Za-z0-9-_.=]")
+        for s in args:
+            args_validator.valid(s)
+        cmd = {
+            'prefix': 'osd crush add',
+            'id': id,
+            'weight': weight,
+            'args': args,
+        }
+        return run_ceph_command(self.rados_config_file, cmd, inbuf='',
+                                target=('mon', ''))
+
+    def osd_crush_rule_create_simple(self, name, root, type, mode=None):
+        """
+        create crush rule <name> to start from <root>, replicate across buckets
+        of type <type>, using a choose mode of <firstn|indep> (default firstn;
+        indep best for erasure pools)
+
+        :param name: six.string_types valid_characters=[A-Za-z0-9-_.] allowed
+            repeats=one
+        :param root: six.string_types valid_characters=[A-Za-z0-9-_.] allowed
+            repeats=one
+        :param type: six.string_types valid_characters=[A-Za-z0-9-_.] allowed
+            repeats=one
+        :param mode: list valid_range=["firstn", "indep"] allowed repeats=one
+        :return: (string outbuf, string outs)
+        :raise CephError: Raises CephError on command execution errors
+        :raise rados.Error: Raises on rados errors
+        """
+
+        name_validator = ceph_argparse.CephString(goodchars="[A-Za-z0-9-_.]")
+        name_validator.valid(name)
+        root_validator = ceph_argparse.CephString(goodchars="[A-Za-z0-9-_.]")
+        root_validator.valid(root)
+        type_validator = ceph_argparse.CephString(goodchars="[A-Za-z0-9-_.]")
+        type_validator.valid(type)
+        cmd = {
+            'prefix': 'osd crush rule create-simple',
+            'name': name,
+            'root': root,
+            'type': type,
+        }
+
+        if mode is not None:
+            mode_validator = ceph_argparse.CephChoices(strings="firstn|indep")
+            mode_validator.valid(mode)
+            cmd['mode'] = mode
+        return run_ceph_command(self.rados_config_file, cmd, inbuf='',
+                                target=('mon', ''))
+
+    def osd_pool_delete(self, pool, pool2=None, sure=None):
+        """
+        delete pool
+
+        :param pool: six.string_types
+        :param pool2: six.string_types
+        :param sure: list valid_range=["--yes-i-really-really-mean-it"] allowed
+            repeats=one
+        :return: (string outbuf, string outs)
+        :raise CephError: Raises CephError on command execution errors
+        :raise rados.Error: Raises on rados errors
+        """
+
+        if not isinstance(pool, six.string_types):
+            raise TypeError("pool is not a String")
+        cmd = {'prefix': 'osd pool delete', 'pool': pool}
+
+        if pool2 is not None:
+            if not isinstance(pool2, six.string_types):
+                raise TypeError("pool2 is not a String")
+            cmd['pool2'] = pool2
+
+        if sure is not None:
+            sure_validator = ceph_argparse.CephChoices(
+                strings="--yes-i-really-really-mean-it")
+            sure_validator.valid(sure)
+            cmd['sure'] = sure
+        return run_ceph_command(self.rados_config_file, cmd, inbuf='',
+                                target=('mon', ''))
+
+    def osd_pool_set_quota(self, pool, field, val):
+        """
+        set object or byte limit on pool
+
+        :param pool: six.string_types
+        :param field: list valid_range=["max_objects", "max_bytes"] allowed
+            repeats=one
+        :param val: six.string_types allowed repeats=one
+        :return: (string outbuf, string outs)
+        :raise CephError: Raises CephError on command execution errors
+        :raise rados.Error: Raises on rados errors
+        """
+
+        if not isinstance(pool, six.string_types):
+            raise TypeError("pool is not a String")
+        field_validator = ceph_argparse.CephChoices(
+            strings="max_objects|max_bytes")
+        field_validator.valid(field)
+        val_validator = ceph_argparse.CephString(goodchars="")
+        val_validator.valid(val)
+        cmd = {
+            'prefix': 'osd pool set-quota',
+            'pool': pool,
+            'field': field,
+            'val': val,
+        }
+        return run_ceph_command(self.rados_config_file, cmd, inbuf='',
+                                target=('mon', ''))
+
+    def osd_tier_add(self, pool, tierpool, force_nonempty=None):
+        """
+        add the tier <tierpool> (the second one) to base pool <pool> (the first
+        one)
+
+        :param pool: six.string_types
+        :param tierpool: six.string_types
+        :param force_nonempty: list valid_range=["--force-nonempty"] allowed
+            repeats=one
+        :return: (string outbuf, string outs)
+        :raise CephError: Raises CephError on command execution errors
+        :raise rados.Error: Raises on rados errors
+        """
+
+        if not isinstance(pool, six.string_types):
+            raise TypeError("pool is not a String")
+        if not isinstance(tierpool, six.string_types):
+            raise TypeError("tierpool is not a String")
+        cmd = {'prefix': 'osd tier add', 'pool': pool, 'tierpool': tierpool}
+
+        if force_nonempty is not None:
+            force_nonempty_validator = ceph_argparse.CephChoices(
+                strings="--force-nonempty")
+            force_nonempty_validator.valid(force_nonempty)
+            cmd['force_nonempty'] = force_nonempty
+        return run_ceph_command(self.rados_config_file, cmd, inbuf='',
+                                target=('mon', ''))
+
+
+class MonitorCommand:
+    def __init__(self, rados_config_file):
+        self.rados_config_file = rados_config_file
+
+    def compact(self):
+        """
+        cause compaction of monitor's leveldb storage
+
+        :return: (string outbuf, string outs)
+        :raise CephError: Raises CephError on command execution errors
+        :raise rados.Error: Raises on rados errors
+        """
+
+        cmd = {'prefix': 'compact'}
+        return run_ceph_command(self.rados_config_file, cmd, inbuf='',
+                                target=('mon', ''))
+
+    def scrub(self):
+        """
+        scrub the monitor stores (DEPRECATED)
+
+        :return: (string outbuf, string outs)
+        :raise CephError: Raises CephError on command execution errors
+        :raise rados.Error: Raises on rados errors
+        """
+
+        cmd = {'prefix': 'scrub'}
+        return run_ceph_command(self.rados_config_file, cmd, inbuf='',
+                                target=('mon', ''))
+
+    def fsid(self):
+        """
+        show cluster FSID/UUID
+
+        :return: (string outbuf, string outs)
+        :raise CephError: Raises CephError on command execution errors
+        :raise rados.Error: Raises on rados errors
+        """
+
+        cmd = {'prefix': 'fsid'}
+        return run_ceph_command(self.rados_config_file, cmd, inbuf='',
+                                target=('mon', ''))
+
+    def quorum(self, quorumcmd):
+        """
+        enter or exit quorum
+
+        :param quorumcmd: list valid_range=["enter", "exit"] allowed
+            repeats=one
+        :return: (string outbuf, string outs)
+        :raise CephError: Raises CephError on command execution errors
+        :raise rados.Error: Raises on rados errors
+        """
+
+        quorumcmd_validator = ceph_argparse.CephChoices(strings="enter|exit")
+        quorumcmd_validator.valid(quorumcmd)
+        cmd = {'prefix': 'quorum', 'quorumcmd': quorumcmd}
+        return run_ceph_command(self.rados_config_file, cmd, inbuf='',
+                                target=('mon', ''))
+
+    def tell(self, target, args):
+        """
+        send a command to a specific daemon
+
+        :param target: six.string_types
+        :param args: six.string_types allowed repeats=many
+        :return: (string outbuf, string outs)
+        :raise CephError: Raises CephError on command execution errors
+        :raise rados.Error: Raises on rados errors
+        """
+
+        target_validator = ceph_argparse.CephName()
+        target_validator.valid(target)
+        args_validator = ceph_argparse.CephString(goodchars="")
+        for s in args:
+            args_validator.valid(s)
+        cmd = {'prefix': 'tell', 'target': target, 'args': args}
+        return run_ceph_command(self.rados_config_file, cmd, inbuf='',
+                                target=('mon', ''))
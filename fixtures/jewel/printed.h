@@ -0,0 +1,56 @@
+COMMAND("pg stat", "show placement group status.", "pg", "r", "cli,rest")
+COMMAND("pg dump_pools_json", "show pg pools info in json only", "pg", "r", "cli,rest")
+COMMAND("pg ls-by-osd " \
+	"name=osd,type=CephOsdName " \
+	"name=pool,type=CephInt,req=false " \
+	"name=states,type=CephChoices,strings=active|clean|down|replay|splitting|scrubbing|scrubq|degraded|inconsistent|peering|repair|recovering|backfill_wait|incomplete|stale|remapped|deep_scrub|backfill|backfill_toofull|recovery_wait|undersized|activating|peered,n=N,req=false ", \
+	"list pg on osd [osd]", "pg", "r", "cli,rest")
+COMMAND("pg deep-scrub " \
+	"name=pgid,type=CephPgid ", \
+	"start deep-scrub on <pgid>", "pg", "rw", "cli,rest")
+COMMAND_WITH_FLAG("compact", "cause compaction of monitor's leveldb storage", "mon", "rw", "cli,rest", \
+	FLAG(NOFORWARD)|FLAG(DEPRECATED))
+COMMAND_WITH_FLAG("scrub", "scrub the monitor stores (DEPRECATED)", "mon", "rw", "cli,rest", \
+	FLAG(DEPRECATED))
+COMMAND("fsid", "show cluster FSID/UUID", "mon", "r", "cli,rest")
+COMMAND("quorum " \
+	"name=quorumcmd,type=CephChoices,strings=enter|exit ", \
+	"enter or exit quorum", "mon", "rw", "cli,rest")
+COMMAND("tell " \
+	"name=target,type=CephName " \
+	"name=args,type=CephString,n=N ", \
+	"send a command to a specific daemon", "mon", "rw", "cli,rest")
+COMMAND("mds set_max_mds " \
+	"name=maxmds,type=CephInt,range=0 ", \
+	"set max MDS index", "mds", "rw", "cli,rest")
+COMMAND("fs new " \
+	"name=fs_name,type=CephString " \
+	"name=metadata,type=CephString " \
+	"name=data,type=CephString ", \
+	"make new filesystem using named pools <metadata> and <data>", "unknown", "rw", "cli,rest")
+COMMAND("osd crush add " \
+	"name=id,type=CephOsdName " \
+	"name=weight,type=CephFloat,range=0 " \
+	"name=args,type=CephString,goodchars=[A-Za-z0-9-_.=],n=N ", \
+	"add or update crushmap position and weight for <name> with <weight> and location <args>", "osd", "rw", "cli,rest")
+COMMAND("osd crush rule create-simple " \
+	"name=name,type=CephString,goodchars=[A-Za-z0-9-_.] " \
+	"name=root,type=CephString,goodchars=[A-Za-z0-9-_.] " \
+	"name=type,type=CephString,goodchars=[A-Za-z0-9-_.] " \
+	"name=mode,type=CephChoices,strings=firstn|indep,req=false ", \
+	"create crush rule <name> to start from <root>, replicate across buckets of type <type>, using a choose mode of <firstn|indep> (default firstn; indep best for erasure pools)", "osd", "rw", "cli,rest")
+COMMAND("osd pool delete " \
+	"name=pool,type=CephPoolname " \
+	"name=pool2,type=CephPoolname,req=false " \
+	"name=sure,type=CephChoices,strings=--yes-i-really-really-mean-it,req=false ", \
+	"delete pool", "osd", "rw", "cli,rest")
+COMMAND("osd pool set-quota " \
+	"name=pool,type=CephPoolname " \
+	"name=field,type=CephChoices,strings=max_objects|max_bytes " \
+	"name=val,type=CephString ", \
+	"set object or byte limit on pool", "osd", "rw", "cli,rest")
+COMMAND("osd tier add " \
+	"name=pool,type=CephPoolname " \
+	"name=tierpool,type=CephPoolname " \
+	"name=force_nonempty,type=CephChoices,strings=--force-nonempty,req=false ", \
+	"add the tier <tierpool> (the second one) to base pool <pool> (the first one)", "osd", "rw", "cli,rest")
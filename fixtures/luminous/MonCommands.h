@@ -0,0 +1,77 @@
+/*
+ * Excerpt of src/mon/MonCommands.h from Ceph luminous (v12.2).
+ */
+
+/*
+ * PG commands PGMonitor.cc
+ */
+
+// note: this should be replaced shortly!
+COMMAND_WITH_FLAG("pg force_create_pg name=pgid,type=CephPgid", \
+		  "force creation of pg <pgid>", "pg", "rw", "cli,rest", FLAG(DEPRECATED))
+COMMAND_WITH_FLAG("pg set_full_ratio name=ratio,type=CephFloat,range=0.0|1.0", \
+		  "set ratio at which pgs are considered full", \
+		  "pg", "rw", "cli,rest", FLAG(DEPRECATED))
+
+/*
+ * Monitor commands (Monitor.cc)
+ */
+COMMAND_WITH_FLAG("fsid", "show cluster FSID/UUID", "mon", "r", "cli,rest", \
+             FLAG(NOFORWARD))
+COMMAND("health name=detail,type=CephChoices,strings=detail,req=false", \
+	"show cluster health", "mon", "r", "cli,rest")
+COMMAND_WITH_FLAG("mon_status", "report status of monitors", "mon", "r", "cli,rest", \
+             FLAG(NOFORWARD))
+COMMAND("features", "report of connected features", \
+        "mon", "r", "cli,rest")
+COMMAND("mon feature set " \
+        "name=feature_name,type=CephString " \
+        "name=sure,type=CephString,req=false", \
+        "set provided feature on mon map", \
+        "mon", "rw", "cli,rest")
+
+/*
+ * OSD commands
+ */
+COMMAND("osd require-osd-release "\
+	"name=release,type=CephChoices,strings=luminous " \
+	"name=sure,type=CephChoices,strings=--yes-i-really-mean-it,req=false", \
+	"set the minimum allowed OSD release to participate in the cluster",
+	"osd", "rw", "cli,rest")
+COMMAND("osd pool application enable " \
+        "name=pool,type=CephPoolname " \
+        "name=app,type=CephString,goodchars=[A-Za-z0-9-_.] " \
+        "name=force,type=CephChoices,strings=--yes-i-really-mean-it,req=false",
+        "enable use of an application <app> [cephfs,rbd,rgw] on pool <poolname>",
+        "osd", "rw", "cli,rest")
+COMMAND("osd pool application get " \
+        "name=pool,type=CephPoolname,req=fasle " \
+        "name=app,type=CephString,req=false " \
+        "name=key,type=CephString,req=false",
+        "get value of key <key> of application <app> on pool <poolname>",
+        "osd", "r", "cli,rest")
+COMMAND("osd destroy " \
+        "name=id,type=CephOsdName " \
+        "name=sure,type=CephChoices,strings=--yes-i-really-mean-it,req=false", \
+        "mark osd as being destroyed. Keeps the ID intact (allowing reuse), " \
+        "but removes cephx keys, config-key data and lockbox keys, "\
+        "rendering data permanently unreadable.", \
+        "osd", "rw", "cli,rest")
+COMMAND("osd set-nearfull-ratio " \
+	"name=ratio,type=CephFloat,range=0.0|1.0", \
+	"set usage ratio at which OSDs are marked near-full",
+	"osd", "rw", "cli,rest")
+
+/*
+ * mgr commands
+ */
+COMMAND("mgr dump " \
+	"name=epoch,type=CephInt,range=0,req=false", \
+	"dump the latest MgrMap", \
+	"mgr", "r", "cli,rest")
+COMMAND("mgr fail name=who,type=CephString", \
+	"treat the named manager daemon as failed", "mgr", "rw", "cli,rest")
+COMMAND("mgr module enable " \
+	"name=module,type=CephString " \
+	"name=force,type=CephChoices,strings=--force,req=false", \
+	"enable mgr module", "mgr", "rw", "cli,rest")
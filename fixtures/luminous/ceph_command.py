@@ -0,0 +1,366 @@
+import json
+import os
+
+import ceph_argparse
+import rados
+import six
+
+
+class CephError(Exception):
+    """Exception raised for errors with running a Ceph command
+
+        :param cmd: cmd in which the error occurred
+        :param msg: explanation of the error
+    """
+
+    def __init__(self, cmd, msg):
+        self.cmd = cmd
+        self.msg = msg
+
+
+def run_ceph_command(conffile, cmd, inbuf, target=('mon', '')):
+    """Run a ceph command and return the results
+
+    :param conffile: The ceph.conf configuration location
+    :param cmd: The json command to run
+    :param inbuf:
+    :param target: (daemon type, daemon id) to send the command to.
+        daemon type is one of mon, mgr, osd or mds
+    :return: (string outbuf, string outs)
+    :raise rados.Error: Raises on rados errors
+    """
+    cluster = rados.Rados(conffile=conffile)
+    try:
+        cluster.connect()
+        if target[0] == 'mgr':
+            result = cluster.mgr_command(json.dumps(cmd), inbuf=inbuf)
+        elif target[0] == 'osd':
+            result = cluster.osd_command(int(target[1]), json.dumps(cmd),
+                                         inbuf=inbuf)
+        elif target[0] == 'mds':
+            import cephfs
+            fs = cephfs.LibCephFS(rados_inst=cluster)
+            fs.init()
+            result = fs.mds_command(str(target[1]), [json.dumps(cmd)], inbuf)
+        else:
+            result = cluster.mon_command(json.dumps(cmd), inbuf=inbuf)
+        if result[0] != 0:
+            raise CephError(cmd=cmd, msg=os.strerror(abs(result[0])))
+        return result[1], result[2]
+    except rados.Error as e:
+        raise e
+
+
+class PlacementGroupCommand:
+    def __init__(self, rados_config_file):
+        self.rados_config_file = rados_config_file
+
+    def pg_force_create_pg(self, pgid):
+        """
+        force creation of pg <pgid>
+
+        :param pgid: six.string_types
+        :return: (string outbuf, string outs)
+        :raise CephError: Raises CephError on command execution errors
+        :raise rados.Error: Raises on rados errors
+        """
+
+        pgid_validator = ceph_argparse.CephPgid()
+        pgid_validator.valid(pgid)
+        cmd = {'prefix': 'pg force_create_pg', 'pgid': pgid}
+        return run_ceph_command(self.rados_config_file, cmd, inbuf='',
+                                target=('mon', ''))
+
+    def pg_set_full_ratio(self, ratio):
+        """
+        set ratio at which pgs are considered full
+
+        :param ratio: float min=0 max=1
+        :return: (string outbuf, string outs)
+        :raise CephError: Raises CephError on command execution errors
+        :raise rados.Error: Raises on rados errors
+        """
+
+        ratio_validator = ceph_argparse.CephFloat(range='0|1')
+        ratio_validator.valid(ratio)
+        cmd = {'prefix': 'pg set_full_ratio', 'ratio': ratio}
+        return run_ceph_command(self.rados_config_file, cmd, inbuf='',
+                                target=('mon', ''))
+
+
+class OsdCommand:
+    def __init__(self, rados_config_file):
+        self.rados_config_file = rados_config_file
+
+    def osd_require_osd_release(self, release, sure=None):
+        """
+        set the minimum allowed OSD release to participate in the cluster
+
+        :param release: list valid_range=["luminous"] allowed repeats=one
+        :param sure: list valid_range=["--yes-i-really-mean-it"] allowed
+            repeats=one
+        :return: (string outbuf, string outs)
+        :raise CephError: Raises CephError on command execution errors
+        :raise rados.Error: Raises on rados errors
+        """
+
+        release_validator = ceph_argparse.CephChoices(strings="luminous")
+        release_validator.valid(release)
+        cmd = {'prefix': 'osd require-osd-release', 'release': release}
+
+        if sure is not None:
+            sure_validator = ceph_argparse.CephChoices(
+                strings="--yes-i-really-mean-it")
+            sure_validator.valid(sure)
+            cmd['sure'] = sure
+        return run_ceph_command(self.rados_config_file, cmd, inbuf='',
+                                target=('mon', ''))
+
+    def osd_pool_application_enable(self, pool, app, force=None):
+        """
+        enable use of an application <app> [cephfs,rbd,rgw] on pool <poolname>
+
+        :param pool: six.string_types
+        :param app: six.string_types valid_characters=[A-Za-z0-9-_.] allowed
+            repeats=one
+        :param force: list valid_range=["--yes-i-really-mean-it"] allowed
+            repeats=one
+        :return: (string outbuf, string outs)
+        :raise CephError: Raises CephError on command execution errors
+        :raise rados.Error: Raises on rados errors
+        """
+
+        if not isinstance(pool, six.string_types):
+            raise TypeError("pool is not a String")
+        app_validator = ceph_argparse.CephString(goodchars="[A-Za-z0-9-_.]")
+        app_validator.valid(app)
+        cmd = {
+            'prefix': 'osd pool application enable',
+            'pool': pool,
+            'app': app,
+        }
+
+        if force is not None:
+            force_validator = ceph_argparse.CephChoices(
+                strings="--yes-i-really-mean-it")
+            force_validator.valid(force)
+            cmd['force'] = force
+        return run_ceph_command(self.rados_config_file, cmd, inbuf='',
+                                target=('mon', ''))
+
+    def osd_pool_application_get(self, pool, app=None, key=None):
+        """
+        get value of key <key> of application <app> on pool <poolname>
+
+        :param pool: six.string_types
+        :param app: six.string_types allowed repeats=one
+        :param key: six.string_types allowed repeats=one
+        :return: (string outbuf, string outs)
+        :raise CephError: Raises CephError on command execution errors
+        :raise rados.Error: Raises on rados errors
+        """
+
+        if not isinstance(pool, six.string_types):
+            raise TypeError("pool is not a String")
+        cmd = {'prefix': 'osd pool application get', 'pool': pool}
+
+        if app is not None:
+            app_validator = ceph_argparse.CephString(goodchars="")
+            app_validator.valid(app)
+            cmd['app'] = app
+
+        if key is not None:
+            key_validator = ceph_argparse.CephString(goodchars="")
+            key_validator.valid(key)
+            cmd['key'] = key
+        return run_ceph_command(self.rados_config_file, cmd, inbuf='',
+                                target=('mon', ''))
+
+    def osd_destroy(self, id, sure=None):
+        """
+        mark osd as being destroyed. Keeps the ID intact (allowing reuse), but
+        removes cephx keys, config-key data and lockbox keys, rendering data
+        permanently unreadable.
+
+        :param id: six.string_types
+        :param sure: list valid_range=["--yes-i-really-mean-it"] allowed
+            repeats=one
+        :return: (string outbuf, string outs)
+        :raise CephError: Raises CephError on command execution errors
+        :raise rados.Error: Raises on rados errors
+        """
+
+        id_validator = ceph_argparse.CephOsdName()
+        id_validator.valid(id)
+        cmd = {'prefix': 'osd destroy', 'id': id}
+
+        if sure is not None:
+            sure_validator = ceph_argparse.CephChoices(
+                strings="--yes-i-really-mean-it")
+            sure_validator.valid(sure)
+            cmd['sure'] = sure
+        return run_ceph_command(self.rados_config_file, cmd, inbuf='',
+                                target=('mon', ''))
+
+    def osd_set_nearfull_ratio(self, ratio):
+        """
+        set usage ratio at which OSDs are marked near-full
+
+        :param ratio: float min=0 max=1
+        :return: (string outbuf, string outs)
+        :raise CephError: Raises CephError on command execution errors
+        :raise rados.Error: Raises on rados errors
+        """
+
+        ratio_validator = ceph_argparse.CephFloat(range='0|1')
+        ratio_validator.valid(ratio)
+        cmd = {'prefix': 'osd set-nearfull-ratio', 'ratio': ratio}
+        return run_ceph_command(self.rados_config_file, cmd, inbuf='',
+                                target=('mon', ''))
+
+
+class MonitorCommand:
+    def __init__(self, rados_config_file):
+        self.rados_config_file = rados_config_file
+
+    def fsid(self):
+        """
+        show cluster FSID/UUID
+
+        :return: (string outbuf, string outs)
+        :raise CephError: Raises CephError on command execution errors
+        :raise rados.Error: Raises on rados errors
+        """
+
+        cmd = {'prefix': 'fsid'}
+        return run_ceph_command(self.rados_config_file, cmd, inbuf='',
+                                target=('mon', ''))
+
+    def health(self, detail=None):
+        """
+        show cluster health
+
+        :param detail: list valid_range=["detail"] allowed repeats=one
+        :return: (string outbuf, string outs)
+        :raise CephError: Raises CephError on command execution errors
+        :raise rados.Error: Raises on rados errors
+        """
+
+        cmd = {'prefix': 'health'}
+
+        if detail is not None:
+            detail_validator = ceph_argparse.CephChoices(strings="detail")
+            detail_validator.valid(detail)
+            cmd['detail'] = detail
+        return run_ceph_command(self.rados_config_file, cmd, inbuf='',
+                                target=('mon', ''))
+
+    def mon_status(self):
+        """
+        report status of monitors
+
+        :return: (string outbuf, string outs)
+        :raise CephError: Raises CephError on command execution errors
+        :raise rados.Error: Raises on rados errors
+        """
+
+        cmd = {'prefix': 'mon_status'}
+        return run_ceph_command(self.rados_config_file, cmd, inbuf='',
+                                target=('mon', ''))
+
+    def features(self):
+        """
+        report of connected features
+
+        :return: (string outbuf, string outs)
+        :raise CephError: Raises CephError on command execution errors
+        :raise rados.Error: Raises on rados errors
+        """
+
+        cmd = {'prefix': 'features'}
+        return run_ceph_command(self.rados_config_file, cmd, inbuf='',
+                                target=('mon', ''))
+
+    def mon_feature_set(self, feature_name, sure=None):
+        """
+        set provided feature on mon map
+
+        :param feature_name: six.string_types allowed repeats=one
+        :param sure: six.string_types allowed repeats=one
+        :return: (string outbuf, string outs)
+        :raise CephError: Raises CephError on command execution errors
+        :raise rados.Error: Raises on rados errors
+        """
+
+        feature_name_validator = ceph_argparse.CephString(goodchars="")
+        feature_name_validator.valid(feature_name)
+        cmd = {'prefix': 'mon feature set', 'feature_name': feature_name}
+
+        if sure is not None:
+            sure_validator = ceph_argparse.CephString(goodchars="")
+            sure_validator.valid(sure)
+            cmd['sure'] = sure
+        return run_ceph_command(self.rados_config_file, cmd, inbuf='',
+                                target=('mon', ''))
+
+
+class ManagerCommand:
+    def __init__(self, rados_config_file):
+        self.rados_config_file = rados_config_file
+
+    def mgr_dump(self, epoch=None):
+        """
+        dump the latest MgrMap
+
+        :param epoch: int min=0
+        :return: (string outbuf, string outs)
+        :raise CephError: Raises CephError on command execution errors
+        :raise rados.Error: Raises on rados errors
+        """
+
+        cmd = {'prefix': 'mgr dump'}
+
+        if epoch is not None:
+            epoch_validator = ceph_argparse.CephInt(range='0')
+            epoch_validator.valid(epoch)
+            cmd['epoch'] = epoch
+        return run_ceph_command(self.rados_config_file, cmd, inbuf='',
+                                target=('mon', ''))
+
+    def mgr_fail(self, who):
+        """
+        treat the named manager daemon as failed
+
+        :param who: six.string_types allowed repeats=one
+        :return: (string outbuf, string outs)
+        :raise CephError: Raises CephError on command execution errors
+        :raise rados.Error: Raises on rados errors
+        """
+
+        who_validator = ceph_argparse.CephString(goodchars="")
+        who_validator.valid(who)
+        cmd = {'prefix': 'mgr fail', 'who': who}
+        return run_ceph_command(self.rados_config_file, cmd, inbuf='',
+                                target=('mon', ''))
+
+    def mgr_module_enable(self, module, force=None):
+        """
+        enable mgr module
+
+        :param module: six.string_types allowed repeats=one
+        :param force: list valid_range=["--force"] allowed repeats=one
+        :return: (string outbuf, string outs)
+        :raise CephError: Raises CephError on command execution errors
+        :raise rados.Error: Raises on rados errors
+        """
+
+        module_validator = ceph_argparse.CephString(goodchars="")
+        module_validator.valid(module)
+        cmd = {'prefix': 'mgr module enable', 'module': module}
+
+        if force is not None:
+            force_validator = ceph_argparse.CephChoices(strings="--force")
+            force_validator.valid(force)
+            cmd['force'] = force
+        return run_ceph_command(self.rados_config_file, cmd, inbuf='',
+                                target=('mon', ''))
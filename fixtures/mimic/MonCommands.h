@@ -0,0 +1,77 @@
+/*
+ * Excerpt of src/mon/MonCommands.h from Ceph mimic (v13.2).
+ */
+
+/*
+ * PG commands PGMonitor.cc
+ */
+
+// note: this should be replaced shortly!
+COMMAND_WITH_FLAG("pg force_create_pg name=pgid,type=CephPgid", \
+		  "force creation of pg <pgid>", "pg", "rw", "cli,rest", FLAG(DEPRECATED))
+COMMAND_WITH_FLAG("pg set_full_ratio name=ratio,type=CephFloat,range=0.0|1.0", \
+		  "set ratio at which pgs are considered full", \
+		  "pg", "rw", "cli,rest", FLAG(DEPRECATED))
+
+/*
+ * auth commands AuthMonitor.cc
+ */
+
+COMMAND("auth export name=entity,type=CephString,req=false", \
+       	"write keyring for requested entity, or master keyring if none given", \
+	"auth", "rx", "cli,rest")
+COMMAND("auth get-or-create-key " \
+	"name=entity,type=CephString " \
+	"name=caps,type=CephString,n=N,req=false", \
+	"get, or add, key for <name> from system/caps pairs specified in the command.  If key already exists, any given caps must match the existing caps for that key.", \
+	"auth", "rwx", "cli,rest")
+
+/*
+ * Monitor commands (Monitor.cc)
+ */
+COMMAND_WITH_FLAG("compact", "cause compaction of monitor's leveldb/rocksdb storage", \
+	     "mon", "rw", "cli,rest", \
+             FLAG(NOFORWARD)|FLAG(DEPRECATED))
+COMMAND("time-sync-status", "show time sync status", "mon", "r", "cli,rest")
+COMMAND("versions", \
+	"check running versions of ceph daemons", \
+	"mon", "r", "cli,rest")
+
+/*
+ * OSD commands
+ */
+COMMAND("osd crush rule create-replicated " \
+	"name=name,type=CephString,goodchars=[A-Za-z0-9-_.] " \
+	"name=root,type=CephString,goodchars=[A-Za-z0-9-_.] " \
+	"name=type,type=CephString,goodchars=[A-Za-z0-9-_.] " \
+	"name=class,type=CephString,goodchars=[A-Za-z0-9-_.],req=false",
+	"create crush rule <name> for replicated pool to start from <root>, replicate across buckets of type <type>, using a choose mode of <firstn|indep> (default firstn; indep best for erasure pools)", \
+	"osd", "rw", "cli,rest")
+COMMAND("osd pool create " \
+	"name=pool,type=CephPoolname " \
+	"name=pg_num,type=CephInt,range=0 " \
+	"name=pgp_num,type=CephInt,range=0,req=false " \
+        "name=pool_type,type=CephChoices,strings=replicated|erasure,req=false " \
+	"name=erasure_code_profile,type=CephString,req=false,goodchars=[A-Za-z0-9-_.] " \
+	"name=rule,type=CephString,req=false " \
+        "name=expected_num_objects,type=CephInt,req=false", \
+	"create pool", "osd", "rw", "cli,rest")
+COMMAND("osd pool get-quota " \
+        "name=pool,type=CephPoolname ",
+        "obtain object or byte limits for pool",
+        "osd", "r", "cli,rest")
+
+/*
+ * config commands
+ */
+COMMAND("config set " \
+	"name=who,type=CephString " \
+	"name=name,type=CephString " \
+	"name=value,type=CephString",
+	"Set a configuration option for one or more entities",
+	"config", "rw", "cli,rest")
+COMMAND("config get " \
+	"name=who,type=CephString " \
+	"name=key,type=CephString,req=False",
+	"Show configuration option(s) for an entity",
+	"config", "r", "cli,rest")
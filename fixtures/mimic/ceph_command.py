@@ -0,0 +1,297 @@
+import json
+import os
+
+import ceph_argparse
+import rados
+import six
+
+
+class CephError(Exception):
+    """Exception raised for errors with running a Ceph command
+
+        :param cmd: cmd in which the error occurred
+        :param msg: explanation of the error
+    """
+
+    def __init__(self, cmd, msg):
+        self.cmd = cmd
+        self.msg = msg
+
+
+def run_ceph_command(conffile, cmd, inbuf, target=('mon', '')):
+    """Run a ceph command and return the results
+
+    :param conffile: The ceph.conf configuration location
+    :param cmd: The json command to run
+    :param inbuf:
+    :param target: (daemon type, daemon id) to send the command to.
+        daemon type is one of mon, mgr, osd or mds
+    :return: (string outbuf, string outs)
+    :raise rados.Error: Raises on rados errors
+    """
+    cluster = rados.Rados(conffile=conffile)
+    try:
+        cluster.connect()
+        if target[0] == 'mgr':
+            result = cluster.mgr_command(json.dumps(cmd), inbuf=inbuf)
+        elif target[0] == 'osd':
+            result = cluster.osd_command(int(target[1]), json.dumps(cmd),
+                                         inbuf=inbuf)
+        elif target[0] == 'mds':
+            import cephfs
+            fs = cephfs.LibCephFS(rados_inst=cluster)
+            fs.init()
+            result = fs.mds_command(str(target[1]), [json.dumps(cmd)], inbuf)
+        else:
+            result = cluster.mon_command(json.dumps(cmd), inbuf=inbuf)
+        if result[0] != 0:
+            raise CephError(cmd=cmd, msg=os.strerror(abs(result[0])))
+        return result[1], result[2]
+    except rados.Error as e:
+        raise e
+
+
+class PlacementGroupCommand:
+    def __init__(self, rados_config_file):
+        self.rados_config_file = rados_config_file
+
+    def pg_force_create_pg(self, pgid):
+        """
+        force creation of pg <pgid>
+
+        :param pgid: six.string_types
+        :return: (string outbuf, string outs)
+        :raise CephError: Raises CephError on command execution errors
+        :raise rados.Error: Raises on rados errors
+        """
+
+        pgid_validator = ceph_argparse.CephPgid()
+        pgid_validator.valid(pgid)
+        cmd = {'prefix': 'pg force_create_pg', 'pgid': pgid}
+        return run_ceph_command(self.rados_config_file, cmd, inbuf='',
+                                target=('mon', ''))
+
+    def pg_set_full_ratio(self, ratio):
+        """
+        set ratio at which pgs are considered full
+
+        :param ratio: float min=0 max=1
+        :return: (string outbuf, string outs)
+        :raise CephError: Raises CephError on command execution errors
+        :raise rados.Error: Raises on rados errors
+        """
+
+        ratio_validator = ceph_argparse.CephFloat(range='0|1')
+        ratio_validator.valid(ratio)
+        cmd = {'prefix': 'pg set_full_ratio', 'ratio': ratio}
+        return run_ceph_command(self.rados_config_file, cmd, inbuf='',
+                                target=('mon', ''))
+
+
+class OsdCommand:
+    def __init__(self, rados_config_file):
+        self.rados_config_file = rados_config_file
+
+    def osd_crush_rule_create_replicated(self, name, root, type, class_=None):
+        """
+        create crush rule <name> for replicated pool to start from <root>,
+        replicate across buckets of type <type>, using a choose mode of
+        <firstn|indep> (default firstn; indep best for erasure pools)
+
+        :param name: six.string_types valid_characters=[A-Za-z0-9-_.] allowed
+            repeats=one
+        :param root: six.string_types valid_characters=[A-Za-z0-9-_.] allowed
+            repeats=one
+        :param type: six.string_types valid_characters=[A-Za-z0-9-_.] allowed
+            repeats=one
+        :param class_: six.string_types valid_characters=[A-Za-z0-9-_.] allowed
+            repeats=one
+        :return: (string outbuf, string outs)
+        :raise CephError: Raises CephError on command execution errors
+        :raise rados.Error: Raises on rados errors
+        """
+
+        name_validator = ceph_argparse.CephString(goodchars="[A-Za-z0-9-_.]")
+        name_validator.valid(name)
+        root_validator = ceph_argparse.CephString(goodchars="[A-Za-z0-9-_.]")
+        root_validator.valid(root)
+        type_validator = ceph_argparse.CephString(goodchars="[A-Za-z0-9-_.]")
+        type_validator.valid(type)
+        cmd = {
+            'prefix': 'osd crush rule create-replicated',
+            'name': name,
+            'root': root,
+            'type': type,
+        }
+
+        if class_ is not None:
+            class__validator = ceph_argparse.CephString(
+                goodchars="[A-Za-z0-9-_.]")
+            class__validator.valid(class_)
+            cmd['class'] = class_
+        return run_ceph_command(self.rados_config_file, cmd, inbuf='',
+                                target=('mon', ''))
+
+    def osd_pool_create(self, pool, pg_num, pgp_num=None, pool_type=None,
+                        erasure_code_profile=None, rule=None,
+                        expected_num_objects=None):
+        """
+        create pool
+
+        :param pool: six.string_types
+        :param pg_num: int min=0
+        :param pgp_num: int min=0
+        :param pool_type: list valid_range=["replicated", "erasure"] allowed
+            repeats=one
+        :param erasure_code_profile: six.string_types
+            valid_characters=[A-Za-z0-9-_.] allowed repeats=one
+        :param rule: six.string_types allowed repeats=one
+        :param expected_num_objects: int
+        :return: (string outbuf, string outs)
+        :raise CephError: Raises CephError on command execution errors
+        :raise rados.Error: Raises on rados errors
+        """
+
+        if not isinstance(pool, six.string_types):
+            raise TypeError("pool is not a String")
+        pg_num_validator = ceph_argparse.CephInt(range='0')
+        pg_num_validator.valid(pg_num)
+        cmd = {'prefix': 'osd pool create', 'pool': pool, 'pg_num': pg_num}
+
+        if pgp_num is not None:
+            pgp_num_validator = ceph_argparse.CephInt(range='0')
+            pgp_num_validator.valid(pgp_num)
+            cmd['pgp_num'] = pgp_num
+
+        if pool_type is not None:
+            pool_type_validator = ceph_argparse.CephChoices(
+                strings="replicated|erasure")
+            pool_type_validator.valid(pool_type)
+            cmd['pool_type'] = pool_type
+
+        if erasure_code_profile is not None:
+            erasure_code_profile_validator = ceph_argparse.CephString(
+                goodchars="[A-Za-z0-9-_.]")
+            erasure_code_profile_validator.valid(erasure_code_profile)
+            cmd['erasure_code_profile'] = erasure_code_profile
+
+        if rule is not None:
+            rule_validator = ceph_argparse.CephString(goodchars="")
+            rule_validator.valid(rule)
+            cmd['rule'] = rule
+
+        if expected_num_objects is not None:
+            expected_num_objects_validator = ceph_argparse.CephInt(range='')
+            expected_num_objects_validator.valid(expected_num_objects)
+            cmd['expected_num_objects'] = expected_num_objects
+        return run_ceph_command(self.rados_config_file, cmd, inbuf='',
+                                target=('mon', ''))
+
+    def osd_pool_get_quota(self, pool):
+        """
+        obtain object or byte limits for pool
+
+        :param pool: six.string_types
+        :return: (string outbuf, string outs)
+        :raise CephError: Raises CephError on command execution errors
+        :raise rados.Error: Raises on rados errors
+        """
+
+        if not isinstance(pool, six.string_types):
+            raise TypeError("pool is not a String")
+        cmd = {'prefix': 'osd pool get-quota', 'pool': pool}
+        return run_ceph_command(self.rados_config_file, cmd, inbuf='',
+                                target=('mon', ''))
+
+
+class MonitorCommand:
+    def __init__(self, rados_config_file):
+        self.rados_config_file = rados_config_file
+
+    def compact(self):
+        """
+        cause compaction of monitor's leveldb/rocksdb storage
+
+        :return: (string outbuf, string outs)
+        :raise CephError: Raises CephError on command execution errors
+        :raise rados.Error: Raises on rados errors
+        """
+
+        cmd = {'prefix': 'compact'}
+        return run_ceph_command(self.rados_config_file, cmd, inbuf='',
+                                target=('mon', ''))
+
+    def time_sync_status(self):
+        """
+        show time sync status
+
+        :return: (string outbuf, string outs)
+        :raise CephError: Raises CephError on command execution errors
+        :raise rados.Error: Raises on rados errors
+        """
+
+        cmd = {'prefix': 'time-sync-status'}
+        return run_ceph_command(self.rados_config_file, cmd, inbuf='',
+                                target=('mon', ''))
+
+    def versions(self):
+        """
+        check running versions of ceph daemons
+
+        :return: (string outbuf, string outs)
+        :raise CephError: Raises CephError on command execution errors
+        :raise rados.Error: Raises on rados errors
+        """
+
+        cmd = {'prefix': 'versions'}
+        return run_ceph_command(self.rados_config_file, cmd, inbuf='',
+                                target=('mon', ''))
+
+
+class AuthCommand:
+    def __init__(self, rados_config_file):
+        self.rados_config_file = rados_config_file
+
+    def auth_export(self, entity=None):
+        """
+        write keyring for requested entity, or master keyring if none given
+
+        :param entity: six.string_types allowed repeats=one
+        :return: (string outbuf, string outs)
+        :raise CephError: Raises CephError on command execution errors
+        :raise rados.Error: Raises on rados errors
+        """
+
+        cmd = {'prefix': 'auth export'}
+
+        if entity is not None:
+            entity_validator = ceph_argparse.CephString(goodchars="")
+            entity_validator.valid(entity)
+            cmd['entity'] = entity
+        return run_ceph_command(self.rados_config_file, cmd, inbuf='',
+                                target=('mon', ''))
+
+    def auth_get_or_create_key(self, entity, caps=None):
+        """
+        get, or add, key for <name> from system/caps pairs specified in the
+        command. If key already exists, any given caps must match the existing
+        caps for that key.
+
+        :param entity: six.string_types allowed repeats=one
+        :param caps: six.string_types allowed repeats=many
+        :return: (string outbuf, string outs)
+        :raise CephError: Raises CephError on command execution errors
+        :raise rados.Error: Raises on rados errors
+        """
+
+        entity_validator = ceph_argparse.CephString(goodchars="")
+        entity_validator.valid(entity)
+        cmd = {'prefix': 'auth get-or-create-key', 'entity': entity}
+
+        if caps is not None:
+            caps_validator = ceph_argparse.CephString(goodchars="")
+            for s in caps:
+                caps_validator.valid(s)
+            cmd['caps'] = caps
+        return run_ceph_command(self.rados_config_file, cmd, inbuf='',
+                                target=('mon', ''))
@@ -0,0 +1,64 @@
+/*
+ * Excerpt of src/mon/MonCommands.h from Ceph nautilus (v14.2).  The
+ * availability column is gone from this release on.
+ */
+
+/*
+ * PG commands PGMonitor.cc
+ */
+
+// note: this should be replaced shortly!
+COMMAND_WITH_FLAG("pg force_create_pg name=pgid,type=CephPgid", \
+		  "force creation of pg <pgid>", "pg", "rw", FLAG(DEPRECATED))
+COMMAND_WITH_FLAG("pg set_full_ratio name=ratio,type=CephFloat,range=0.0|1.0", \
+		  "set ratio at which pgs are considered full", \
+		  "pg", "rw", FLAG(DEPRECATED))
+
+/*
+ * Monitor commands (Monitor.cc)
+ */
+COMMAND_WITH_FLAG("fsid", "show cluster FSID/UUID", "mon", "r",
+             FLAG(NOFORWARD))
+COMMAND("health name=detail,type=CephChoices,strings=detail,req=false",
+	"show cluster health", "mon", "r")
+COMMAND("health mute "\
+	"name=code,type=CephString " \
+	"name=ttl,type=CephString,req=false "\
+	"name=sticky,type=CephBool,req=false",
+	"mute health alert", "mon", "w")
+COMMAND("mon enable-msgr2",
+	"enable the msgr2 protocol on port 3300",
+	"mon", "rw")
+
+/*
+ * OSD commands
+ */
+COMMAND("osd pool create " \
+	"name=pool,type=CephPoolname " \
+	"name=pg_num,type=CephInt,range=0,req=false " \
+	"name=pgp_num,type=CephInt,range=0,req=false " \
+        "name=pool_type,type=CephChoices,strings=replicated|erasure,req=false " \
+	"name=erasure_code_profile,type=CephString,req=false,goodchars=[A-Za-z0-9-_.] " \
+	"name=rule,type=CephString,req=false " \
+        "name=expected_num_objects,type=CephInt,range=0,req=false " \
+        "name=size,type=CephInt,range=0,req=false " \
+	"name=pg_num_min,type=CephInt,range=0,req=false " \
+	"name=target_size_bytes,type=CephInt,range=0,req=false " \
+	"name=target_size_ratio,type=CephFloat,range=0|1,req=false",\
+	"create pool", "osd", "rw")
+COMMAND("osd pool rm " \
+	"name=pool,type=CephPoolname " \
+	"name=pool2,type=CephPoolname,req=false " \
+	"name=yes_i_really_really_mean_it,type=CephBool,req=false " \
+	"name=yes_i_really_really_mean_it_not_faking,type=CephBool,req=false ", \
+	"remove pool", \
+	"osd", "rw")
+COMMAND("osd safe-to-destroy name=ids,type=CephString,n=N",
+	"check whether osd(s) can be safely destroyed without reducing data durability",
+	"osd", "r")
+COMMAND("osd purge " \
+        "name=id,type=CephOsdName " \
+        "name=force,type=CephBool,req=false " \
+        "name=yes_i_really_mean_it,type=CephBool,req=false",
+        "purge all osd data from the monitors including the OSD id and CRUSH position",
+	"osd", "rw")
@@ -0,0 +1,338 @@
+import json
+import os
+
+import ceph_argparse
+import rados
+import six
+
+
+class CephError(Exception):
+    """Exception raised for errors with running a Ceph command
+
+        :param cmd: cmd in which the error occurred
+        :param msg: explanation of the error
+    """
+
+    def __init__(self, cmd, msg):
+        self.cmd = cmd
+        self.msg = msg
+
+
+def run_ceph_command(conffile, cmd, inbuf, target=('mon', '')):
+    """Run a ceph command and return the results
+
+    :param conffile: The ceph.conf configuration location
+    :param cmd: The json command to run
+    :param inbuf:
+    :param target: (daemon type, daemon id) to send the command to.
+        daemon type is one of mon, mgr, osd or mds
+    :return: (string outbuf, string outs)
+    :raise rados.Error: Raises on rados errors
+    """
+    cluster = rados.Rados(conffile=conffile)
+    try:
+        cluster.connect()
+        if target[0] == 'mgr':
+            result = cluster.mgr_command(json.dumps(cmd), inbuf=inbuf)
+        elif target[0] == 'osd':
+            result = cluster.osd_command(int(target[1]), json.dumps(cmd),
+                                         inbuf=inbuf)
+        elif target[0] == 'mds':
+            import cephfs
+            fs = cephfs.LibCephFS(rados_inst=cluster)
+            fs.init()
+            result = fs.mds_command(str(target[1]), [json.dumps(cmd)], inbuf)
+        else:
+            result = cluster.mon_command(json.dumps(cmd), inbuf=inbuf)
+        if result[0] != 0:
+            raise CephError(cmd=cmd, msg=os.strerror(abs(result[0])))
+        return result[1], result[2]
+    except rados.Error as e:
+        raise e
+
+
+class PlacementGroupCommand:
+    def __init__(self, rados_config_file):
+        self.rados_config_file = rados_config_file
+
+    def pg_force_create_pg(self, pgid):
+        """
+        force creation of pg <pgid>
+
+        :param pgid: six.string_types
+        :return: (string outbuf, string outs)
+        :raise CephError: Raises CephError on command execution errors
+        :raise rados.Error: Raises on rados errors
+        """
+
+        pgid_validator = ceph_argparse.CephPgid()
+        pgid_validator.valid(pgid)
+        cmd = {'prefix': 'pg force_create_pg', 'pgid': pgid}
+        return run_ceph_command(self.rados_config_file, cmd, inbuf='',
+                                target=('mon', ''))
+
+    def pg_set_full_ratio(self, ratio):
+        """
+        set ratio at which pgs are considered full
+
+        :param ratio: float min=0 max=1
+        :return: (string outbuf, string outs)
+        :raise CephError: Raises CephError on command execution errors
+        :raise rados.Error: Raises on rados errors
+        """
+
+        ratio_validator = ceph_argparse.CephFloat(range='0|1')
+        ratio_validator.valid(ratio)
+        cmd = {'prefix': 'pg set_full_ratio', 'ratio': ratio}
+        return run_ceph_command(self.rados_config_file, cmd, inbuf='',
+                                target=('mon', ''))
+
+
+class OsdCommand:
+    def __init__(self, rados_config_file):
+        self.rados_config_file = rados_config_file
+
+    def osd_pool_create(self, pool, pg_num=None, pgp_num=None, pool_type=None,
+                        erasure_code_profile=None, rule=None,
+                        expected_num_objects=None, size=None, pg_num_min=None,
+                        target_size_bytes=None, target_size_ratio=None):
+        """
+        create pool
+
+        :param pool: six.string_types
+        :param pg_num: int min=0
+        :param pgp_num: int min=0
+        :param pool_type: list valid_range=["replicated", "erasure"] allowed
+            repeats=one
+        :param erasure_code_profile: six.string_types
+            valid_characters=[A-Za-z0-9-_.] allowed repeats=one
+        :param rule: six.string_types allowed repeats=one
+        :param expected_num_objects: int min=0
+        :param size: int min=0
+        :param pg_num_min: int min=0
+        :param target_size_bytes: int min=0
+        :param target_size_ratio: float min=0 max=1
+        :return: (string outbuf, string outs)
+        :raise CephError: Raises CephError on command execution errors
+        :raise rados.Error: Raises on rados errors
+        """
+
+        if not isinstance(pool, six.string_types):
+            raise TypeError("pool is not a String")
+        cmd = {'prefix': 'osd pool create', 'pool': pool}
+
+        if pg_num is not None:
+            pg_num_validator = ceph_argparse.CephInt(range='0')
+            pg_num_validator.valid(pg_num)
+            cmd['pg_num'] = pg_num
+
+        if pgp_num is not None:
+            pgp_num_validator = ceph_argparse.CephInt(range='0')
+            pgp_num_validator.valid(pgp_num)
+            cmd['pgp_num'] = pgp_num
+
+        if pool_type is not None:
+            pool_type_validator = ceph_argparse.CephChoices(
+                strings="replicated|erasure")
+            pool_type_validator.valid(pool_type)
+            cmd['pool_type'] = pool_type
+
+        if erasure_code_profile is not None:
+            erasure_code_profile_validator = ceph_argparse.CephString(
+                goodchars="[A-Za-z0-9-_.]")
+            erasure_code_profile_validator.valid(erasure_code_profile)
+            cmd['erasure_code_profile'] = erasure_code_profile
+
+        if rule is not None:
+            rule_validator = ceph_argparse.CephString(goodchars="")
+            rule_validator.valid(rule)
+            cmd['rule'] = rule
+
+        if expected_num_objects is not None:
+            expected_num_objects_validator = ceph_argparse.CephInt(range='0')
+            expected_num_objects_validator.valid(expected_num_objects)
+            cmd['expected_num_objects'] = expected_num_objects
+
+        if size is not None:
+            size_validator = ceph_argparse.CephInt(range='0')
+            size_validator.valid(size)
+            cmd['size'] = size
+
+        if pg_num_min is not None:
+            pg_num_min_validator = ceph_argparse.CephInt(range='0')
+            pg_num_min_validator.valid(pg_num_min)
+            cmd['pg_num_min'] = pg_num_min
+
+        if target_size_bytes is not None:
+            target_size_bytes_validator = ceph_argparse.CephInt(range='0')
+            target_size_bytes_validator.valid(target_size_bytes)
+            cmd['target_size_bytes'] = target_size_bytes
+
+        if target_size_ratio is not None:
+            target_size_ratio_validator = ceph_argparse.CephFloat(range='0|1')
+            target_size_ratio_validator.valid(target_size_ratio)
+            cmd['target_size_ratio'] = target_size_ratio
+        return run_ceph_command(self.rados_config_file, cmd, inbuf='',
+                                target=('mon', ''))
+
+    def osd_pool_rm(self, pool, pool2=None, yes_i_really_really_mean_it=None,
+                    yes_i_really_really_mean_it_not_faking=None):
+        """
+        remove pool
+
+        :param pool: six.string_types
+        :param pool2: six.string_types
+        :param yes_i_really_really_mean_it: bool
+        :param yes_i_really_really_mean_it_not_faking: bool
+        :return: (string outbuf, string outs)
+        :raise CephError: Raises CephError on command execution errors
+        :raise rados.Error: Raises on rados errors
+        """
+
+        if not isinstance(pool, six.string_types):
+            raise TypeError("pool is not a String")
+        cmd = {'prefix': 'osd pool rm', 'pool': pool}
+
+        if pool2 is not None:
+            if not isinstance(pool2, six.string_types):
+                raise TypeError("pool2 is not a String")
+            cmd['pool2'] = pool2
+
+        if yes_i_really_really_mean_it is not None:
+            if not isinstance(yes_i_really_really_mean_it, bool):
+                raise TypeError("yes_i_really_really_mean_it is not a bool")
+            cmd['yes_i_really_really_mean_it'] = yes_i_really_really_mean_it
+
+        if yes_i_really_really_mean_it_not_faking is not None:
+            if not isinstance(yes_i_really_really_mean_it_not_faking, bool):
+                raise TypeError(
+                    "yes_i_really_really_mean_it_not_faking is not a bool")
+            cmd['yes_i_really_really_mean_it_not_faking'] = (
+                yes_i_really_really_mean_it_not_faking)
+        return run_ceph_command(self.rados_config_file, cmd, inbuf='',
+                                target=('mon', ''))
+
+    def osd_safe_to_destroy(self, ids):
+        """
+        check whether osd(s) can be safely destroyed without reducing data
+        durability
+
+        :param ids: six.string_types allowed repeats=many
+        :return: (string outbuf, string outs)
+        :raise CephError: Raises CephError on command execution errors
+        :raise rados.Error: Raises on rados errors
+        """
+
+        ids_validator = ceph_argparse.CephString(goodchars="")
+        for s in ids:
+            ids_validator.valid(s)
+        cmd = {'prefix': 'osd safe-to-destroy', 'ids': ids}
+        return run_ceph_command(self.rados_config_file, cmd, inbuf='',
+                                target=('mon', ''))
+
+    def osd_purge(self, id, force=None, yes_i_really_mean_it=None):
+        """
+        purge all osd data from the monitors including the OSD id and CRUSH
+        position
+
+        :param id: six.string_types
+        :param force: bool
+        :param yes_i_really_mean_it: bool
+        :return: (string outbuf, string outs)
+        :raise CephError: Raises CephError on command execution errors
+        :raise rados.Error: Raises on rados errors
+        """
+
+        id_validator = ceph_argparse.CephOsdName()
+        id_validator.valid(id)
+        cmd = {'prefix': 'osd purge', 'id': id}
+
+        if force is not None:
+            if not isinstance(force, bool):
+                raise TypeError("force is not a bool")
+            cmd['force'] = force
+
+        if yes_i_really_mean_it is not None:
+            if not isinstance(yes_i_really_mean_it, bool):
+                raise TypeError("yes_i_really_mean_it is not a bool")
+            cmd['yes_i_really_mean_it'] = yes_i_really_mean_it
+        return run_ceph_command(self.rados_config_file, cmd, inbuf='',
+                                target=('mon', ''))
+
+
+class MonitorCommand:
+    def __init__(self, rados_config_file):
+        self.rados_config_file = rados_config_file
+
+    def fsid(self):
+        """
+        show cluster FSID/UUID
+
+        :return: (string outbuf, string outs)
+        :raise CephError: Raises CephError on command execution errors
+        :raise rados.Error: Raises on rados errors
+        """
+
+        cmd = {'prefix': 'fsid'}
+        return run_ceph_command(self.rados_config_file, cmd, inbuf='',
+                                target=('mon', ''))
+
+    def health(self, detail=None):
+        """
+        show cluster health
+
+        :param detail: list valid_range=["detail"] allowed repeats=one
+        :return: (string outbuf, string outs)
+        :raise CephError: Raises CephError on command execution errors
+        :raise rados.Error: Raises on rados errors
+        """
+
+        cmd = {'prefix': 'health'}
+
+        if detail is not None:
+            detail_validator = ceph_argparse.CephChoices(strings="detail")
+            detail_validator.valid(detail)
+            cmd['detail'] = detail
+        return run_ceph_command(self.rados_config_file, cmd, inbuf='',
+                                target=('mon', ''))
+
+    def health_mute(self, code, ttl=None, sticky=None):
+        """
+        mute health alert
+
+        :param code: six.string_types allowed repeats=one
+        :param ttl: six.string_types allowed repeats=one
+        :param sticky: bool
+        :return: (string outbuf, string outs)
+        :raise CephError: Raises CephError on command execution errors
+        :raise rados.Error: Raises on rados errors
+        """
+
+        code_validator = ceph_argparse.CephString(goodchars="")
+        code_validator.valid(code)
+        cmd = {'prefix': 'health mute', 'code': code}
+
+        if ttl is not None:
+            ttl_validator = ceph_argparse.CephString(goodchars="")
+            ttl_validator.valid(ttl)
+            cmd['ttl'] = ttl
+
+        if sticky is not None:
+            if not isinstance(sticky, bool):
+                raise TypeError("sticky is not a bool")
+            cmd['sticky'] = sticky
+        return run_ceph_command(self.rados_config_file, cmd, inbuf='',
+                                target=('mon', ''))
+
+    def mon_enable_msgr2(self):
+        """
+        enable the msgr2 protocol on port 3300
+
+        :return: (string outbuf, string outs)
+        :raise CephError: Raises CephError on command execution errors
+        :raise rados.Error: Raises on rados errors
+        """
+
+        cmd = {'prefix': 'mon enable-msgr2'}
+        return run_ceph_command(self.rados_config_file, cmd, inbuf='',
+                                target=('mon', ''))
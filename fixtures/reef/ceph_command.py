@@ -0,0 +1,390 @@
+import json
+import os
+
+import ceph_argparse
+import rados
+import six
+
+
+class CephError(Exception):
+    """Exception raised for errors with running a Ceph command
+
+        :param cmd: cmd in which the error occurred
+        :param msg: explanation of the error
+    """
+
+    def __init__(self, cmd, msg):
+        self.cmd = cmd
+        self.msg = msg
+
+
+def run_ceph_command(conffile, cmd, inbuf, target=('mon', '')):
+    """Run a ceph command and return the results
+
+    :param conffile: The ceph.conf configuration location
+    :param cmd: The json command to run
+    :param inbuf:
+    :param target: (daemon type, daemon id) to send the command to.
+        daemon type is one of mon, mgr, osd or mds
+    :return: (string outbuf, string outs)
+    :raise rados.Error: Raises on rados errors
+    """
+    cluster = rados.Rados(conffile=conffile)
+    try:
+        cluster.connect()
+        if target[0] == 'mgr':
+            result = cluster.mgr_command(json.dumps(cmd), inbuf=inbuf)
+        elif target[0] == 'osd':
+            result = cluster.osd_command(int(target[1]), json.dumps(cmd),
+                                         inbuf=inbuf)
+        elif target[0] == 'mds':
+            import cephfs
+            fs = cephfs.LibCephFS(rados_inst=cluster)
+            fs.init()
+            result = fs.mds_command(str(target[1]), [json.dumps(cmd)], inbuf)
+        else:
+            result = cluster.mon_command(json.dumps(cmd), inbuf=inbuf)
+        if result[0] != 0:
+            raise CephError(cmd=cmd, msg=os.strerror(abs(result[0])))
+        return result[1], result[2]
+    except rados.Error as e:
+        raise e
+
+
+class OsdCommand:
+    def __init__(self, rados_config_file):
+        self.rados_config_file = rados_config_file
+
+    def osd_blocklist(self, blocklistop, addr, expire=None):
+        """
+        add (optionally until <expire> seconds from now) or remove <addr> from
+        blocklist
+
+        :param blocklistop: list valid_range=["add", "rm"] allowed repeats=one
+        :param addr: CephIPAddr + optional '/nonce'
+        :param expire: float min=0
+        :return: (string outbuf, string outs)
+        :raise CephError: Raises CephError on command execution errors
+        :raise rados.Error: Raises on rados errors
+        """
+
+        blocklistop_validator = ceph_argparse.CephChoices(strings="add|rm")
+        blocklistop_validator.valid(blocklistop)
+        addr_validator = ceph_argparse.CephEntityAddr()
+        addr_validator.valid(addr)
+        cmd = {
+            'prefix': 'osd blocklist',
+            'blocklistop': blocklistop,
+            'addr': addr,
+        }
+
+        if expire is not None:
+            expire_validator = ceph_argparse.CephFloat(range='0')
+            expire_validator.valid(expire)
+            cmd['expire'] = expire
+        return run_ceph_command(self.rados_config_file, cmd, inbuf='',
+                                target=('mon', ''))
+
+    def osd_pool_create(self, pool, pg_num=None, pgp_num=None, pool_type=None,
+                        erasure_code_profile=None, rule=None,
+                        expected_num_objects=None, size=None, pg_num_min=None,
+                        pg_num_max=None, autoscale_mode=None, bulk=None,
+                        target_size_bytes=None, target_size_ratio=None,
+                        yes_i_really_mean_it=None):
+        """
+        create pool
+
+        :param pool: six.string_types
+        :param pg_num: int min=0
+        :param pgp_num: int min=0
+        :param pool_type: list valid_range=["replicated", "erasure"] allowed
+            repeats=one
+        :param erasure_code_profile: six.string_types
+            valid_characters=[A-Za-z0-9-_.] allowed repeats=one
+        :param rule: six.string_types allowed repeats=one
+        :param expected_num_objects: int min=0
+        :param size: int min=0
+        :param pg_num_min: int min=0
+        :param pg_num_max: int min=0
+        :param autoscale_mode: list valid_range=["on", "off", "warn"] allowed
+            repeats=one
+        :param bulk: bool
+        :param target_size_bytes: int min=0
+        :param target_size_ratio: float min=0
+        :param yes_i_really_mean_it: bool
+        :return: (string outbuf, string outs)
+        :raise CephError: Raises CephError on command execution errors
+        :raise rados.Error: Raises on rados errors
+        """
+
+        if not isinstance(pool, six.string_types):
+            raise TypeError("pool is not a String")
+        cmd = {'prefix': 'osd pool create', 'pool': pool}
+
+        if pg_num is not None:
+            pg_num_validator = ceph_argparse.CephInt(range='0')
+            pg_num_validator.valid(pg_num)
+            cmd['pg_num'] = pg_num
+
+        if pgp_num is not None:
+            pgp_num_validator = ceph_argparse.CephInt(range='0')
+            pgp_num_validator.valid(pgp_num)
+            cmd['pgp_num'] = pgp_num
+
+        if pool_type is not None:
+            pool_type_validator = ceph_argparse.CephChoices(
+                strings="replicated|erasure")
+            pool_type_validator.valid(pool_type)
+            cmd['pool_type'] = pool_type
+
+        if erasure_code_profile is not None:
+            erasure_code_profile_validator = ceph_argparse.CephString(
+                goodchars="[A-Za-z0-9-_.]")
+            erasure_code_profile_validator.valid(erasure_code_profile)
+            cmd['erasure_code_profile'] = erasure_code_profile
+
+        if rule is not None:
+            rule_validator = ceph_argparse.CephString(goodchars="")
+            rule_validator.valid(rule)
+            cmd['rule'] = rule
+
+        if expected_num_objects is not None:
+            expected_num_objects_validator = ceph_argparse.CephInt(range='0')
+            expected_num_objects_validator.valid(expected_num_objects)
+            cmd['expected_num_objects'] = expected_num_objects
+
+        if size is not None:
+            size_validator = ceph_argparse.CephInt(range='0')
+            size_validator.valid(size)
+            cmd['size'] = size
+
+        if pg_num_min is not None:
+            pg_num_min_validator = ceph_argparse.CephInt(range='0')
+            pg_num_min_validator.valid(pg_num_min)
+            cmd['pg_num_min'] = pg_num_min
+
+        if pg_num_max is not None:
+            pg_num_max_validator = ceph_argparse.CephInt(range='0')
+            pg_num_max_validator.valid(pg_num_max)
+            cmd['pg_num_max'] = pg_num_max
+
+        if autoscale_mode is not None:
+            autoscale_mode_validator = ceph_argparse.CephChoices(
+                strings="on|off|warn")
+            autoscale_mode_validator.valid(autoscale_mode)
+            cmd['autoscale_mode'] = autoscale_mode
+
+        if bulk is not None:
+            if not isinstance(bulk, bool):
+                raise TypeError("bulk is not a bool")
+            cmd['bulk'] = bulk
+
+        if target_size_bytes is not None:
+            target_size_bytes_validator = ceph_argparse.CephInt(range='0')
+            target_size_bytes_validator.valid(target_size_bytes)
+            cmd['target_size_bytes'] = target_size_bytes
+
+        if target_size_ratio is not None:
+            target_size_ratio_validator = ceph_argparse.CephFloat(range='0')
+            target_size_ratio_validator.valid(target_size_ratio)
+            cmd['target_size_ratio'] = target_size_ratio
+
+        if yes_i_really_mean_it is not None:
+            if not isinstance(yes_i_really_mean_it, bool):
+                raise TypeError("yes_i_really_mean_it is not a bool")
+            cmd['yes_i_really_mean_it'] = yes_i_really_mean_it
+        return run_ceph_command(self.rados_config_file, cmd, inbuf='',
+                                target=('mon', ''))
+
+    def osd_pool_stretch_set(self, pool, peering_crush_bucket_count,
+                             peering_crush_bucket_target,
+                             peering_crush_bucket_barrier, crush_rule, size,
+                             min_size, yes_i_really_mean_it=None):
+        """
+        make the pool stretched across the specified number of CRUSH buckets
+
+        :param pool: six.string_types
+        :param peering_crush_bucket_count: int min=0
+        :param peering_crush_bucket_target: int min=0
+        :param peering_crush_bucket_barrier: six.string_types allowed
+            repeats=one
+        :param crush_rule: six.string_types allowed repeats=one
+        :param size: int min=0
+        :param min_size: int min=0
+        :param yes_i_really_mean_it: bool
+        :return: (string outbuf, string outs)
+        :raise CephError: Raises CephError on command execution errors
+        :raise rados.Error: Raises on rados errors
+        """
+
+        if not isinstance(pool, six.string_types):
+            raise TypeError("pool is not a String")
+        peering_crush_bucket_count_validator = ceph_argparse.CephInt(range='0')
+        peering_crush_bucket_count_validator.valid(peering_crush_bucket_count)
+        peering_crush_bucket_target_validator = ceph_argparse.CephInt(
+            range='0')
+        peering_crush_bucket_target_validator.valid(
+            peering_crush_bucket_target)
+        peering_crush_bucket_barrier_validator = ceph_argparse.CephString(
+            goodchars="")
+        peering_crush_bucket_barrier_validator.valid(
+            peering_crush_bucket_barrier)
+        crush_rule_validator = ceph_argparse.CephString(goodchars="")
+        crush_rule_validator.valid(crush_rule)
+        size_validator = ceph_argparse.CephInt(range='0')
+        size_validator.valid(size)
+        min_size_validator = ceph_argparse.CephInt(range='0')
+        min_size_validator.valid(min_size)
+        cmd = {
+            'prefix': 'osd pool stretch set',
+            'pool': pool,
+            'peering_crush_bucket_count': peering_crush_bucket_count,
+            'peering_crush_bucket_target': peering_crush_bucket_target,
+            'peering_crush_bucket_barrier': peering_crush_bucket_barrier,
+            'crush_rule': crush_rule,
+            'size': size,
+            'min_size': min_size,
+        }
+
+        if yes_i_really_mean_it is not None:
+            if not isinstance(yes_i_really_mean_it, bool):
+                raise TypeError("yes_i_really_mean_it is not a bool")
+            cmd['yes_i_really_mean_it'] = yes_i_really_mean_it
+        return run_ceph_command(self.rados_config_file, cmd, inbuf='',
+                                target=('mon', ''))
+
+    def pg_repeer(self, pgid):
+        """
+        force a PG to repeer
+
+        :param pgid: six.string_types
+        :return: (string outbuf, string outs)
+        :raise CephError: Raises CephError on command execution errors
+        :raise rados.Error: Raises on rados errors
+        """
+
+        pgid_validator = ceph_argparse.CephPgid()
+        pgid_validator.valid(pgid)
+        cmd = {'prefix': 'pg repeer', 'pgid': pgid}
+        return run_ceph_command(self.rados_config_file, cmd, inbuf='',
+                                target=('mon', ''))
+
+
+class MonitorCommand:
+    def __init__(self, rados_config_file):
+        self.rados_config_file = rados_config_file
+
+    def fsid(self):
+        """
+        show cluster FSID/UUID
+
+        :return: (string outbuf, string outs)
+        :raise CephError: Raises CephError on command execution errors
+        :raise rados.Error: Raises on rados errors
+        """
+
+        cmd = {'prefix': 'fsid'}
+        return run_ceph_command(self.rados_config_file, cmd, inbuf='',
+                                target=('mon', ''))
+
+    def health(self, detail=None):
+        """
+        show cluster health
+
+        :param detail: list valid_range=["detail"] allowed repeats=one
+        :return: (string outbuf, string outs)
+        :raise CephError: Raises CephError on command execution errors
+        :raise rados.Error: Raises on rados errors
+        """
+
+        cmd = {'prefix': 'health'}
+
+        if detail is not None:
+            detail_validator = ceph_argparse.CephChoices(strings="detail")
+            detail_validator.valid(detail)
+            cmd['detail'] = detail
+        return run_ceph_command(self.rados_config_file, cmd, inbuf='',
+                                target=('mon', ''))
+
+    def mon_ok_to_stop(self, ids):
+        """
+        check whether mon(s) can be safely stopped without reducing immediate
+        availability
+
+        :param ids: six.string_types allowed repeats=many
+        :return: (string outbuf, string outs)
+        :raise CephError: Raises CephError on command execution errors
+        :raise rados.Error: Raises on rados errors
+        """
+
+        ids_validator = ceph_argparse.CephString(goodchars="")
+        for s in ids:
+            ids_validator.valid(s)
+        cmd = {'prefix': 'mon ok-to-stop', 'ids': ids}
+        return run_ceph_command(self.rados_config_file, cmd, inbuf='',
+                                target=('mon', ''))
+
+    def mon_set_location(self, name, args):
+        """
+        specify location <args> for the monitor <name>, using CRUSH bucket
+        names
+
+        :param name: six.string_types allowed repeats=one
+        :param args: six.string_types valid_characters=[A-Za-z0-9-_.=] allowed
+            repeats=many
+        :return: (string outbuf, string outs)
+        :raise CephError: Raises CephError on command execution errors
+        :raise rados.Error: Raises on rados errors
+        """
+
+        name_validator = ceph_argparse.CephString(goodchars="")
+        name_validator.valid(name)
+        args_validator = ceph_argparse.CephString(goodchars="[A-Za-z0-9-_.=]")
+        for s in args:
+            args_validator.valid(s)
+        cmd = {'prefix': 'mon set_location', 'name': name, 'args': args}
+        return run_ceph_command(self.rados_config_file, cmd, inbuf='',
+                                target=('mon', ''))
+
+
+class ManagerCommand:
+    def __init__(self, rados_config_file):
+        self.rados_config_file = rados_config_file
+
+    def mgr_module_enable(self, module, force=None):
+        """
+        enable mgr module
+
+        :param module: six.string_types allowed repeats=one
+        :param force: bool
+        :return: (string outbuf, string outs)
+        :raise CephError: Raises CephError on command execution errors
+        :raise rados.Error: Raises on rados errors
+        """
+
+        module_validator = ceph_argparse.CephString(goodchars="")
+        module_validator.valid(module)
+        cmd = {'prefix': 'mgr module enable', 'module': module}
+
+        if force is not None:
+            if not isinstance(force, bool):
+                raise TypeError("force is not a bool")
+            cmd['force'] = force
+        return run_ceph_command(self.rados_config_file, cmd, inbuf='',
+                                target=('mon', ''))
+
+    def mgr_count_metadata(self, property):
+        """
+        count ceph-mgr daemons by metadata field property
+
+        :param property: six.string_types allowed repeats=one
+        :return: (string outbuf, string outs)
+        :raise CephError: Raises CephError on command execution errors
+        :raise rados.Error: Raises on rados errors
+        """
+
+        property_validator = ceph_argparse.CephString(goodchars="")
+        property_validator.valid(property)
+        cmd = {'prefix': 'mgr count-metadata', 'property': property}
+        return run_ceph_command(self.rados_config_file, cmd, inbuf='',
+                                target=('mon', ''))
@@ -0,0 +1,55 @@
+COMMAND_WITH_FLAG("fsid", "show cluster FSID/UUID", "mon", "r", \
+	FLAG(NOFORWARD))
+COMMAND("health " \
+	"name=detail,type=CephChoices,strings=detail,req=false ", \
+	"show cluster health", "mon", "r")
+COMMAND("mon ok-to-stop " \
+	"name=ids,type=CephString,n=N ", \
+	"check whether mon(s) can be safely stopped without reducing immediate availability", "mon", "r")
+COMMAND("mon set_location " \
+	"name=name,type=CephString " \
+	"name=args,type=CephString,goodchars=[A-Za-z0-9-_.=],n=N ", \
+	"specify location <args> for the monitor <name>, using CRUSH bucket names", "mon", "rw")
+COMMAND("osd blocklist " \
+	"name=blocklistop,type=CephChoices,strings=add|rm " \
+	"name=addr,type=CephEntityAddr " \
+	"name=expire,type=CephFloat,range=0,req=false ", \
+	"add (optionally until <expire> seconds from now) or remove <addr> from blocklist", "osd", "rw")
+COMMAND("osd pool create " \
+	"name=pool,type=CephPoolname " \
+	"name=pg_num,type=CephInt,range=0,req=false " \
+	"name=pgp_num,type=CephInt,range=0,req=false " \
+	"name=pool_type,type=CephChoices,strings=replicated|erasure,req=false " \
+	"name=erasure_code_profile,type=CephString,goodchars=[A-Za-z0-9-_.],req=false " \
+	"name=rule,type=CephString,req=false " \
+	"name=expected_num_objects,type=CephInt,range=0,req=false " \
+	"name=size,type=CephInt,range=0,req=false " \
+	"name=pg_num_min,type=CephInt,range=0,req=false " \
+	"name=pg_num_max,type=CephInt,range=0,req=false " \
+	"name=autoscale_mode,type=CephChoices,strings=on|off|warn,req=false " \
+	"name=bulk,type=CephBool,req=false " \
+	"name=target_size_bytes,type=CephInt,range=0,req=false " \
+	"name=target_size_ratio,type=CephFloat,range=0,req=false " \
+	"name=yes_i_really_mean_it,type=CephBool,req=false ", \
+	"create pool", "osd", "rw")
+COMMAND("osd pool stretch set " \
+	"name=pool,type=CephPoolname " \
+	"name=peering_crush_bucket_count,type=CephInt,range=0 " \
+	"name=peering_crush_bucket_target,type=CephInt,range=0 " \
+	"name=peering_crush_bucket_barrier,type=CephString " \
+	"name=crush_rule,type=CephString " \
+	"name=size,type=CephInt,range=0 " \
+	"name=min_size,type=CephInt,range=0 " \
+	"name=yes_i_really_mean_it,type=CephBool,req=false ", \
+	"make the pool stretched across the specified number of CRUSH buckets", "osd", "rw")
+COMMAND("pg repeer " \
+	"name=pgid,type=CephPgid ", \
+	"force a PG to repeer", "osd", "rw")
+COMMAND("mgr module enable " \
+	"name=module,type=CephString " \
+	"name=force,type=CephBool,req=false ", \
+	"enable mgr module", "mgr", "rw")
+COMMAND_WITH_FLAG("mgr count-metadata " \
+	"name=property,type=CephString ", \
+	"count ceph-mgr daemons by metadata field property", "mgr", "r", \
+	FLAG(HIDDEN))
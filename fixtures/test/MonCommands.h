@@ -0,0 +1,61 @@
+/*
+ * Commands the unit tests share, read by test_fixtures in src/lib.rs.  This
+ * isn't any one release: each command is here for what it exercises.
+ */
+
+COMMAND_WITH_FLAG("osd pool create "
+	"name=pool,type=CephPoolname "
+	"name=pg_num,type=CephInt,range=0|65536 "
+	"name=pool_type,type=CephChoices,strings=replicated|erasure,req=false "
+	"name=crush-rule,type=CephString,n=N,req=false",
+	"create pool", "osd", "rw", "cli,rest", FLAG(DEPRECATED))
+
+// An overload of the osd pool create above
+COMMAND("osd pool create "
+	"name=pool,type=CephPoolname "
+	"name=size,type=CephInt",
+	"create pool", "osd", "rw", "cli,rest")
+COMMAND_WITH_FLAG("osd pool set-quota name=pool,type=CephPoolname", "set object or byte limit on pool",
+	"osd", "rw", "cli,rest", FLAG(DEPRECATED))
+COMMAND("osd crush rule list", "list crush rules", "osd", "r", "cli,rest")
+COMMAND_WITH_FLAG("osd crush rule create-legacy", "create crush rule", "osd", "rw", "cli,rest", FLAG(OBSOLETE))
+
+COMMAND("osd pool ls name=detail,type=CephChoices,strings=detail,req=false", "list pools", "osd", "r", "cli,rest")
+COMMAND("osd pool rm "
+	"name=pool,type=CephPoolname "
+	"name=pool2,type=CephPoolname,req=false "
+	"name=yes_i_really_really_mean_it,type=CephBool,req=false",
+	"remove pool", "osd", "rw", "cli,rest")
+COMMAND("pg stat", "show placement group status.", "pg", "r", "cli,rest")
+COMMAND("pg repeer name=pgid,type=CephPgid", "force a PG to repeer", "pg", "rw", "cli,rest")
+COMMAND("pg dump "
+	"name=dumpcontents,type=CephChoices,strings=all|summary|sum|delta|pools|osds|pgs|pgs_brief,n=N,req=false",
+	"show human-readable versions of pg map (only 'all' valid with plain)", "pg", "r", "cli,rest")
+COMMAND("balancer on", "Enable automatic balancing", "mgr", "rw", "cli,rest")
+
+// Tell commands, which the tests send to an osd or an mds
+COMMAND("bench "
+	"name=count,type=CephInt,req=false "
+	"name=size,type=CephInt,req=false",
+	"OSD benchmark: write <count> <size>-byte objects", "osd", "rw", "cli,rest")
+COMMAND("injectargs name=injected_args,type=CephString,n=N",
+	"inject configuration arguments into running daemon", "mds", "rw", "cli,rest")
+COMMAND("version", "report version of daemon", "osd", "r", "cli,rest")
+COMMAND("flush journal", "Flush the journal to the backing store", "mds", "rw", "cli,rest")
+
+// A module the generators don't know
+COMMAND("config set "
+	"name=who,type=CephString "
+	"name=name,type=CephString "
+	"name=value,type=CephString",
+	"Set a configuration option", "config", "rw", "cli,rest")
+
+// Parameters named after keywords
+COMMAND("osd crush rule create-replicated "
+	"name=name,type=CephString,goodchars=[A-Za-z0-9-_.] "
+	"name=root,type=CephString,goodchars=[A-Za-z0-9-_.] "
+	"name=type,type=CephString,goodchars=[A-Za-z0-9-_.] "
+	"name=class,type=CephString,goodchars=[A-Za-z0-9-_.],req=false",
+	"create crush rule <name> for replicated pool to start from <root>, replicate across buckets of type <type>, "
+	"use devices of type <class> (ssd or hdd)",
+	"osd", "rw", "cli,rest")
@@ -0,0 +1,6 @@
+// Descriptors that are wrong but still have to print back to the same commands
+COMMAND("health name=detail,type=CephChoices,req=false", "h", "mon", "r")
+COMMAND("pg x name=gid,type=CephPgid(,", "h", "pg", "r", "cli,rest")
+COMMAND("osd x name=id,type=CephInt,type name=y,type=CephInt,req,req=false", "h", "osd", "r")
+COMMAND("osd y name=id,type=CephString,type=CephInt,req=false,req=maybe", "h", "osd", "r", "cli,rest")
+COMMAND("osd z name=id,type=CephInt( name=w,type=", "h", "osd", "r", "cli,rest")
@@ -0,0 +1,17 @@
+#![no_main]
+#[macro_use]
+extern crate libfuzzer_sys;
+extern crate command_parser;
+
+use command_parser::header;
+
+fuzz_target!(|data: &[u8]| {
+    // Whatever parses has to print back to the same commands
+    if let Ok(cmds) = command_parser::parse_input("fuzz", data) {
+        let printed = header::generate(&cmds);
+        match command_parser::parse_input("printed", printed.as_bytes()) {
+            Ok(again) => assert_eq!(cmds, again, "printed as:\n{}", printed),
+            Err(e) => panic!("{}\nprinted as:\n{}", e, printed),
+        }
+    }
+});
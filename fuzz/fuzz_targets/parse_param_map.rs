@@ -0,0 +1,10 @@
+#![no_main]
+#[macro_use]
+extern crate libfuzzer_sys;
+extern crate command_parser;
+
+use command_parser::ceph_command::parse_param_map;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = parse_param_map(data);
+});
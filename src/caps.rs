@@ -0,0 +1,167 @@
+use std::collections::BTreeSet;
+
+use ceph_command::{Command, Target};
+
+#[cfg(test)]
+use test_fixtures::{command, sent_to};
+
+#[test]
+fn test_mon_caps() {
+    let cmds = vec![command("osd pool ls"), command("pg stat"), command("osd pool ls")];
+    let caps = Caps::from_commands(&cmds);
+    assert_eq!(Some("allow command \"osd pool ls\", allow command \"pg stat\"".to_string()), caps.mon);
+    assert_eq!(None, caps.mgr);
+    assert_eq!(None, caps.osd);
+    assert_eq!("ceph auth get-or-create client.monitoring mon 'allow command \"osd pool ls\", allow command \"pg stat\"'",
+               caps.auth_get_or_create("client.monitoring"));
+}
+
+#[test]
+fn test_daemon_caps() {
+    let cmds = vec![sent_to("pg dump", Target::Mgr),
+                    sent_to("bench", Target::Osd),
+                    sent_to("injectargs", Target::Mds),
+                    sent_to("version", Target::Osd)];
+    let caps = Caps::from_commands(&cmds);
+    // The maps are read from the monitors to find the other daemons
+    assert_eq!(Some("allow r".to_string()), caps.mon);
+    assert_eq!(Some("allow command \"pg dump\"".to_string()), caps.mgr);
+    assert_eq!(Some("allow *".to_string()), caps.osd);
+    assert_eq!(Some("allow *".to_string()), caps.mds);
+    assert_eq!("ceph auth get-or-create client.admin-lite mon 'allow r' mgr 'allow command \"pg dump\"' osd 'allow *' \
+                mds 'allow *'",
+               caps.auth_get_or_create("client.admin-lite"));
+
+    let cmds = vec![command("pg stat"), sent_to("pg dump", Target::Mgr)];
+    assert_eq!(Some("allow r, allow command \"pg stat\"".to_string()), Caps::from_commands(&cmds).mon);
+}
+
+#[test]
+fn test_allows() {
+    let caps = Caps {
+        mon: Some("allow r, allow command \"osd pool create\"".to_string()),
+        mgr: Some("allow *".to_string()),
+        osd: Some("allow rwx".to_string()),
+        mds: Some("allow *".to_string()),
+    };
+    assert!(caps.allows(&command("pg stat")));
+    assert!(caps.allows(&command("osd pool create")));
+    assert!(!caps.allows(&command("osd pool rm")));
+    assert!(caps.allows(&sent_to("balancer on", Target::Mgr)));
+    // tell commands need allow *
+    assert!(!caps.allows(&sent_to("bench", Target::Osd)));
+    assert!(caps.allows(&sent_to("flush journal", Target::Mds)));
+    // Caps::from_commands grants exactly what it was given
+    let cmds = vec![sent_to("bench", Target::Osd), sent_to("pg dump", Target::Mgr)];
+    let caps = Caps::from_commands(&cmds);
+    assert!(cmds.iter().all(|c| caps.allows(c)));
+}
+
+/// The cephx caps needed to run a set of commands.  The monitors and the
+/// manager can grant individual commands so those caps list every command.
+/// OSDs and MDSes check tell commands against allow * and clients need to
+/// read the maps from the monitors to find them, or the manager, at all.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Caps {
+    pub mon: Option<String>,
+    pub mgr: Option<String>,
+    pub osd: Option<String>,
+    pub mds: Option<String>,
+}
+
+/// Join grants into a cap, None if there aren't any
+fn join_grants(grants: Vec<String>) -> Option<String> {
+    if grants.is_empty() {
+        None
+    } else {
+        Some(grants.join(", "))
+    }
+}
+
+fn allow_commands(prefixes: &BTreeSet<&str>) -> Vec<String> {
+    prefixes.iter().map(|p| format!("allow command \"{}\"", p)).collect()
+}
+
+/// Does one grant in a cap string allow cmd?  Only the grants Caps writes are
+/// understood: allow *, allow with some of r, w and x, and allow command.
+fn grants(cap: &str, cmd: &Command) -> bool {
+    cap.split([',', ';']).any(|grant| {
+        let grant = grant.trim();
+        if !grant.starts_with("allow ") {
+            return false;
+        }
+        let what = grant["allow ".len()..].trim();
+        if what == "*" {
+            true
+        } else if let Some(command) = what.strip_prefix("command ") {
+            command.trim().trim_matches('"') == cmd.signature.prefix
+        } else if !what.is_empty() && what.chars().all(|c| "rwx".contains(c)) {
+            let p = &cmd.permissions;
+            (!p.read || what.contains('r')) && (!p.write || what.contains('w')) && (!p.execute || what.contains('x'))
+        } else {
+            false
+        }
+    })
+}
+
+impl Caps {
+    pub fn from_commands(cmds: &[Command]) -> Caps {
+        let mut mon: BTreeSet<&str> = BTreeSet::new();
+        let mut mgr: BTreeSet<&str> = BTreeSet::new();
+        let mut osd = false;
+        let mut mds = false;
+
+        for cmd in cmds.iter() {
+            match cmd.target {
+                Target::Mon => {
+                    mon.insert(&cmd.signature.prefix);
+                }
+                Target::Mgr => {
+                    mgr.insert(&cmd.signature.prefix);
+                }
+                Target::Osd => osd = true,
+                Target::Mds => mds = true,
+            }
+        }
+        let mut mon_grants = Vec::new();
+        if !mgr.is_empty() || osd || mds {
+            mon_grants.push("allow r".to_string());
+        }
+        mon_grants.extend(allow_commands(&mon));
+        let tell = |used: bool| if used { Some("allow *".to_string()) } else { None };
+        Caps {
+            mon: join_grants(mon_grants),
+            mgr: join_grants(allow_commands(&mgr)),
+            osd: tell(osd),
+            mds: tell(mds),
+        }
+    }
+
+    /// Could an entity with these caps run cmd?  Only the caps of the daemon
+    /// cmd is sent to count.  OSDs and MDSes want allow * for tell commands.
+    pub fn allows(&self, cmd: &Command) -> bool {
+        let (cap, tell) = match cmd.target {
+            Target::Mon => (&self.mon, false),
+            Target::Mgr => (&self.mgr, false),
+            Target::Osd => (&self.osd, true),
+            Target::Mds => (&self.mds, true),
+        };
+        match cap.as_ref() {
+            Some(c) if tell => c.split([',', ';']).any(|grant| grant.trim() == "allow *"),
+            Some(c) => grants(c, cmd),
+            None => false,
+        }
+    }
+
+    /// The ceph auth command that creates entity with these caps
+    pub fn auth_get_or_create(&self, entity: &str) -> String {
+        let mut output = format!("ceph auth get-or-create {}", entity);
+        let daemons = vec![("mon", &self.mon), ("mgr", &self.mgr), ("osd", &self.osd), ("mds", &self.mds)];
+        for (daemon, cap) in daemons {
+            if let Some(c) = cap {
+                output.push_str(&format!(" {} '{}'", daemon, c));
+            }
+        }
+        output
+    }
+}
@@ -1,32 +1,46 @@
 extern crate nom;
 
-use nom::{is_digit, is_alphabetic, is_alphanumeric, eof, multispace, not_line_ending, rest, space};
-
-use std::collections::HashMap;
+use nom::IResult;
+use nom::branch::alt;
+use nom::bytes::complete::{tag, take_until, take_while};
+use nom::character::complete::{multispace1, not_line_ending};
+use nom::combinator::{eof, map, map_res, opt, recognize, value};
+use nom::multi::{many0, separated_list0};
+use nom::sequence::{delimited, pair, preceded, terminated, tuple};
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::{self, Read};
 use std::str::{from_utf8, FromStr};
 
+use code_writer::CodeWriter;
+use rustc_serialize::json::Json;
+use schema::{python_field_name, SchemaRegistry};
+
 #[test]
 fn one_command() {
     let x: &[u8] = &[];
     let input = r#"COMMAND("pg dump_pools_json", "show pg pools info in json only",\
 	"pg", "r", "cli,rest")"#;
-    let result = Command::parse(&input.as_bytes());
-    //println!("Result: {:?}", result);
+    let result = Command::parse(input.as_bytes());
 
     assert_eq!(
-        nom::IResult::Done(x,
+        Ok((x,
             Command {
                 signature: Signature {
                     prefix: "pg dump_pools_json".to_string(),
                     parameters: HashMap::new(),
                     duplicate: false,
+                    renames: HashMap::new(),
+                    order: Vec::new(),
             },
             helpstring: "show pg pools info in json only".to_string(),
             module_name: Module::Pg,
             permissions: Permissions { read: true, write: false, execute: false },
             availability: Availability::Both,
-            flags: None }
-        ), result);
+            flags: None,
+            target: Target::Mon,
+            syntax: Syntax::default() }
+        )), result);
 }
 
 #[test]
@@ -37,31 +51,32 @@ fn piped_command() {
         "name=pool,type=CephInt,req=false " \
     	"name=states,type=CephChoices,strings=active|clean|down|replay|splitting|scrubbing|scrubq|degraded|inconsistent|peering|repair|recovering|backfill_wait|incomplete|stale|remapped|deep_scrub|backfill|backfill_toofull|recovery_wait|undersized|activating|peered,n=N,req=false ", \
     	"list pg on osd [osd]", "pg", "r", "cli,rest")"#;
-    let result = Command::parse(&input.as_bytes());
-    //println!("piped_command Result: {:?}", result);
+    let result = Command::parse(input.as_bytes());
 
     //Expected params that will be parsed
     let mut params = HashMap::new();
-    params.insert("osd".to_string(),CephType { req: true, variant: CephEnum::CephOsdName });
-    params.insert("states".to_string(), CephType { req: false, variant: CephEnum::CephChoices {
+    params.insert("osd".to_string(),CephType::new(true, CephEnum::CephOsdName));
+    params.insert("states".to_string(), CephType::new(false, CephEnum::CephChoices {
         choices: vec![
             "active".to_string(), "clean".to_string(), "down".to_string(), "replay".to_string(), "splitting".to_string(), "scrubbing".to_string(), "scrubq".to_string(), "degraded".to_string(), "inconsistent".to_string(), "peering".to_string(), "repair".to_string(), "recovering".to_string(), "backfill_wait".to_string(), "incomplete".to_string(), "stale".to_string(), "remapped".to_string(), "deep_scrub".to_string(), "backfill".to_string(), "backfill_toofull".to_string(), "recovery_wait".to_string(), "undersized".to_string(), "activating".to_string(), "peered".to_string()]
-            , allowed_repeats: AllowedRepeats::Many } });
-    params.insert("pool".to_string(), CephType { req: false, variant: CephEnum::CephInt { min: None, max: None }});
+            , allowed_repeats: AllowedRepeats::Many }));
+    params.insert("pool".to_string(), CephType::new(false, CephEnum::CephInt { min: None, max: None }));
 
     assert_eq!(
-        nom::IResult::Done(x,
+        Ok((x,
             Command {
                 signature: Signature {
                     prefix: "pg ls-by-osd".to_string(),
                     parameters: params,
                     duplicate: false,
+                    renames: HashMap::new(),
+                    order: vec!["osd".to_string(), "pool".to_string(), "states".to_string()],
             },
             helpstring: "list pg on osd [osd]".to_string(),
             module_name: Module::Pg,
             permissions: Permissions { read: true, write: false, execute: false },
-            availability: Availability::Both, flags: None }
-        ), result);
+            availability: Availability::Both, flags: None, target: Target::Mon, syntax: Syntax::default() }
+        )), result);
 }
 
 #[derive(Clone, Debug,Eq,PartialEq)]
@@ -74,19 +89,42 @@ pub enum Flag {
     Obsolete,
     ///command is considered deprecated
     Deprecated,
+    ///command is handled by the manager (luminous)
+    Mgr,
+    ///command is polled by the cli rather than run once
+    Poll,
+    ///command is left out of the help output
+    Hidden,
 }
 
 impl Flag {
-    fn from_str(m: &str) -> Flag {
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(m: &str) -> Flag {
         trace!("Input to Flag: {:?}", m);
         match m {
             "NONE" => Flag::NoFlag,
             "NOFORWARD" => Flag::NoForward,
             "OBSOLETE" => Flag::Obsolete,
             "DEPRECATED" => Flag::Deprecated,
+            "MGR" => Flag::Mgr,
+            "POLL" => Flag::Poll,
+            "HIDDEN" => Flag::Hidden,
             _ => Flag::NoFlag,
         }
     }
+
+    /// The name used in the command tables
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Flag::NoFlag => "NONE",
+            Flag::NoForward => "NOFORWARD",
+            Flag::Obsolete => "OBSOLETE",
+            Flag::Deprecated => "DEPRECATED",
+            Flag::Mgr => "MGR",
+            Flag::Poll => "POLL",
+            Flag::Hidden => "HIDDEN",
+        }
+    }
 }
 
 #[derive(Clone, Debug, Eq,PartialEq)]
@@ -98,7 +136,8 @@ pub enum Availability {
 }
 
 impl Availability {
-    fn from_str(m: &str) -> Availability {
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(m: &str) -> Availability {
         trace!("Input to Availability: {:?}", m);
         match m {
             "cli" => Availability::Cli,
@@ -107,6 +146,16 @@ impl Availability {
             _ => Availability::Unknown,
         }
     }
+
+    /// The name used in the command tables
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Availability::Cli => "cli",
+            Availability::Rest => "rest",
+            Availability::Both => "cli,rest",
+            Availability::Unknown => "unknown",
+        }
+    }
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -118,11 +167,13 @@ pub enum Module {
     Auth,
     Log,
     ConfigKey,
+    Mgr,
     Unknown,
 }
 
 impl Module {
-    fn from_str(m: &str) -> Module {
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(m: &str) -> Module {
         trace!("Input to Module: {:?}", m);
         match m {
             "mds" => Module::Mds,
@@ -132,9 +183,30 @@ impl Module {
             "auth" => Module::Auth,
             "log" => Module::Log,
             "config-key" => Module::ConfigKey,
+            "mgr" => Module::Mgr,
             _ => Module::Unknown,
         }
     }
+    /// The name used in the command tables
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Module::Mds => "mds",
+            Module::Osd => "osd",
+            Module::Pg => "pg",
+            Module::Mon => "mon",
+            Module::Auth => "auth",
+            Module::Log => "log",
+            Module::ConfigKey => "config-key",
+            Module::Mgr => "mgr",
+            Module::Unknown => "unknown",
+        }
+    }
+    /// Every module the generators make a class of, in the order they come in
+    pub fn all() -> Vec<Module> {
+        vec![Module::Pg, Module::Mds, Module::Osd, Module::Mon, Module::Auth, Module::Log, Module::ConfigKey,
+             Module::Mgr]
+    }
+    #[allow(clippy::inherent_to_string)]
     pub fn to_string(self) -> String{
         match self{
             Module::Mds => "MdsCommand".to_string(),
@@ -144,11 +216,189 @@ impl Module {
             Module::Auth => "AuthCommand".to_string(),
             Module::Log => "LogCommand".to_string(),
             Module::ConfigKey => "ConfigKeyCommand".to_string(),
+            Module::Mgr => "ManagerCommand".to_string(),
             Module::Unknown => "UnknownCommand".to_string(),
         }
     }
 }
 
+#[test]
+fn test_target_overrides(){
+    let input = r#"# Commands the monitors hand off to the manager
+pg dump = mgr
+osd pool stats=mgr
+
+bench = osd
+"#;
+    let overrides = parse_target_overrides(input);
+    assert_eq!(Some(&Target::Mgr), overrides.get("pg dump"));
+    assert_eq!(Some(&Target::Mgr), overrides.get("osd pool stats"));
+    assert_eq!(Some(&Target::Osd), overrides.get("bench"));
+    assert_eq!(3, overrides.len());
+}
+
+#[test]
+fn test_target_from_source(){
+    assert_eq!(Some(Target::Mon), Target::from_source("/tmp/MonCommands.h"));
+    assert_eq!(Some(Target::Mgr), Target::from_source("src/mgr/MgrCommands.h"));
+    assert_eq!(Some(Target::Osd), Target::from_source("src/osd/OSD.cc"));
+    assert_eq!(Some(Target::Mds), Target::from_source("src/mds/MDSDaemon.cc"));
+    assert_eq!(Some(Target::Mon), Target::from_source("fixtures/reef-MonCommands.h"));
+    assert_eq!(None, Target::from_source("commands.txt"));
+}
+
+#[test]
+fn test_target_from_module(){
+    let mut cmds = ::parse_input("commands.txt",
+                                 b"COMMAND(\"balancer status\", \"show balancer status\", \"mgr\", \"r\", \"cli,rest\")\n\
+                                   COMMAND(\"osd stat\", \"print summary of OSD map\", \"osd\", \"r\", \"cli,rest\")")
+        .unwrap();
+    assign_targets(&mut cmds, None, &HashMap::new());
+    assert_eq!(Target::Mgr, cmds[0].target);
+    assert_eq!(Target::Mon, cmds[1].target);
+
+    // A known source wins over the module and overrides over both
+    assign_targets(&mut cmds, Some(Target::Mon), &HashMap::new());
+    assert_eq!(Target::Mon, cmds[0].target);
+    let mut overrides = HashMap::new();
+    overrides.insert("osd stat".to_string(), Target::Mgr);
+    override_targets(&mut cmds, &overrides);
+    assert_eq!(vec![Target::Mon, Target::Mgr], cmds.iter().map(|c| c.target.clone()).collect::<Vec<Target>>());
+}
+
+/// The daemon a command has to be sent to.  MonCommands.h is served entirely
+/// by the monitors, even for the mds/osd/pg modules, so the target comes from
+/// where the command table was read rather than from the module name.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Target {
+    ///Sent to the monitors with mon_command
+    Mon,
+    ///Sent to the active manager with mgr_command
+    Mgr,
+    ///Sent to a single osd with osd_command (ceph tell osd.<id>)
+    Osd,
+    ///Sent to a single mds with mds_command (ceph tell mds.<id>)
+    Mds,
+}
+
+impl Target {
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(m: &str) -> Option<Target> {
+        trace!("Input to Target: {:?}", m);
+        match m {
+            "mon" => Some(Target::Mon),
+            "mgr" => Some(Target::Mgr),
+            "osd" => Some(Target::Osd),
+            "mds" => Some(Target::Mds),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Target::Mon => "mon",
+            Target::Mgr => "mgr",
+            Target::Osd => "osd",
+            Target::Mds => "mds",
+        }
+    }
+
+    /// Work out the target from the name of the file the commands were read from.
+    /// A prefixed copy such as reef-MonCommands.h counts.  Returns None if the
+    /// file isn't one of the known Ceph command tables.
+    pub fn from_source(path: &str) -> Option<Target> {
+        let file_name = path.rsplit('/').next().unwrap_or(path);
+        let sources = [("MonCommands.h", Target::Mon),
+                       ("MgrCommands.h", Target::Mgr),
+                       ("OSD.cc", Target::Osd),
+                       ("MDSDaemon.cc", Target::Mds)];
+        sources.iter()
+            .find(|(name, _)| file_name.ends_with(name))
+            .map(|(_, target)| target.clone())
+    }
+
+    /// The target of a command from a table that isn't one of the known files.
+    /// Only the mgr module says where a command goes, the monitors serve the
+    /// osd, mds and pg modules themselves.
+    pub fn from_module(module: &Module) -> Target {
+        match *module {
+            Module::Mgr => Target::Mgr,
+            _ => Target::Mon,
+        }
+    }
+
+    /// Name of the extra parameter a generated method needs to pick which
+    /// daemon the command is sent to.  Mon and mgr commands don't need one.
+    pub fn id_param(&self) -> Option<&'static str> {
+        match *self {
+            Target::Mon => None,
+            Target::Mgr => None,
+            Target::Osd => Some("osd_id"),
+            Target::Mds => Some("mds_id"),
+        }
+    }
+
+    /// The (daemon type, daemon id) tuple passed to run_ceph_command
+    fn to_python(&self) -> String {
+        match *self {
+            Target::Mon => "('mon', '')".to_string(),
+            Target::Mgr => "('mgr', '')".to_string(),
+            Target::Osd => "('osd', osd_id)".to_string(),
+            Target::Mds => "('mds', mds_id)".to_string(),
+        }
+    }
+}
+
+/// Parse a target override file.  Each line is `command prefix = target`
+/// where target is one of mon, mgr, osd or mds.  Blank lines and lines
+/// starting with # are ignored.
+pub fn parse_target_overrides(input: &str) -> HashMap<String, Target> {
+    let mut overrides: HashMap<String, Target> = HashMap::new();
+    for line in input.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with("#") {
+            continue;
+        }
+        let parts: Vec<&str> = line.splitn(2, '=').collect();
+        if parts.len() != 2 {
+            warn!("Ignoring target override without '=': {:?}", line);
+            continue;
+        }
+        match Target::from_str(parts[1].trim()) {
+            Some(target) => {
+                overrides.insert(parts[0].trim().to_string(), target);
+            }
+            None => {
+                warn!("Ignoring unknown target {:?} for {:?}", parts[1].trim(), parts[0].trim());
+            }
+        }
+    }
+    overrides
+}
+
+/// Set the target of every command.  The source file decides the default, or
+/// the module if the source isn't known, and entries in overrides win over
+/// that for the prefixes they name.
+pub fn assign_targets(cmds: &mut [Command], source: Option<Target>, overrides: &HashMap<String, Target>) {
+    for cmd in cmds.iter_mut() {
+        cmd.target = match source {
+            Some(ref t) => t.clone(),
+            None => Target::from_module(&cmd.module_name),
+        };
+    }
+    override_targets(cmds, overrides);
+}
+
+/// Send the commands overrides names to the target it gives, leaving the
+/// rest where they were
+pub fn override_targets(cmds: &mut [Command], overrides: &HashMap<String, Target>) {
+    for cmd in cmds.iter_mut() {
+        if let Some(t) = overrides.get(&cmd.signature.prefix) {
+            cmd.target = t.clone();
+        }
+    }
+}
+
 #[derive(Clone, Debug, Eq,PartialEq)]
 pub struct Permissions {
     pub read: bool,
@@ -157,13 +407,34 @@ pub struct Permissions {
 }
 
 impl Permissions {
-    fn from_str(perms: &str) -> Permissions {
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(perms: &str) -> Permissions {
         Permissions {
             read: perms.contains("r"),
             write: perms.contains("w"),
             execute: perms.contains("x"),
         }
     }
+
+    pub fn is_read_only(&self) -> bool {
+        self.read && !self.write && !self.execute
+    }
+
+    /// The permissions as written in the command tables, for example "rw"
+    #[allow(clippy::inherent_to_string)]
+    pub fn to_string(&self) -> String {
+        let mut perms = String::new();
+        if self.read {
+            perms.push('r');
+        }
+        if self.write {
+            perms.push('w');
+        }
+        if self.execute {
+            perms.push('x');
+        }
+        perms
+    }
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -171,6 +442,10 @@ pub struct Signature {
     pub prefix: String,
     pub duplicate: bool,
     pub parameters: HashMap<String, CephType>,
+    /// Parameter name -> name used for it in the generated API
+    pub renames: HashMap<String, String>,
+    /// Parameter names in the order they're declared
+    pub order: Vec<String>,
 }
 
 impl Signature {
@@ -178,31 +453,34 @@ impl Signature {
     fn parse(input: & str) -> Self {
         let mut prefix: Vec<String> = Vec::new();
 
-        // Replace all the nasty things
-        let no_slashes_input = input.replace("\\", "")
-                                    .replace("\"", "")
-                                    .replace("\n", "")
-                                    .replace("    ", "");
+        // Replace all the nasty things once the literals are joined the way
+        // C joins them, so a choice list can be split across lines
+        let no_slashes_input = join_literals(input).replace("\\", "")
+                                                   .replace("\"", "")
+                                                   .replace("\n", "")
+                                                   .replace("    ", "");
 
-        //println!("Signature input: {:?}", no_slashes_input);
         let parts: Vec<&str> = no_slashes_input.split_whitespace()
-                                               .filter(|x| x.len() > 0)
+                                               .filter(|x| !x.is_empty())
                                                .collect();
         let mut parameters: HashMap<String, CephType> = HashMap::new();
+        let mut order: Vec<String> = Vec::new();
+        let mut unparsed: Vec<&str> = Vec::new();
         // If name= in the parts array than we have a CephType and not the prefix
         for part in parts.iter() {
             if part.contains("name=") {
                 // We have a parameter
                 // "name=pool,type=CephInt,req=false "
 
-                //println!("parse_param_map: {:?}", part);
                 let result = parse_param_map(part.as_bytes());
                 match result{
-                    nom::IResult::Done(_, ref param_tuple) => {
-                        parameters.insert(param_tuple.0.clone(), param_tuple.1.clone());
+                    Ok((_, ref param_tuple)) => {
+                        if parameters.insert(param_tuple.0.clone(), param_tuple.1.clone()).is_none() {
+                            order.push(param_tuple.0.clone());
+                        }
                     }
                     _ =>{
-                        println!("Failed to parse: {:?}", result);
+                        unparsed.push(part);
                     }
                 }
             } else {
@@ -211,10 +489,51 @@ impl Signature {
             }
         }
 
+        let prefix = prefix.join(" ");
+        for part in unparsed.iter() {
+            warn!("{}: couldn't parse parameter {:?}, it's left out", prefix, part);
+        }
+        for name in order.iter() {
+            if parameters[name].variant == CephEnum::Unknown {
+                warn!("{}: parameter {} has unknown type {}, it won't be validated",
+                      prefix, name, parameters[name].extra.get("type").map(|t| &t[..]).unwrap_or("?"));
+            }
+            for (key, value) in parameters[name].extra.iter().filter(|&(k, _)| k != "type") {
+                warn!("{}: parameter {} has an attribute the generators don't understand: {}={}",
+                      prefix, name, key, value);
+            }
+        }
+
         Signature {
-            prefix: prefix.join(" "),
+            prefix,
             duplicate: false,
-            parameters: parameters,
+            parameters,
+            renames: HashMap::new(),
+            order,
+        }
+    }
+
+    /// Parameters in declaration order.  Any that aren't in `order`, for
+    /// example ones added by hand, come last sorted by name.
+    pub fn ordered_parameters(&self) -> Vec<(&String, &CephType)> {
+        let mut params: Vec<(&String, &CephType)> = self.order
+            .iter()
+            .filter_map(|name| self.parameters.get(name).map(|t| (name, t)))
+            .collect();
+        let mut rest: Vec<(&String, &CephType)> = self.parameters
+            .iter()
+            .filter(|&(name, _)| !self.order.contains(name))
+            .collect();
+        rest.sort_by(|a, b| a.0.cmp(b.0));
+        params.extend(rest);
+        params
+    }
+
+    /// The name a parameter goes by in the generated API
+    pub fn api_name<'a>(&'a self, param_name: &'a str) -> &'a str {
+        match self.renames.get(param_name) {
+            Some(name) => name,
+            None => param_name,
         }
     }
 }
@@ -227,12 +546,14 @@ pub enum AllowedRepeats{
 }
 
 impl AllowedRepeats{
-    fn from_str(repeats: &str) -> AllowedRepeats {
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(repeats: &str) -> AllowedRepeats {
         match repeats{
             "N" => AllowedRepeats::Many,
             _ => AllowedRepeats::One,
         }
     }
+    #[allow(clippy::inherent_to_string, clippy::wrong_self_convention)]
     fn to_string(self)->String{
         match self{
             AllowedRepeats::Many => "many".to_string(),
@@ -245,264 +566,144 @@ impl AllowedRepeats{
 pub struct CephType{
     pub req: bool,
     pub variant: CephEnum,
+    /// positional=false means the argument can only be given by name
+    pub positional: bool,
+    /// allowempty=true lets a string argument be ""
+    pub allow_empty: bool,
+    /// key=value pairs the parser doesn't know about, kept so they can be
+    /// reported and dumped instead of lost
+    pub extra: BTreeMap<String, String>,
 }
 
 impl CephType{
-    fn parse<'a>(input: &'a [u8], ceph_type: String) -> nom::IResult<&'a [u8], Self> {
-        //if ceph_type == "CephPoolname" {
-        //    println!("ceph_type: {}", ceph_type);
-        //    println!("Input to ceph_type: {}", String::from_utf8_lossy(input));
-        //}
-        match &ceph_type[..] {
+    pub fn new(req: bool, variant: CephEnum) -> CephType {
+        CephType {
+            req,
+            variant,
+            positional: true,
+            allow_empty: false,
+            extra: BTreeMap::new(),
+        }
+    }
+
+    // Everything after name= and type= is a list of key=value pairs in any
+    // order.  Keys that don't apply to ceph_type end up in extra.
+    fn parse(input: &[u8], mut ceph_type: String) -> IResult<&[u8], Self> {
+        let (remaining, attrs) = attributes(input)?;
+        let mut extra: BTreeMap<String, String> = BTreeMap::new();
+        let mut req = true;
+        let mut positional = true;
+        let mut allow_empty = false;
+        let mut range: Option<String> = None;
+        let mut repeats: Option<AllowedRepeats> = None;
+        let mut goodchars: Option<String> = None;
+        let mut badchars: Option<String> = None;
+        let mut strings: Option<Vec<String>> = None;
+        for (key, value) in attrs {
+            match &key[..] {
+                // Later keys win like they do in ceph_argparse's dict
+                "type" => ceph_type = value,
+                "req" => req = parse_bool(&key, &value, &mut extra).unwrap_or(req),
+                "positional" => positional = parse_bool(&key, &value, &mut extra).unwrap_or(positional),
+                "allowempty" => allow_empty = parse_bool(&key, &value, &mut extra).unwrap_or(allow_empty),
+                "range" => range = Some(value),
+                "n" => repeats = Some(AllowedRepeats::from_str(&value)),
+                "goodchars" => goodchars = Some(value.trim_matches(|c| c == '[' || c == ']').to_string()),
+                "badchars" => badchars = Some(value.trim_matches(|c| c == '[' || c == ']').to_string()),
+                "strings" => strings = Some(value.split("|").map(|s| s.to_string()).collect()),
+                _ => {
+                    extra.insert(key, value);
+                }
+            }
+        }
+
+        let variant = match &ceph_type[..] {
             "CephInt" => {
-                chain!(
-                    input,
-                    range_min: dbg!(call!(u32_min_range)) ~
-                    range_max: dbg!(call!(u32_max_range)) ~
-                    req: dbg!(call!(req)),
-                    ||{
-                        CephType{
-                            req: req,
-                            variant: CephEnum::CephInt{
-                                min: range_min,
-                                max: range_max,
-                            }
-                        }
-                    }
-                )
+                let (min, max) = parse_range(range.take(), &mut extra);
+                CephEnum::CephInt { min, max }
             }
             "CephFloat" => {
-                chain!(
-                    input,
-                    min_range: dbg!(call!(f32_min_range)) ~
-                    max_range: dbg!(call!(f32_max_range)) ~
-                    req: dbg!(call!(req)) ,
-                    ||{
-                        CephType{
-                            req: req,
-                            variant: CephEnum::CephFloat{
-                                min: min_range,
-                                max: max_range,
-                            }
-                        }
-                    }
-                )
+                let (min, max) = parse_range(range.take(), &mut extra);
+                CephEnum::CephFloat { min, max }
             }
             "CephString" => {
-                if input.len() == 0{
-                    nom::IResult::Done(input,
-
-                        CephType{
-                            req: true,
-                            variant: CephEnum::CephString{
-                                goodchars: None,
-                                allowed_repeats: AllowedRepeats::One
-                            }
-                        }
-                    )
-                }else{
-                    chain!(
-                        input,
-                        repeats: call!(one_or_more) ~
-                        goodchars: call!(good_chars) ~
-                        req: call!(req),
-                        ||{
-                            CephType{
-                                req: req,
-                                variant: CephEnum::CephString{
-                                    goodchars: goodchars,
-                                    allowed_repeats: repeats,
-                                }
-                            }
-                        }
-                    )
+                CephEnum::CephString {
+                    goodchars: goodchars.take(),
+                    badchars: badchars.take(),
+                    allowed_repeats: repeats.take().unwrap_or(AllowedRepeats::One),
                 }
             }
-            "CephSocketpath" => {
-                chain!(
-                    input,
-                    req: call!(req) ,
-                    ||{
-                        CephType{
-                            req: req,
-                            variant: CephEnum::CephSocketpath
-                        }
-                    }
-                )
-            }
-            "CephIPAddr" => {
-                chain!(
-                    input,
-                    req: call!(req) ,
-                    ||{
-                        CephType{
-                            req: req,
-                            variant: CephEnum::CephIPAddr
-                        }
-                    }
-                )
-            }
-            "CephEntityAddr" => {
-                chain!(
-                    input,
-                    req: call!(req) ,
-                    ||{
-                        CephType{
-                            req: req,
-                            variant: CephEnum::CephEntityAddr
-                        }
-                    }
-                )
-            }
-            "CephPoolname" => {
-                chain!(
-                    input,
-                    repeats: dbg!(opt!(call!(allowed))) ~
-                    req: call!(req) ,
-                    ||{
-                        CephType{
-                            req: req,
-                            variant: CephEnum::CephPoolname{
-                                allowed_repeats: repeats,
-                            }
-                        }
-                    }
-                )
-            }
-            "CephObjectname" => {
-                chain!(
-                    input,
-                    req: call!(req) ,
-                    ||{
-                        CephType{
-                            req: req,
-                            variant: CephEnum::CephObjectname
-                        }
-                    }
-                )
-            }
-            "CephPgid" => {
-                chain!(
-                    input,
-                    req: call!(req) ,
-                    ||{
-                        CephType{
-                            req: req,
-                            variant: CephEnum::CephPgid
-                        }
-                    }
-                )
-            }
-            "CephName" => {
-                chain!(
-                    input,
-                    req: call!(req) ,
-                    ||{
-                        CephType{
-                            req: req,
-                            variant: CephEnum::CephName
-                        }
-                    }
-                )
-            }
-            "CephOsdName" => {
-                chain!(
-                    input,
-                    req: call!(req) ,
-                    ||{
-                        CephType{
-                            req: req,
-                            variant: CephEnum::CephOsdName
-                        }
-                    }
-                )
-            }
+            "CephSocketpath" => CephEnum::CephSocketpath,
+            "CephIPAddr" => CephEnum::CephIPAddr,
+            "CephEntityAddr" => CephEnum::CephEntityAddr,
+            "CephPoolname" => CephEnum::CephPoolname { allowed_repeats: repeats.take() },
+            "CephObjectname" => CephEnum::CephObjectname,
+            "CephPgid" => CephEnum::CephPgid,
+            "CephName" => CephEnum::CephName,
+            "CephOsdName" => CephEnum::CephOsdName,
             "CephChoices" => {
-                chain!(
-                    input,
-                    choices: choices ~
-                    repeats: call!(one_or_more) ~
-                    req: call!(req) ,
-                    ||{
-                        CephType{
-                            req: req,
-                            variant: CephEnum::CephChoices{
-                                choices: choices.clone(),
-                                allowed_repeats: repeats,
-                            }
-                        }
-                    }
-                )
-            }
-            "CephFilepath" => {
-                chain!(
-                    input,
-                    req: call!(req) ,
-                    ||{
-                        CephType{
-                            req: req,
-                            variant: CephEnum::CephFilepath
-                        }
-                    }
-                )
-            }
-            "CephFragment" => {
-                chain!(
-                    input,
-                    req: call!(req) ,
-                    ||{
-                        CephType{
-                            req: req,
-                            variant: CephEnum::CephFragment
-                        }
-                    }
-                )
-            }
-            "CephUUID" => {
-                chain!(
-                    input,
-                    req: call!(req) ,
-                    ||{
-                        CephType{
-                            req: req,
-                            variant: CephEnum::CephUUID
-                        }
-                    }
-                )
-            }
-            "CephPrefix" => {
-                chain!(
-                    input,
-                    req: call!(req) ,
-                    ||{
-                        CephType{
-                            req: req,
-                            variant: CephEnum::CephPrefix
-                        }
-                    }
-                )
+                CephEnum::CephChoices {
+                    choices: strings.take().unwrap_or_default(),
+                    allowed_repeats: repeats.take().unwrap_or(AllowedRepeats::One),
+                }
             }
+            "CephFilepath" => CephEnum::CephFilepath,
+            "CephFragment" => CephEnum::CephFragment,
+            "CephUUID" => CephEnum::CephUUID,
+            "CephPrefix" => CephEnum::CephPrefix,
+            "CephBool" => CephEnum::CephBool,
+            "CephTimespan" => CephEnum::CephTimespan,
+            "CephPgidList" => CephEnum::CephPgidList,
+            "CephArgtype" => CephEnum::CephArgtype,
             _ => {
-                nom::IResult::Done(input, CephType{req: false, variant: CephEnum::Unknown})
+                extra.insert("type".to_string(), ceph_type.clone());
+                CephEnum::Unknown
             }
+        };
+
+        // Whatever the variant didn't use doesn't apply to this type
+        if let Some(r) = range {
+            extra.insert("range".to_string(), r);
+        }
+        if let Some(n) = repeats {
+            extra.insert("n".to_string(), if n == AllowedRepeats::Many { "N" } else { "1" }.to_string());
+        }
+        if let Some(chars) = goodchars {
+            extra.insert("goodchars".to_string(), format!("[{}]", chars));
         }
+        if let Some(chars) = badchars {
+            extra.insert("badchars".to_string(), format!("[{}]", chars));
+        }
+        if let Some(s) = strings {
+            extra.insert("strings".to_string(), s.join("|"));
+        }
+
+        Ok((remaining,
+                           CephType {
+                               req,
+                               variant,
+                               positional,
+                               allow_empty,
+                               extra,
+                           }))
     }
 }
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum CephEnum {
     CephInt {
-        min: Option<u32>,
-        max: Option<u32>,
-    }, // Optional: range=min[|max]
+        min: Option<i64>,
+        max: Option<i64>,
+    }, // Optional: range=min[|max], either end can be left out
     CephFloat {
         min: Option<f32>,
         max: Option<f32>,
     }, // Optional range
     CephString {
         goodchars: Option<String>,
+        badchars: Option<String>,
         allowed_repeats: AllowedRepeats,
-    }, // optional badchars
+    }, // optional goodchars and badchars
     CephSocketpath, // validation involves "is it S_ISSOCK"
     CephIPAddr, // v4 or v6 addr with optional port, syntax validated
     CephEntityAddr, // CephIPAddr + optional '/nonce'
@@ -525,190 +726,215 @@ pub enum CephEnum {
     CephFragment, // cephfs 'fragID': val/bits, val in hex 0xnnn, bits in dec
     CephUUID, // uuid in text matching Python uuid.UUID()
     CephPrefix, // special type assigned to literals
-    Unknown,
+    CephBool, // true or false
+    CephTimespan, // duration such as 30, 5m or 1h30m
+    CephPgidList, // list of CephPgid
+    CephArgtype, // generic argument that ceph_argparse doesn't validate further
+    Unknown, // type name is kept in CephType.extra
 }
 
 impl CephEnum {
-    fn validate_string(&self, param_name: &String, indent: String) -> String{
-        match self{
-            &CephEnum::CephInt{min, max}  => {
-                let mut range = String::from("'");
-                if min.is_some(){
-                    range.push_str(&format!("{}", min.unwrap()));
-                }
-                if max.is_some(){
-                    range.push_str(&format!("|{}", max.unwrap()));
-                }
-                range.push_str("'");
+    /// The type name used in the command tables
+    pub fn type_name(&self) -> &'static str {
+        match *self {
+            CephEnum::CephInt { .. } => "CephInt",
+            CephEnum::CephFloat { .. } => "CephFloat",
+            CephEnum::CephString { .. } => "CephString",
+            CephEnum::CephSocketpath => "CephSocketpath",
+            CephEnum::CephIPAddr => "CephIPAddr",
+            CephEnum::CephEntityAddr => "CephEntityAddr",
+            CephEnum::CephPoolname { .. } => "CephPoolname",
+            CephEnum::CephObjectname => "CephObjectname",
+            CephEnum::CephPgid => "CephPgid",
+            CephEnum::CephName => "CephName",
+            CephEnum::CephOsdName => "CephOsdName",
+            CephEnum::CephChoices { .. } => "CephChoices",
+            CephEnum::CephFilepath => "CephFilepath",
+            CephEnum::CephFragment => "CephFragment",
+            CephEnum::CephUUID => "CephUUID",
+            CephEnum::CephPrefix => "CephPrefix",
+            CephEnum::CephBool => "CephBool",
+            CephEnum::CephTimespan => "CephTimespan",
+            CephEnum::CephPgidList => "CephPgidList",
+            CephEnum::CephArgtype => "CephArgtype",
+            CephEnum::Unknown => "Unknown",
+        }
+    }
 
-                let mut validate = String::new();
-                validate.push_str(&format!("{}{}_validator = ceph_argparse.CephInt(range={})", indent, param_name, range));
-                validate.push_str(&format!("\n{}{}_validator.valid({})", indent, param_name, param_name));
+    /// Does this take a list of values rather than a single one?
+    pub fn is_many(&self) -> bool {
+        match self {
+            CephEnum::CephString { allowed_repeats, .. } => *allowed_repeats == AllowedRepeats::Many,
+            CephEnum::CephChoices { allowed_repeats, .. } => *allowed_repeats == AllowedRepeats::Many,
+            CephEnum::CephPoolname { allowed_repeats } => *allowed_repeats == Some(AllowedRepeats::Many),
+            &CephEnum::CephPgidList => true,
+            _ => false,
+        }
+    }
 
-                validate
+    /// Python checking a value of this type before it's sent.  A list is
+    /// checked an item at a time.
+    fn write_validation(&self, w: &mut CodeWriter, param_name: &str) {
+        let many = self.is_many();
+        match self{
+            &CephEnum::CephInt{min, max}  => {
+                // ceph_argparse can't take an upper bound without a lower one
+                let range = python_range(min, max, &i64::MIN.to_string());
+                write_validator(w, param_name, "CephInt", &[format!("range={}", range)], many);
             },
             &CephEnum::CephFloat{min, max} => {
-                let mut range = String::from("'");
-                if min.is_some(){
-                    range.push_str(&format!("{}", min.unwrap()));
-                }
-                if max.is_some(){
-                    range.push_str(&format!("|{}", max.unwrap()));
-                }
-                range.push_str("'");
-
-                let mut validate = String::new();
-                validate.push_str(&format!("{}{}_validator = ceph_argparse.CephFloat(range={})", indent, param_name, range));
-                validate.push_str(&format!("\n{}{}_validator.valid({})", indent, param_name, param_name));
-
-                validate
-            },
-            &CephEnum::CephString{ref goodchars, ref allowed_repeats} => {
-                let good = goodchars.clone().unwrap_or("".to_string());
-
-                let mut validate = String::new();
-                validate.push_str(&format!("{}{}_validator = ceph_argparse.CephString(goodchars=\"{}\")", indent, param_name, good));
-                validate.push_str(&format!("\n{}{}_validator.valid({})", indent, param_name, param_name));
-
-                validate
-            },
-            &CephEnum::CephSocketpath => {
-                let mut validate = String::new();
-                validate.push_str(&format!("{}{}_validator = ceph_argparse.CephSocketpath()", indent, param_name));
-                validate.push_str(&format!("\n{}{}_validator.valid({})", indent, param_name, param_name));
-
-                validate
-            },
-            &CephEnum::CephIPAddr => {
-                let mut validate = String::new();
-
-                validate.push_str(&format!("{}{}_validator = ceph_argparse.CephIPAddr()", indent, param_name));
-                validate.push_str(&format!("\n{}{}_validator.valid({})", indent, param_name, param_name));
-
-                validate
+                let range = python_range(min, max, "-inf");
+                write_validator(w, param_name, "CephFloat", &[format!("range={}", range)], many);
             },
-            &CephEnum::CephEntityAddr => {
-                let mut validate = String::new();
-
-                validate.push_str(&format!("{}{}_validator = ceph_argparse.CephEntityAddr()", indent, param_name));
-                validate.push_str(&format!("\n{}{}_validator.valid({})", indent, param_name, param_name));
-
-                validate
-            },
-            &CephEnum::CephPoolname{ref allowed_repeats} => {
-                let mut validate = String::new();
-                validate.push_str(&format!("{}if not isinstance({}, six.string_types):", indent, param_name));
-                validate.push_str(&format!("\n{}    raise TypeError(\"{} is not a String\")", indent, param_name));
-
-                validate
-            },
-            &CephEnum::CephObjectname => {
-                let mut validate = String::new();
-                validate.push_str(&format!("{}if not isinstance({}, six.string_types):", indent, param_name));
-                validate.push_str(&format!("\n{}    raise TypeError(\"{} is not a String\")", indent, param_name));
-
-                validate
-            },
-            &CephEnum::CephPgid => {
-                let mut validate = String::new();
-                validate.push_str(&format!("{}{}_validator = ceph_argparse.CephPgid()", indent, param_name));
-                validate.push_str(&format!("\n{}{}_validator.valid({})", indent, param_name, param_name));
-
-                validate
-            },
-            &CephEnum::CephName => {
-                let mut validate = String::new();
-                validate.push_str(&format!("{}{}_validator = ceph_argparse.CephName()", indent, param_name));
-                validate.push_str(&format!("\n{}{}_validator.valid({})", indent, param_name, param_name));
-
-                validate
-            },
-            &CephEnum::CephOsdName => {
-                let mut validate = String::new();
-                validate.push_str(&format!("{}{}_validator = ceph_argparse.CephOsdName()", indent, param_name));
-                validate.push_str(&format!("\n{}{}_validator.valid({})", indent, param_name, param_name));
-
-                validate
-            },
-            &CephEnum::CephChoices{ref choices, ref allowed_repeats} => {
-                let mut validate = String::new();
-
-                validate.push_str(&format!("{}{}_validator = ceph_argparse.CephChoices(strings=\"{}\")",
-                    indent,
-                    param_name,
-                    choices.join("|")
-                ));
-                validate.push_str(&format!("\n{}for s in {}:", indent, param_name));
-                validate.push_str(&format!("\n{}    {}_validator.valid(s)", indent, param_name));
-
-                validate
+            CephEnum::CephString{goodchars, badchars, ..} => {
+                // goodchars is a regex character class
+                let good = goodchars.as_ref().map_or(String::new(), |g| format!("[{}]", g));
+                write_validator(w, param_name, "CephString", &[format!("goodchars=\"{}\"", good)], many);
+                if let Some(bad) = badchars {
+                    let message = Json::String(format!("{} contains one of {}", param_name, bad)).to_string();
+                    let chars = if many {
+                        format!("c in {} for s in {} for c in s", Json::String(bad.clone()), param_name)
+                    } else {
+                        format!("c in {} for c in {}", Json::String(bad.clone()), param_name)
+                    };
+                    w.block(&format!("if any({}):", chars), |w| {
+                        w.call("raise ValueError", &[message], "");
+                    });
+                }
             },
-            &CephEnum::CephFilepath => {
-                let mut validate = String::new();
-                validate.push_str(&format!("{}{}_validator = ceph_argparse.CephFilepath()", indent, param_name));
-                validate.push_str(&format!("\n{}{}_validator.valid({})", indent, param_name, param_name));
-
-                validate
+            &CephEnum::CephSocketpath => write_validator(w, param_name, "CephSocketpath", &[], many),
+            &CephEnum::CephIPAddr => write_validator(w, param_name, "CephIPAddr", &[], many),
+            &CephEnum::CephEntityAddr => write_validator(w, param_name, "CephEntityAddr", &[], many),
+            &CephEnum::CephPoolname{..} | &CephEnum::CephObjectname => {
+                write_isinstance(w, param_name, "six.string_types", "String", many)
             },
-            &CephEnum::CephFragment => {
-                let mut validate = String::new();
-
-                validate.push_str(&format!("{}{}_validator = ceph_argparse.CephFragment()", indent, param_name));
-                validate.push_str(&format!("\n{}{}_validator.valid({})", indent, param_name, param_name));
-
-                validate
+            &CephEnum::CephPgid => write_validator(w, param_name, "CephPgid", &[], many),
+            &CephEnum::CephName => write_validator(w, param_name, "CephName", &[], many),
+            &CephEnum::CephOsdName => write_validator(w, param_name, "CephOsdName", &[], many),
+            CephEnum::CephChoices{choices, ..} => {
+                write_validator(w, param_name, "CephChoices", &[format!("strings=\"{}\"", choices.join("|"))], many);
             },
-            &CephEnum::CephUUID => {
-                let mut validate = String::new();
-                validate.push_str(&format!("{}{}_validator = ceph_argparse.CephUUID()", indent, param_name));
-                validate.push_str(&format!("\n{}{}_validator.valid({})", indent, param_name, param_name));
-
-                validate
+            &CephEnum::CephFilepath => write_validator(w, param_name, "CephFilepath", &[], many),
+            &CephEnum::CephFragment => write_validator(w, param_name, "CephFragment", &[], many),
+            &CephEnum::CephUUID => write_validator(w, param_name, "CephUUID", &[], many),
+            &CephEnum::CephPrefix => write_validator(w, param_name, "CephPrefix", &[], many),
+            &CephEnum::CephBool => write_isinstance(w, param_name, "bool", "bool", many),
+            &CephEnum::CephTimespan => {
+                write_isinstance(w, param_name, "six.string_types + six.integer_types", "timespan", many)
             },
-            &CephEnum::CephPrefix => {
-                let mut validate = String::new();
+            &CephEnum::CephPgidList => write_validator(w, param_name, "CephPgid", &[], many),
+            &CephEnum::CephArgtype => {},
+            &CephEnum::Unknown => {},
+        }
+    }
 
-                validate.push_str(&format!("{}{}_validator = ceph_argparse.CephPrefix()", indent, param_name));
-                validate.push_str(&format!("\n{}{}_validator.valid({})", indent, param_name, param_name));
+    /// A value the generated clients accept, for the generated tests.  None
+    /// if there's no value that passes everywhere, like a socket path.
+    pub fn sample(&self) -> Option<Json> {
+        let one = match self {
+            &CephEnum::CephInt { min, max } => Json::I64(min.or(max).unwrap_or(1)),
+            &CephEnum::CephFloat { min, max } => Json::F64(min.or(max).unwrap_or(1.0) as f64),
+            &CephEnum::CephString { .. } => Json::String("sample".to_string()),
+            &CephEnum::CephSocketpath | &CephEnum::CephPrefix => return None,
+            &CephEnum::CephIPAddr => Json::String("192.168.0.1".to_string()),
+            &CephEnum::CephEntityAddr => Json::String("192.168.0.1:6789/0".to_string()),
+            &CephEnum::CephPoolname { .. } => Json::String("rbd".to_string()),
+            &CephEnum::CephObjectname => Json::String("object".to_string()),
+            &CephEnum::CephPgid | &CephEnum::CephPgidList => Json::String("1.0".to_string()),
+            &CephEnum::CephName | &CephEnum::CephOsdName => Json::String("osd.0".to_string()),
+            CephEnum::CephChoices { choices, .. } => {
+                match choices.first() {
+                    Some(choice) => Json::String(choice.clone()),
+                    None => return None,
+                }
+            }
+            &CephEnum::CephFilepath => Json::String("/dev/null".to_string()),
+            &CephEnum::CephFragment => Json::String("0x0/0".to_string()),
+            &CephEnum::CephUUID => Json::String("5a8f6bd2-7c4e-4d3b-9f1a-2e6c8b0d4f17".to_string()),
+            &CephEnum::CephBool => Json::Boolean(true),
+            &CephEnum::CephTimespan => Json::String("1h".to_string()),
+            &CephEnum::CephArgtype | &CephEnum::Unknown => Json::String("value".to_string()),
+        };
+        Some(self.repeat(one))
+    }
 
-                validate
-            },
-            &CephEnum::Unknown => {
-                "".to_string()
+    /// A value the generated Python client rejects before sending anything.
+    /// None if nothing is checked.  Numbers and strings are only out of range
+    /// or contain a bad character so typed clients can use them too.
+    pub fn bad_sample(&self) -> Option<Json> {
+        let one = match self {
+            &CephEnum::CephInt { min, max } => {
+                min.and_then(|m| m.checked_sub(1))
+                    .or(max.and_then(|m| m.checked_add(1)))
+                    .map_or(Json::String("one".to_string()), Json::I64)
+            }
+            &CephEnum::CephFloat { min, max } => {
+                min.map(|m| m as f64 - 1.0)
+                    .or(max.map(|m| m as f64 + 1.0))
+                    .map_or(Json::String("one".to_string()), Json::F64)
+            }
+            CephEnum::CephString { goodchars, badchars, .. } => {
+                match (badchars.as_ref().and_then(|b| b.chars().next()), goodchars) {
+                    (Some(bad), _) => Json::String(format!("sample{}", bad)),
+                    (None, Some(good)) if !good.is_empty() => Json::String("bad value!".to_string()),
+                    _ => return None,
+                }
+            }
+            &CephEnum::CephSocketpath => Json::String("/dev/null".to_string()),
+            &CephEnum::CephPoolname { .. } | &CephEnum::CephObjectname => Json::I64(1),
+            &CephEnum::CephIPAddr | &CephEnum::CephEntityAddr | &CephEnum::CephPgid | &CephEnum::CephPgidList |
+            &CephEnum::CephName | &CephEnum::CephOsdName | &CephEnum::CephFragment | &CephEnum::CephUUID => {
+                Json::String("nope".to_string())
             }
+            &CephEnum::CephChoices { .. } => Json::String("not-a-choice".to_string()),
+            &CephEnum::CephFilepath => Json::String("/nonexistent/file".to_string()),
+            &CephEnum::CephBool => Json::String("yes".to_string()),
+            &CephEnum::CephTimespan => Json::F64(1.5),
+            &CephEnum::CephPrefix | &CephEnum::CephArgtype | &CephEnum::Unknown => return None,
+        };
+        Some(self.repeat(one))
+    }
+
+    /// value in a list if this takes many
+    fn repeat(&self, value: Json) -> Json {
+        if self.is_many() {
+            Json::Array(vec![value])
+        } else {
+            value
         }
     }
 
+    #[allow(clippy::inherent_to_string)]
     fn to_string(&self) -> String{
         match self{
             &CephEnum::CephInt{min, max}  => {
                 let mut out = String::from("int");
-                if min.is_some(){
-                    out.push_str(&format!(" min={}", min.unwrap()))
+                if let Some(min) = min {
+                    out.push_str(&format!(" min={}", min))
                 }
-                if max.is_some(){
-                    out.push_str(&format!(" max={}", max.unwrap()));
+                if let Some(max) = max {
+                    out.push_str(&format!(" max={}", max));
                 }
                 out
             },
             &CephEnum::CephFloat{min, max} => {
                 let mut out = String::from("float");
-                if min.is_some(){
-                    out.push_str(&format!(" min={}", min.unwrap()))
+                if let Some(min) = min {
+                    out.push_str(&format!(" min={}", min))
                 }
-                if max.is_some(){
-                    out.push_str(&format!(" max={}", max.unwrap()));
+                if let Some(max) = max {
+                    out.push_str(&format!(" max={}", max));
                 }
                 out
             }
-            &CephEnum::CephString{ref goodchars, ref allowed_repeats}  => {
+            CephEnum::CephString{goodchars, allowed_repeats, ..}  => {
                 let mut out = String::from("six.string_types");
 
                 if goodchars.is_some(){
                     let charset = goodchars.clone().unwrap();
                     out.push_str(" valid_characters=[");
                     out.push_str(&charset);
-                    out.push_str("]");
+                    out.push(']');
                 }
                 out.push_str(" allowed repeats=");
                 out.push_str(&allowed_repeats.clone().to_string());
@@ -718,7 +944,7 @@ impl CephEnum {
             &CephEnum::CephSocketpath => "socket".to_string(),
             &CephEnum::CephIPAddr => "v4 or v6 addr with optional port".to_string(),
             &CephEnum::CephEntityAddr => "CephIPAddr + optional '/nonce'".to_string(),
-            &CephEnum::CephPoolname{ref allowed_repeats} => {
+            CephEnum::CephPoolname{allowed_repeats} => {
                 let mut out = String::from("six.string_types");
                 if allowed_repeats.is_some(){
                     let repeats = allowed_repeats.clone().unwrap();
@@ -731,14 +957,14 @@ impl CephEnum {
             &CephEnum::CephPgid => "six.string_types".to_string(),
             &CephEnum::CephName => "six.string_types".to_string(),
             &CephEnum::CephOsdName => "six.string_types".to_string(),
-            &CephEnum::CephChoices{ref choices, ref allowed_repeats} => {
+            CephEnum::CephChoices{choices, allowed_repeats} => {
                 let mut out = String::from("list");
 
                 out.push_str(" valid_range=[");
                 //choices
                 let quoted_choices:Vec<String> = choices.iter().map(|s| format!("\"{}\"", s)).collect();
-                out.push_str(&quoted_choices.join(","));
-                out.push_str("]");
+                out.push_str(&quoted_choices.join(", "));
+                out.push(']');
 
                 out.push_str(" allowed repeats=");
                 out.push_str(&allowed_repeats.clone().to_string());
@@ -749,6 +975,10 @@ impl CephEnum {
             &CephEnum::CephFragment => "six.string_types".to_string(),
             &CephEnum::CephUUID => "uuid.UUID".to_string(),
             &CephEnum::CephPrefix => "".to_string(),
+            &CephEnum::CephBool => "bool".to_string(),
+            &CephEnum::CephTimespan => "six.string_types or int seconds".to_string(),
+            &CephEnum::CephPgidList => "list of pgids".to_string(),
+            &CephEnum::CephArgtype => "six.string_types".to_string(),
             &CephEnum::Unknown => "unknown".to_string(),
         }
     }
@@ -757,403 +987,282 @@ impl CephEnum {
 #[test]
 fn test_good_chars(){
     let x: &[u8] = &[];
-    let input = "goodchars=[A-Za-z0-9-_.],";
-    let result = good_chars(input.as_bytes());
-    assert_eq!(nom::IResult::Done(x, Some("A-Za-z0-9-_.".to_string())), result);
-
-    let input2 = "goodchars=[A-Za-z0-9-_.] ";
-    let result2 = good_chars(input2.as_bytes());
-    assert_eq!(nom::IResult::Done(x, Some("A-Za-z0-9-_.".to_string())), result2);
-
-    let input3 = "goodchars=[A-Za-z0-9-_.=]";
-    let result3 = good_chars(input3.as_bytes());
-    assert_eq!(nom::IResult::Done(x, Some("A-Za-z0-9-_.=".to_string())), result3);
-}
-
-named!(parse_good_chars<&[u8], Option<String> >,
-    chain!(
-        tag!("goodchars=[") ~
-        chars: map_res!(
-                take_until!("]"), from_utf8) ~
-        tag!("]") ~
-        call!(trailing_chars),
-        ||{
-            Some(chars.to_string())
-        }
-    )
-);
-
-fn good_chars(input: &[u8]) -> nom::IResult<&[u8], Option<String>>{
-    let chars = tag!(input, "goodchars=");
-    match chars{
-        nom::IResult::Done(_, _) => {
-            return parse_good_chars(input);
-        }
-        nom::IResult::Incomplete(_) => {
-            nom::IResult::Done(input, None)
-        }
-        nom::IResult::Error(_) => {
-            nom::IResult::Done(input, None)
-        }
-    }
-}
-
-fn trailing_chars(input: &[u8]) ->nom::IResult<&[u8], ()>{
-    //3 possible trailing chars either "," " " or "".  They all need to be handled
-    let comma = tag!(input,",");
-    match comma{
-        nom::IResult::Done(remaining, _) => {
-            //Found a comma, we're done
-            return nom::IResult::Done(remaining, ());
-        },
-        nom::IResult::Incomplete(_) => {
-            //Ran out of input.  We're done
-            return nom::IResult::Done(input, ());
-        },
-        nom::IResult::Error(_) => {
-            //Possibly a space?
-            let space = tag!(input, " ");
-            match space{
-                nom::IResult::Done(remaining, _) => {
-                    //Found a space, we're done
-                    return nom::IResult::Done(remaining, ());
-                }
-                nom::IResult::Incomplete(_) => {
-                    //Ran out of input.  We're done
-                    return nom::IResult::Done(input, ());
-                }
-                nom::IResult::Error(_) => {
-                    return nom::IResult::Done(input, ());
-                }
-            }
-        }
+    let input = "name=name,type=CephString,goodchars=[A-Za-z0-9-_.],";
+    let result = parse_param_map(input.as_bytes());
+    assert_eq!(Ok((x, ("name".to_string(), CephType::new(true, CephEnum::CephString {
+        goodchars: Some("A-Za-z0-9-_.".to_string()), badchars: None, allowed_repeats: AllowedRepeats::One })))), result);
+
+    let input2 = "name=name,type=CephString,goodchars=[A-Za-z0-9-_.=],n=N";
+    let result2 = parse_param_map(input2.as_bytes());
+    assert_eq!(Ok((x, ("name".to_string(), CephType::new(true, CephEnum::CephString {
+        goodchars: Some("A-Za-z0-9-_.=".to_string()), badchars: None, allowed_repeats: AllowedRepeats::Many })))), result2);
+}
+
+#[test]
+fn test_attributes(){
+    let x: &[u8] = &[];
+    let input = "name=caps,type=CephString,badchars=[/:],req=false,positional=false,allowempty=true,by=user";
+    let result = parse_param_map(input.as_bytes());
+    let mut expected = CephType::new(false, CephEnum::CephString {
+        goodchars: None, badchars: Some("/:".to_string()), allowed_repeats: AllowedRepeats::One });
+    expected.positional = false;
+    expected.allow_empty = true;
+    expected.extra.insert("by".to_string(), "user".to_string());
+    assert_eq!(Ok((x, ("caps".to_string(), expected))), result);
+
+    // strings= doesn't mean anything for a CephInt
+    let input2 = "name=id,type=CephInt,strings=a|b,req=maybe";
+    let result2 = parse_param_map(input2.as_bytes());
+    let mut expected2 = CephType::new(true, CephEnum::CephInt { min: None, max: None });
+    expected2.extra.insert("strings".to_string(), "a|b".to_string());
+    expected2.extra.insert("req".to_string(), "maybe".to_string());
+    assert_eq!(Ok((x, ("id".to_string(), expected2))), result2);
+
+    // Repeated keys override earlier ones and a bad value leaves the earlier
+    // one alone.  fuzz/corpus/parse_commands/malformed.h has this descriptor so
+    // `cargo +nightly fuzz run parse_commands` replays it through the printer.
+    let input3 = "name=id,type=CephString,type=CephInt,req=false,req=maybe";
+    let result3 = parse_param_map(input3.as_bytes());
+    let mut expected3 = CephType::new(false, CephEnum::CephInt { min: None, max: None });
+    expected3.extra.insert("req".to_string(), "maybe".to_string());
+    assert_eq!(Ok((x, ("id".to_string(), expected3))), result3);
+}
+
+/// Split the rest of a parameter descriptor into its key=value pairs the
+/// same way ceph_argparse does, on commas and then the first =.  A space
+/// ends the descriptor.
+fn attributes(input: &[u8]) -> IResult<&[u8], Vec<(String, String)>> {
+    let end = input.iter().position(|&c| c == b' ').unwrap_or(input.len());
+    let text = String::from_utf8_lossy(&input[..end]).into_owned();
+    let mut attrs = Vec::new();
+    for part in text.split(',').filter(|p| !p.is_empty()) {
+        let mut kv = part.splitn(2, '=');
+        let key = kv.next().unwrap_or("").to_string();
+        let value = kv.next().unwrap_or("").to_string();
+        attrs.push((key, value));
     }
+    Ok((&input[end..], attrs))
 }
 
-fn allowed(input: &[u8]) -> nom::IResult<&[u8], AllowedRepeats>{
-    if input.len() == 0{
-        return nom::IResult::Done(input, AllowedRepeats::One);
-    }else{
-        return allowed_repeats(input);
+/// Quoted range argument for the ceph_argparse validators.  lowest fills in
+/// for a missing lower bound.
+fn python_range<T: ::std::fmt::Display>(min: Option<T>, max: Option<T>, lowest: &str) -> String {
+    match (min, max) {
+        (None, None) => "''".to_string(),
+        (Some(min), None) => format!("'{}'", min),
+        (None, Some(max)) => format!("'{}|{}'", lowest, max),
+        (Some(min), Some(max)) => format!("'{}|{}'", min, max),
     }
 }
 
-fn req(input: &[u8]) -> nom::IResult<&[u8], bool>{
-    if input.len() == 0{
-        return nom::IResult::Done(input, true);
-    }else{
-        return parse_req(input);
+/// Build a ceph_argparse validator for param_name and check its value, or
+/// each of its values if many
+fn write_validator(w: &mut CodeWriter, param_name: &str, class: &str, args: &[String], many: bool) {
+    w.call(&format!("{}_validator = ceph_argparse.{}", param_name, class), args, "");
+    if many {
+        w.block(&format!("for s in {}:", param_name), |w| {
+            w.line(&format!("{}_validator.valid(s)", param_name));
+        });
+    } else {
+        w.call(&format!("{}_validator.valid", param_name), &[param_name.to_string()], "");
     }
 }
 
-named!(parse_req<&[u8], bool>,
-    map_res!(
-        map_res!(
-            chain!(
-                tag!("req=") ~
-                req: take_while!(is_alphabetic) ,
-                ||{
-                    req
-                }
-            ), from_utf8),
-        bool::from_str
-    )
-);
-
-fn u32_min_range(input: &[u8]) -> nom::IResult<&[u8], Option<u32>>{
-    let range = tag!(input, "range=");
-    match range{
-        nom::IResult::Done(remaining, _) => {
-            chain!(
-                remaining,
-                min: map_res!(
-                map_res!(
-                    take_while!(is_digit),
-                    from_utf8), u32::from_str) ~
-                call!(trailing_chars),
-                ||{
-                    Some(min)
-                }
-            )
-        }
-        nom::IResult::Incomplete(_) => {
-            nom::IResult::Done(input, None)
-        }
-        nom::IResult::Error(_) => {
-            nom::IResult::Done(input, None)
-        }
+/// Raise a TypeError unless param_name, or each of its values if many, is
+/// one of types
+fn write_isinstance(w: &mut CodeWriter, param_name: &str, types: &str, what: &str, many: bool) {
+    if many {
+        w.line(&format!("for s in {}:", param_name));
+        w.indent();
+    }
+    let value = if many { "s" } else { param_name };
+    w.call("if not isinstance", &[value.to_string(), types.to_string()], ":");
+    w.indent();
+    w.call("raise TypeError", &[format!("\"{} is not a {}\"", param_name, what)], "");
+    w.dedent();
+    if many {
+        w.dedent();
     }
 }
 
-fn u32_max_range(input: &[u8]) -> nom::IResult<&[u8], Option<u32>>{
-    let start = tag!(input, "|");
-    match start{
-        nom::IResult::Done(_, _) => {
-            return u32_max(input);
+fn parse_bool(key: &str, value: &str, extra: &mut BTreeMap<String, String>) -> Option<bool> {
+    match bool::from_str(value) {
+        Ok(b) => Some(b),
+        Err(_) => {
+            extra.insert(key.to_string(), value.to_string());
+            None
         }
-        nom::IResult::Incomplete(_) => {
-            nom::IResult::Done(input, None)
+    }
+}
+
+/// range=min[|max].  A range that doesn't parse as T is kept in extra.
+fn parse_range<T: FromStr>(range: Option<String>, extra: &mut BTreeMap<String, String>) -> (Option<T>, Option<T>) {
+    let range = match range {
+        Some(r) => r,
+        None => return (None, None),
+    };
+    let mut parts = range.splitn(2, '|');
+    let min = parts.next().unwrap_or("");
+    let max = parts.next().unwrap_or("");
+    let parse = |s: &str| -> Result<Option<T>, ()> {
+        if s.is_empty() {
+            Ok(None)
+        } else {
+            T::from_str(s).map(Some).map_err(|_| ())
         }
-        nom::IResult::Error(_) => {
-            nom::IResult::Done(input, None)
+    };
+    match (parse(min), parse(max)) {
+        (Ok(min), Ok(max)) => (min, max),
+        _ => {
+            extra.insert("range".to_string(), range.clone());
+            (None, None)
         }
     }
 }
 
-named!(u32_max<&[u8], Option<u32> >,
-    chain!(
-        tag!("|") ~
-        max: map_res!(
-                map_res!(
-                    take_while!(is_digit),
-                    from_utf8), u32::from_str
-            )? ~
-        call!(trailing_chars),
-        ||{
-            max
-        }
-    )
-);
+#[test]
+fn test_int_range(){
+    let x: &[u8] = &[];
+    let result = parse_param_map("name=id,type=CephInt,range=-1".as_bytes());
+    assert_eq!(Ok((x, ("id".to_string(), CephType::new(true, CephEnum::CephInt { min: Some(-1), max: None })))),
+               result);
+
+    let result2 = parse_param_map("name=size,type=CephInt,range=|17592186044416".as_bytes());
+    assert_eq!(Ok((x, ("size".to_string(),
+                                      CephType::new(true, CephEnum::CephInt { min: None, max: Some(17592186044416) })))),
+               result2);
+
+    assert_eq!("'-9223372036854775808|100'", python_range(None, Some(100), &i64::MIN.to_string()));
+    assert_eq!("'0'", python_range(Some(0), None, "-inf"));
+}
+
+#[test]
+fn test_newer_types(){
+    let x: &[u8] = &[];
+    let result = parse_param_map("name=yes_i_really_mean_it,type=CephBool,req=false".as_bytes());
+    assert_eq!(Ok((x, ("yes_i_really_mean_it".to_string(), CephType::new(false, CephEnum::CephBool)))), result);
+
+    let result2 = parse_param_map("name=pgid,type=CephPgidList".as_bytes());
+    assert_eq!(Ok((x, ("pgid".to_string(), CephType::new(true, CephEnum::CephPgidList)))), result2);
+
+    let result3 = parse_param_map("name=when,type=CephWhenever,req=false".as_bytes());
+    let mut expected = CephType::new(false, CephEnum::Unknown);
+    expected.extra.insert("type".to_string(), "CephWhenever".to_string());
+    assert_eq!(Ok((x, ("when".to_string(), expected))), result3);
+}
 
 #[test]
 fn test_float(){
     let x: &[u8] = &[];
     let input = "type=CephFloat,name=weight,range=0.0|1.0";
     let result = parse_param_map(input.as_bytes());
-    //println!("Result: {:?}", result);
     assert_eq!(
-        nom::IResult::Done(x,
+        Ok((x,
             (
                 "weight".to_string(),
-                CephType { req: true, variant: CephEnum::CephFloat { min: Some(0.0), max: Some(1.0) } }
-            )
-    ), result);
-}
-
-fn is_float(chr: u8) -> bool {
-    (chr >= 0x30 && chr <= 0x39) || chr == 0x2e
-}
-
-fn f32_min_range(input: &[u8]) -> nom::IResult<&[u8], Option<f32>>{
-    //println!("input: {:?}", input);
-    let range = tag!(input, "range=");
-    match range{
-        nom::IResult::Done(remaining, _) => {
-            chain!(
-                remaining,
-                min: map_res!(
-                map_res!(
-                    take_while!(is_float),
-                    from_utf8), f32::from_str) ~
-                call!(trailing_chars),
-                ||{
-                    Some(min)
-                }
+                CephType::new(true, CephEnum::CephFloat { min: Some(0.0), max: Some(1.0) })
             )
-        }
-        nom::IResult::Incomplete(_) => {
-            nom::IResult::Done(input, None)
-        }
-        nom::IResult::Error(_) => {
-            nom::IResult::Done(input, None)
-        }
-    }
-}
-
-named!(f32_max<&[u8], Option<f32> >,
-    chain!(
-        tag!("|") ~
-        max: map_res!(
-                map_res!(
-                    take_while!(is_float),
-                    from_utf8), f32::from_str
-            )? ~
-        call!(trailing_chars),
-        ||{
-            max
-        }
-    )
-);
+    )), result);
 
-fn f32_max_range(input: &[u8]) -> nom::IResult<&[u8], Option<f32>> {
-    //println!("input: {:?}", input);
-    let start = tag!(input, "|");
-    match start{
-        nom::IResult::Done(_, _) => {
-            return f32_max(input);
-        }
-        nom::IResult::Incomplete(_) => {
-            nom::IResult::Done(input, None)
-        }
-        nom::IResult::Error(_) => {
-            nom::IResult::Done(input, None)
-        }
-    }
+    let input2 = "name=delta,type=CephFloat,range=-1.5|1.5";
+    let result2 = parse_param_map(input2.as_bytes());
+    assert_eq!(
+        Ok((x,
+            ("delta".to_string(), CephType::new(true, CephEnum::CephFloat { min: Some(-1.5), max: Some(1.5) }))
+    )), result2);
 }
 
 #[test]
 fn test_choices(){
     let x: &[u8] = &[];
-    let input = "strings=replicated|erasure,";
-    let result = choices(input.as_bytes());
-    assert_eq!(nom::IResult::Done(x, vec!["replicated".to_string(), "erasure".to_string()]), result);
-
-    let input2 = "strings=--yes-i-really-really-mean-it,";
-    let result2 = choices(input2.as_bytes());
-    assert_eq!(nom::IResult::Done(x, vec!["--yes-i-really-really-mean-it".to_string()]), result2);
-
-    let input3 = "strings=unfound_objects_exist|degraded_pgs_exist";
-    let result3 = choices(input3.as_bytes());
-    assert_eq!(nom::IResult::Done(x, vec!["unfound_objects_exist".to_string(), "degraded_pgs_exist".to_string()]), result3);
-}
-
-named!(choices<&[u8], Vec<String> >,
-    chain!(
-        tag!("strings=") ~
-        choices: map_res!(
-                    alt!(
-                        take_until_and_consume!(",") |
-                        rest
-                ), from_utf8),
-        ||{
-            choices.split("|").map(|s: &str| s.to_string()).collect()
-        }
-    )
-);
-
-named!(quoted_string <&[u8], &str>,
-    map_res!(
-        chain!(
-            //space? ~
-            take_until!("\"") ~
-            tag!("\"") ~
-            s: take_until!("\",") ~
-            tag!("\"")~
-            tag!(",")?,
-            ||{
-                s
-            }
-        ), from_utf8
-    )
-);
-
-named!(parse_name <&[u8], &str>,
-    map_res!(
-        chain!(
-            tag!("name=") ~
-            s: take_until_and_consume!(",") ,
-            ||{
-                s
-            }
-        ), from_utf8
-    )
-);
+    let input = "name=pool_type,type=CephChoices,strings=replicated|erasure,";
+    let result = parse_param_map(input.as_bytes());
+    assert_eq!(Ok((x, ("pool_type".to_string(), CephType::new(true, CephEnum::CephChoices {
+        choices: vec!["replicated".to_string(), "erasure".to_string()], allowed_repeats: AllowedRepeats::One })))), result);
+
+    let input2 = "name=sure,type=CephChoices,strings=--yes-i-really-really-mean-it,req=false";
+    let result2 = parse_param_map(input2.as_bytes());
+    assert_eq!(Ok((x, ("sure".to_string(), CephType::new(false, CephEnum::CephChoices {
+        choices: vec!["--yes-i-really-really-mean-it".to_string()], allowed_repeats: AllowedRepeats::One })))), result2);
+}
+
+#[test]
+fn test_samples(){
+    let int = CephEnum::CephInt { min: Some(2), max: Some(8) };
+    assert_eq!(Some(Json::I64(2)), int.sample());
+    assert_eq!(Some(Json::I64(1)), int.bad_sample());
+    assert_eq!(Some(Json::String("one".to_string())), CephEnum::CephInt { min: None, max: None }.bad_sample());
+
+    let choices = CephEnum::CephChoices { choices: vec!["a".to_string(), "b".to_string()], allowed_repeats: AllowedRepeats::Many };
+    assert_eq!(Some(Json::Array(vec![Json::String("a".to_string())])), choices.sample());
+    assert_eq!(Some(Json::Array(vec![Json::String("not-a-choice".to_string())])), choices.bad_sample());
+
+    let string = CephEnum::CephString { goodchars: None, badchars: Some("/:".to_string()), allowed_repeats: AllowedRepeats::One };
+    assert_eq!(Some(Json::String("sample/".to_string())), string.bad_sample());
+    let string = CephEnum::CephString { goodchars: None, badchars: None, allowed_repeats: AllowedRepeats::One };
+    assert_eq!(None, string.bad_sample());
+
+    // Nothing is sure to be a socket so commands that need one go untested
+    assert_eq!(None, CephEnum::CephSocketpath.sample());
+}
+
+fn quoted_string(input: &[u8]) -> IResult<&[u8], &str> {
+    map_res(delimited(pair(take_until("\""), tag("\"")),
+                      take_until("\","),
+                      pair(tag("\""), opt(tag(",")))),
+            from_utf8)(input)
+}
+
+fn parse_name(input: &[u8]) -> IResult<&[u8], &str> {
+    map_res(preceded(tag("name="), terminated(take_until(","), tag(","))), from_utf8)(input)
+}
 
 #[test]
 fn check_type_parameter() {
     let x: &[u8] = &[];
     let input = "type=CephInt,";
     let result = parse_type(input.as_bytes());
-    assert_eq!(nom::IResult::Done(x, "CephInt".to_string()), result);
+    assert_eq!(Ok((x, "CephInt".to_string())), result);
 
     let input2 = "type=CephInt";
     let result2 = parse_type(input2.as_bytes());
-    assert_eq!(nom::IResult::Done(x, "CephInt".to_string()), result2);
-}
-
-named!(parse_type <&[u8], String>,
-    map!(
-    map_res!(
-        chain!(
-            tag!("type=") ~
-            s: dbg!(alt!(
-                take_until_and_consume!(",") |
-                take_until_and_consume!(" ") |
-                take_while!(is_alphabetic))),
-            ||{
-                s
-            }
-        ), from_utf8
-    ), str::to_string)
-);
+    assert_eq!(Ok((x, "CephInt".to_string())), result2);
+
+    // A bad type name has to come out the same whether or not another
+    // attribute follows it, and an empty one is still a type.  Both are in
+    // fuzz/corpus/parse_commands/malformed.h.
+    let input3 = "type=CephInt(";
+    let result3 = parse_type(input3.as_bytes());
+    assert_eq!(Ok((x, "CephInt(".to_string())), result3);
+
+    let input4 = "type=";
+    let result4 = parse_type(input4.as_bytes());
+    assert_eq!(Ok((x, "".to_string())), result4);
+}
 
-fn one_or_more(input: &[u8]) -> nom::IResult<&[u8], AllowedRepeats>{
-    let start = tag!(input, "n=");
-    match start{
-        nom::IResult::Done(_, _) => {
-            return allowed_repeats(input);
-        }
-        nom::IResult::Incomplete(_) => {
-            nom::IResult::Done(input, AllowedRepeats::One)
-        }
-        nom::IResult::Error(_) => {
-            nom::IResult::Done(input, AllowedRepeats::One)
-        }
-    }
+fn parse_type(input: &[u8]) -> IResult<&[u8], String> {
+    preceded(tag("type="), type_name)(input)
 }
 
-named!(allowed_repeats <&[u8], AllowedRepeats>,
-    map!(
-        map_res!(
-            chain!(
-                tag!("n=") ~
-                more: alt!(
-                    take_until_and_consume!(",") |
-                    take_while!(is_alphanumeric)),
-                ||{
-                    more
-                }
-            ), from_utf8
-        ), AllowedRepeats::from_str
-    )
-);
+/// The type name runs to the first comma, or a space if there's no comma,
+/// or the end of the descriptor.  It can be empty.
+fn type_name(input: &[u8]) -> IResult<&[u8], String> {
+    let end = input.iter()
+        .position(|&c| c == b',')
+        .or_else(|| input.iter().position(|&c| c == b' '))
+        .unwrap_or(input.len());
+    let ceph_type = String::from_utf8_lossy(&input[..end]).into_owned();
+    let next = if end < input.len() { end + 1 } else { end };
+    Ok((&input[next..], ceph_type))
+}
 
-fn parse_param_map(input: &[u8]) -> nom::IResult<&[u8], (String, CephType)> {
+pub fn parse_param_map(input: &[u8]) -> IResult<&[u8], (String, CephType)> {
     //A few of the Command's have a reversed type="",name="" which is unfortunate
     trace!("parse_param_map input: {:?}", String::from_utf8_lossy(input));
-    let name_first = tag!(input, "name=");
-    match name_first{
-        nom::IResult::Done(_,_) => {
-            //name="" is first.  Parse normally
-            chain!(
-                input,
-                name: parse_name ~
-                ceph_type: parse_type ~
-                ceph_struct: dbg!(call!(CephType::parse, ceph_type)) ,
-                ||{
-                    (name.to_string(), ceph_struct)
-                }
-            )
-        }
-        nom::IResult::Error(_) => {
-            //name="" is not first. Lets try type="" and see if that is first
-            let type_first = tag!(input, "type=");
-            match type_first{
-                nom::IResult::Done(_,_) => {
-                    chain!(
-                        input,
-                        ceph_type: parse_type ~
-                        name: parse_name ~
-                        ceph_struct: dbg!(call!(CephType::parse, ceph_type)) ,
-                        ||{
-                            (name.to_string(), ceph_struct)
-                        }
-                    )
-                }
-                nom::IResult::Error(e) => {
-                    //I don't know how to parse this
-                    return nom::IResult::Error(e);
-                }
-                nom::IResult::Incomplete(needed) =>{
-                    return nom::IResult::Incomplete(needed);
-                }
-            }
-        }
-        nom::IResult::Incomplete(needed) =>{
-            return nom::IResult::Incomplete(needed);
-        }
-    }
-
+    let (input, (name, ceph_type)) = alt((pair(parse_name, parse_type),
+                                          map(pair(parse_type, parse_name), |(t, n)| (n, t))))(input)?;
+    let (input, ceph_struct) = CephType::parse(input, ceph_type)?;
+    Ok((input, (name.to_string(), ceph_struct)))
 }
 
 #[test]
@@ -1161,50 +1270,32 @@ fn check_parse_param_map() {
     let x: &[u8] = &[];
     let input = "name=epoch,type=CephInt,range=0,req=false";
     let result = parse_param_map(input.as_bytes());
-    //println!("Result: {:?}", result);
 
     assert_eq!(
-        nom::IResult::Done(x,
+        Ok((x,
             (
                 "epoch".to_string(),
-                CephType { req: false, variant: CephEnum::CephInt { min: Some(0), max: None } }
+                CephType::new(false, CephEnum::CephInt { min: Some(0), max: None })
             )
-    ), result);
-}
-
-named!(quoted_avail_string <&[u8], &str>,
-    map_res!(
-        chain!(
-            space? ~
-            dbg!(take_until_and_consume!("\"")) ~
-            //dbg!(tag!("\"")) ~
-            s: dbg!(take_until!("\"")) ~
-            dbg!(tag!("\"")),
-            ||{
-                s
-            }
-        ), from_utf8
-    )
-);
-
-named!(module <&[u8], Module>,
-    map!(
-        chain!(
-            module_name: quoted_string,
-            ||{
-                module_name
-            }
-        ), Module::from_str
-    )
-);
+    )), result);
+}
+
+// A quoted field that isn't followed by a comma, like the last one in a
+// command that has no availability or flags
+fn quoted_field(input: &[u8]) -> IResult<&[u8], &str> {
+    map_res(preceded(blanks, delimited(tag("\""), take_until("\""), tag("\""))), from_utf8)(input)
+}
+
+fn module(input: &[u8]) -> IResult<&[u8], Module> {
+    map(quoted_string, Module::from_str)(input)
+}
 
 #[test]
 fn check_parse_flags() {
     let x: &[u8] = &[];
     let input = ", FLAG(NOFORWARD)|FLAG(DEPRECATED)";
     let result = flags(input.as_bytes());
-    println!("Result: {:?}", result);
-    assert_eq!(nom::IResult::Done(x, vec![Flag::NoForward, Flag::Deprecated]), result);
+    assert_eq!(Ok((x, vec![Flag::NoForward, Flag::Deprecated])), result);
 }
 
 #[test]
@@ -1213,8 +1304,7 @@ fn check_parse_flags_2() {
     let input = r#", \
         FLAG(NOFORWARD)|FLAG(DEPRECATED)"#;
     let result = flags(input.as_bytes());
-    println!("Result: {:?}", result);
-    assert_eq!(nom::IResult::Done(x, vec![Flag::NoForward, Flag::Deprecated]), result);
+    assert_eq!(Ok((x, vec![Flag::NoForward, Flag::Deprecated])), result);
 }
 
 #[test]
@@ -1223,20 +1313,17 @@ fn check_parse_flags_3() {
     let input = r#",
         FLAG(NOFORWARD)|FLAG(DEPRECATED)"#;
     let result = flags(input.as_bytes());
-    println!("Result: {:?}", result);
-    assert_eq!(nom::IResult::Done(x, vec![Flag::NoForward, Flag::Deprecated]), result);
+    assert_eq!(Ok((x, vec![Flag::NoForward, Flag::Deprecated])), result);
 }
 
-named!(flags<&[u8], Vec<Flag> >,
-    chain!(
-        tag!(",") ~
-        blanks ~
-        flags: separated_list!(tag!("|"), call!(flag)),
-        ||{
-            flags
-        }
-    )
-);
+pub fn flags(input: &[u8]) -> IResult<&[u8], Vec<Flag>> {
+    map(spelled_flags, |flags| flags.into_iter().map(|(flag, _)| flag).collect())(input)
+}
+
+/// Flags along with whether each was spelled without FLAG()
+fn spelled_flags(input: &[u8]) -> IResult<&[u8], Vec<(Flag, bool)>> {
+    preceded(pair(tag(","), blanks), separated_list0(tag("|"), flag))(input)
+}
 
 #[test]
 fn test_command_with_flag(){
@@ -1245,21 +1332,57 @@ fn test_command_with_flag(){
              "mon", "rw", "cli,rest", \
              FLAG(DEPRECATED))"#;
     let result = Command::parse(input.as_bytes());
-    //println!("Result: {:?}", result);
     assert_eq!(
-        nom::IResult::Done(x,
+        Ok((x,
             Command {
                 signature: Signature {
                     prefix: "scrub".to_string(),
                     parameters: HashMap::new(),
                     duplicate: false,
+                    renames: HashMap::new(),
+                    order: Vec::new(),
                 },
                 helpstring: "scrub the monitor stores (DEPRECATED)".to_string(),
                 module_name: Module::Mon,
                 permissions: Permissions { read: true, write: true, execute: false },
                 availability: Availability::Both,
-                flags: Some(vec![Flag::Deprecated])
-        }), result);
+                flags: Some(vec![Flag::Deprecated]),
+                target: Target::Mon,
+                syntax: Syntax::default(),
+        })), result);
+}
+
+#[test]
+fn test_command_without_availability(){
+    let x: &[u8] = &[];
+    let input = r#"COMMAND_WITH_FLAG("pg stat", "show placement group status.",
+	"pg", "r", FLAG(MGR))
+COMMAND("pg getmap", "get binary pg map to -o/stdout", "pg", "r")"#;
+    match parse_commands(input.as_bytes()) {
+        Ok((remaining, cmds)) => {
+            assert_eq!(x, remaining);
+            assert_eq!(2, cmds.len());
+            assert_eq!(Availability::Both, cmds[0].availability);
+            assert_eq!(Some(vec![Flag::Mgr]), cmds[0].flags);
+            assert_eq!(Permissions { read: true, write: false, execute: false }, cmds[1].permissions);
+            assert_eq!(None, cmds[1].flags);
+        }
+        other => panic!("Failed to parse: {:?}", other),
+    }
+}
+
+#[test]
+fn test_unparsed_parameter(){
+    // A descriptor that doesn't start with name= is warned about and left
+    // out rather than printed into whatever is being generated
+    let input = r#"COMMAND("osd x id=1,name=id,type=CephInt name=y,type=CephInt", "h", "osd", "r", "cli,rest")"#;
+    match parse_commands(input.as_bytes()) {
+        Ok((_, cmds)) => {
+            assert_eq!("osd x", cmds[0].signature.prefix);
+            assert_eq!(vec!["y".to_string()], cmds[0].signature.order);
+        }
+        other => panic!("Failed to parse: {:?}", other),
+    }
 }
 
 #[test]
@@ -1268,163 +1391,191 @@ fn test_command_with_flag_hammer(){
     let input = r#"COMMAND_WITH_FLAG("compact", "cause compaction of monitor's leveldb storage", \
              "mon", "rw", "cli,rest", NOFORWARD)"#;
     let result = Command::parse(input.as_bytes());
-    println!("Result: {:?}", result);
     assert_eq!(
-        nom::IResult::Done(x,
+        Ok((x,
             Command {
                 signature: Signature {
                     prefix: "compact".to_string(),
                     parameters: HashMap::new(),
                     duplicate: false,
+                    renames: HashMap::new(),
+                    order: Vec::new(),
                 },
                 helpstring: "cause compaction of monitor\'s leveldb storage".to_string(),
                 module_name: Module::Mon,
                 permissions: Permissions { read: true, write: true, execute: false },
                 availability: Availability::Both,
-                flags: Some(vec![Flag::NoForward])
-        }), result);
-}
-
-fn flag(input: &[u8]) -> nom::IResult<&[u8], Flag>{
-    let noforward = tag!(input, "NOFORWARD");
-    match noforward{
-        nom::IResult::Done(rest, _) => {
-            nom::IResult::Done(rest, Flag::NoForward)
-        }
-        nom::IResult::Incomplete(_) => {
-            chain!(input,
-                dbg!(take_until_and_consume!("FLAG(")) ~
-                flag: dbg_dmp!(map_res!(take_until_and_consume!(")"), from_utf8)),
-                ||{
-                    Flag::from_str(flag)
-                }
-            )
-        }
-        nom::IResult::Error(_) => {
-            chain!(input,
-                dbg!(take_until_and_consume!("FLAG(")) ~
-                flag: dbg_dmp!(map_res!(take_until_and_consume!(")"), from_utf8)),
-                ||{
-                    Flag::from_str(flag)
-                }
-            )
-        }
-    }
+                flags: Some(vec![Flag::NoForward]),
+                target: Target::Mon,
+                syntax: Syntax { availability: true, bare_noforward: true },
+        })), result);
 }
 
-named!(availability <&[u8], Availability>,
-    map!(
-        chain!(
-            availabity_string: dbg_dmp!(quoted_avail_string),
-            ||{
-                availabity_string
-            }
-        ), Availability::from_str
-    )
-);
-
-named!(permissions <&[u8], Permissions>,
-    map!(
-        chain!(
-            perms: quoted_string,
-            ||{
-                perms
-            }
-        ), Permissions::from_str
-    )
-);
-
-// Copied from: https://github.com/filipegoncalves/rust-config/blob/master/src/parser.rs
-named!(blanks,
-       chain!(
-           many0!(
-               alt!(
-                   multispace |
-                   comment_one_line |
-                   comment_block |
-                   continue_line
-               )),
-           || { &b""[..] }));
-
-named!(continue_line,
-    tag!("\\\n")
-);
+#[test]
+fn check_flags_stop_at_junk() {
+    // Anything but another FLAG(..) after a | used to be skipped over until
+    // the next FLAG( even if that was in a later command
+    let input = ", FLAG(NOFORWARD)|garbage FLAG(MGR)";
+    let rest: &[u8] = b"|garbage FLAG(MGR)";
+    assert_eq!(Ok((rest, vec![Flag::NoForward])), flags(input.as_bytes()));
+
+    let input = r#"COMMAND_WITH_FLAG("a", "h", "mon", "r", "cli", FLAG(NOFORWARD)|bogus)
+COMMAND_WITH_FLAG("b", "h", "mon", "r", "cli", FLAG(DEPRECATED))
+"#;
+    assert_eq!(Ok((input.as_bytes(), Vec::new())), parse_commands(input.as_bytes()));
+    assert_eq!(Err("t:1: failed to parse \"COMMAND_WITH_FLAG(\\\"a\\\", \\\"h\\\", \\\"mon\\\", \\\"r\\\", \\\"cli\\\", \
+                    FLAG(NOFORWARD)|bogus)\"".to_string()),
+               ::parse_input("t", input.as_bytes()));
+}
+
+#[test]
+fn check_flag_without_close() {
+    // A flag name runs to the next ) so an unclosed one must not eat the
+    // following command
+    let input = r#"COMMAND_WITH_FLAG("a", "h", "mon", "r", "cli", FLAG(NOFORWARD)
+COMMAND_WITH_FLAG("b", "h", "mon", "r", "cli", FLAG(DEPRECATED))
+"#;
+    assert_eq!(Ok((input.as_bytes(), Vec::new())), parse_commands(input.as_bytes()));
+}
+
+fn flag(input: &[u8]) -> IResult<&[u8], (Flag, bool)> {
+    alt((// Hammer has a bare NOFORWARD without the FLAG() around it
+         value((Flag::NoForward, true), tag("NOFORWARD")),
+         map(delimited(pair(blanks, tag("FLAG(")), map_res(take_while(is_flag_char), from_utf8), tag(")")),
+             |name| (Flag::from_str(name), false))))(input)
+}
+
+fn is_flag_char(c: u8) -> bool {
+    c.is_ascii_uppercase() || c == b'_'
+}
+
+// Nautilus dropped the availability column so it's optional
+fn availability(input: &[u8]) -> IResult<&[u8], Availability> {
+    map(preceded(pair(tag(","), blanks), quoted_field), Availability::from_str)(input)
+}
+
+fn permissions(input: &[u8]) -> IResult<&[u8], Permissions> {
+    map(quoted_field, Permissions::from_str)(input)
+}
+
+// Based on: https://github.com/filipegoncalves/rust-config/blob/master/src/parser.rs
+fn blanks(input: &[u8]) -> IResult<&[u8], &[u8]> {
+    recognize(many0(alt((multispace1, comment_one_line, comment_block, continue_line))))(input)
+}
+
+fn continue_line(input: &[u8]) -> IResult<&[u8], &[u8]> {
+    tag("\\\n")(input)
+}
 
 // Auxiliary parser to ignore newlines
 // NOTE: In some cases, this parser is combined with others that use `not_line_ending`
 //       However, `not_line_ending` won't match `\u{2028}` or `\u{2029}`
-// Copied from: https://github.com/filipegoncalves/rust-config/blob/master/src/parser.rs
-named!(eol,
-       alt!(tag!("\n") | tag!("\r\n") | tag!("\u{2028}") | tag!("\u{2029}")));
+// Based on: https://github.com/filipegoncalves/rust-config/blob/master/src/parser.rs
+fn eol(input: &[u8]) -> IResult<&[u8], &[u8]> {
+    alt((tag("\n"), tag("\r\n"), tag("\u{2028}"), tag("\u{2029}")))(input)
+}
 
 // Auxiliary parser to ignore one-line comments
-// Copied from: https://github.com/filipegoncalves/rust-config/blob/master/src/parser.rs
-named!(comment_one_line,
-       chain!(
-           alt!(tag!("//") | tag!("#")) ~
-           not_line_ending? ~
-           alt!(eof | eol),
-           || { &b""[..] }));
+// Based on: https://github.com/filipegoncalves/rust-config/blob/master/src/parser.rs
+fn comment_one_line(input: &[u8]) -> IResult<&[u8], &[u8]> {
+    recognize(tuple((alt((tag("//"), tag("#"))), opt(not_line_ending), alt((eof, eol)))))(input)
+}
 
 // Auxiliary parser to ignore block comments
-// Copied from: https://github.com/filipegoncalves/rust-config/blob/master/src/parser.rs
-named!(comment_block,
-       chain!(
-           tag!("/*") ~
-           take_until_and_consume!(&b"*/"[..]),
-           || { &b""[..] }));
-
-//Generate parameter list from a HashMap with optional parameters at the end
-fn generate_param_list(params: &HashMap<String, CephType>)->String{
+// Based on: https://github.com/filipegoncalves/rust-config/blob/master/src/parser.rs
+fn comment_block(input: &[u8]) -> IResult<&[u8], &[u8]> {
+    recognize(tuple((tag("/*"), take_until("*/"), tag("*/"))))(input)
+}
+
+#[test]
+fn test_join_literals(){
+    assert_eq!("show cluster health", join_literals("show cluster health"));
+    assert_eq!("check whether mon(s) can be safely stopped without reducing immediate availability",
+               join_literals("check whether mon(s) can be safely stopped without reducing immediate \"\n\t\"availability"));
+    assert_eq!("a b c", join_literals("a \" \\\n  \"b \"\\\n\"c"));
+    assert_eq!("the \\\"first\\\" one", join_literals("the \\\"first\\\" one"));
+}
+
+/// C joins string literals that are only separated by whitespace, so a long
+/// helpstring can be split over several lines.  Do the same.
+fn join_literals(s: &str) -> String {
+    let mut out = String::new();
+    let mut chars = s.chars().peekable();
+    let mut escaped = false;
+    while let Some(c) = chars.next() {
+        if c == '"' && !escaped {
+            // Skip to the opening quote of the next literal
+            while let Some(&next) = chars.peek() {
+                if next.is_whitespace() || next == '\\' {
+                    chars.next();
+                } else {
+                    break;
+                }
+            }
+            if chars.peek() == Some(&'"') {
+                chars.next();
+            }
+            continue;
+        }
+        escaped = c == '\\' && !escaped;
+        out.push(c);
+    }
+    out
+}
+
+/// Python parameters for a signature with the optional ones at the end
+fn python_params(signature: &Signature) -> Vec<String> {
     let mut optional_params: Vec<String> = Vec::new();
     let mut mandatory_params: Vec<String> = Vec::new();
-    let mut output = String::new();
 
-    for (key, ceph_type) in params{
+    for (key, ceph_type) in signature.ordered_parameters().into_iter(){
         if ceph_type.req{
-            mandatory_params.push(key.clone());
+            mandatory_params.push(python_field_name(signature.api_name(key)));
         }else{
             //Optional parameter
-            optional_params.push(format!("{}=None", key));
+            optional_params.push(format!("{}=None", python_field_name(signature.api_name(key))));
         }
     }
     trace!("mandatory_params: {:?}", mandatory_params);
     trace!("optional_params: {:?}", optional_params);
-    if mandatory_params.len() >0 {
-        output.push_str(",");
-        output.push_str(&mandatory_params.join(","));
-    }
-    if optional_params.len() > 0{
-        output.push_str(",");
-        output.push_str(&optional_params.join(","));
-    }
-    trace!("generate_param_list output: {:?}", output);
-    output
-}
-
-/// Wraps a string at 80 chars or less by splitting space and then adding the chunks together
-fn wrap_string(s: &String)->String{
-    let mut count: usize = 0;
-    let mut output: Vec<String> = Vec::new();
-    let parts: Vec<&str> = s.split_whitespace().collect();
-
-    for part in parts{
-        count +=  part.chars().count();
-        if count >= 50{
-            output.push("\n       ".to_string());
-            output.push(part.to_string());
-            count = 0;
-        }else{
-            output.push(part.to_string());
-        }
-    }
-    //TODO: This inserts trailing spaces.  I hate pep8
-    output.join(" ")
+    mandatory_params.extend(optional_params);
+    mandatory_params
+}
+
+#[test]
+fn test_supports_json(){
+    let mut params = HashMap::new();
+    params.insert("format".to_string(), CephType::new(false, CephEnum::CephChoices {
+        choices: vec!["json".to_string(), "plain".to_string()], allowed_repeats: AllowedRepeats::One }));
+    let mut cmd = Command {
+        signature: Signature { prefix: "osd tree".to_string(), duplicate: false, parameters: HashMap::new(), renames: HashMap::new(), order: Vec::new() },
+        helpstring: "print OSD tree".to_string(),
+        module_name: Module::Osd,
+        permissions: Permissions { read: true, write: false, execute: false },
+        availability: Availability::Both,
+        flags: None,
+        target: Target::Mon,
+        syntax: Syntax::default(),
+    };
+    assert!(cmd.supports_json());
+    let python = cmd.to_python(&GeneratorOptions { json_output: true, schemas: SchemaRegistry::default(), release: None });
+    assert!(python.contains("cmd['format'] = 'json'"));
+    assert!(python.contains("return decode_json_result(cmd, outbuf)"));
+    assert!(!cmd.to_python(&GeneratorOptions::default()).contains("decode_json_result"));
+
+    cmd.permissions.write = true;
+    assert!(!cmd.supports_json());
+    cmd.signature.parameters = params;
+    assert!(cmd.supports_json());
+    let python = cmd.to_python(&GeneratorOptions { json_output: true, schemas: SchemaRegistry::default(), release: None });
+    assert!(!python.contains("cmd['format'] = 'json'"));
+    // format=None still gets json back, so the output can be decoded
+    assert!(python.contains("cmd.setdefault('format', 'json')"));
+    assert!(python.contains("return decode_json_result(cmd, outbuf)"));
 }
 
 #[test]
 fn test_mds(){
-    let x: &[u8] = &[];
     let input = r#"COMMAND("mds set " \
 	"name=var,type=CephChoices,strings=max_mds|max_file_size"
 	"|allow_new_snaps|inline_data|allow_multimds|allow_dirfrags " \
@@ -1432,18 +1583,75 @@ fn test_mds(){
 	"name=confirm,type=CephString,req=false",			\
 	"set mds parameter <var> to <val>", "mds", "rw", "cli,rest")"#;
 
-    let result = parse_commands(input.as_bytes());
-    println!("test_mds: {:?}", result);
+    let (rest, cmds) = parse_commands(input.as_bytes()).unwrap();
+    assert!(rest.is_empty());
+    assert_eq!(1, cmds.len());
+    let cmd = &cmds[0];
+    assert_eq!("mds set", cmd.signature.prefix);
+    assert_eq!(vec!["var", "val", "confirm"], cmd.signature.order);
+    match cmd.signature.parameters["var"].variant {
+        CephEnum::CephChoices { ref choices, .. } => {
+            assert_eq!(vec!["max_mds", "max_file_size", "allow_new_snaps", "inline_data", "allow_multimds",
+                            "allow_dirfrags"],
+                       *choices)
+        }
+        ref other => panic!("var should be CephChoices, got {:?}", other),
+    }
+    assert!(cmd.signature.parameters["val"].req);
+    assert!(!cmd.signature.parameters["confirm"].req);
+    assert_eq!("set mds parameter <var> to <val>", cmd.helpstring);
+    assert_eq!(Module::Mds, cmd.module_name);
+    assert_eq!(Permissions { read: true, write: true, execute: false }, cmd.permissions);
+    assert_eq!(Availability::Both, cmd.availability);
 }
 
 #[test]
 fn test_multi_command(){
-    let x: &[u8] = &[];
     let input = r#"COMMAND("pg getmap", "get binary pg map to -o/stdout", "pg", "r", "cli,rest")
 COMMAND("pg send_pg_creates", "trigger pg creates to be issued",\
         "pg", "rw", "cli,rest")"#;
-    let result = parse_commands(input.as_bytes());
-    println!("test_multi_command: {:?}", result);
+    let (rest, cmds) = parse_commands(input.as_bytes()).unwrap();
+    assert!(rest.is_empty());
+    assert_eq!(2, cmds.len());
+    assert_eq!("pg getmap", cmds[0].signature.prefix);
+    assert!(cmds[0].signature.parameters.is_empty());
+    assert_eq!("get binary pg map to -o/stdout", cmds[0].helpstring);
+    assert_eq!(Permissions { read: true, write: false, execute: false }, cmds[0].permissions);
+    assert_eq!("pg send_pg_creates", cmds[1].signature.prefix);
+    assert_eq!("trigger pg creates to be issued", cmds[1].helpstring);
+    assert_eq!(Permissions { read: true, write: true, execute: false }, cmds[1].permissions);
+    for cmd in cmds.iter() {
+        assert_eq!(Module::Pg, cmd.module_name);
+        assert_eq!(Availability::Both, cmd.availability);
+    }
+}
+
+/// Knobs shared by the code generators
+#[derive(Clone, Debug, Default)]
+pub struct GeneratorOptions {
+    /// Send format=json to commands that support it and return the decoded result
+    pub json_output: bool,
+    /// Response shapes.  Commands with a schema return typed models instead of a dict
+    pub schemas: SchemaRegistry,
+    /// The Ceph release the commands come from, by name or version number
+    pub release: Option<String>,
+}
+
+/// How a command was written in its header.  The syntax changed between
+/// releases and print writes a command back the way it was read.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Syntax {
+    /// The availability column.  Nautilus dropped it, and a command without
+    /// one is available everywhere.
+    pub availability: bool,
+    /// Hammer's NOFORWARD without FLAG() around it
+    pub bare_noforward: bool,
+}
+
+impl Default for Syntax {
+    fn default() -> Syntax {
+        Syntax { availability: true, bare_noforward: false }
+    }
 }
 
 // COMMAND(signature, helpstring, modulename, req perms, availability)
@@ -1455,129 +1663,583 @@ pub struct Command {
     pub permissions: Permissions,
     pub availability: Availability,
     pub flags: Option<Vec<Flag>>,
+    pub target: Target,
+    pub syntax: Syntax,
 }
 
 impl Command {
-    fn parse(input: & [u8]) -> nom::IResult<&[u8], Self> {
+    fn parse(input: &[u8]) -> IResult<&[u8], Self> {
         trace!("Input to Command: {}", String::from_utf8_lossy(input));
-        chain!(
-            input,
-                dbg!(many0!(blanks)) ~
-                dbg!(
-                    alt!(
-                        tag!("COMMAND(")
-                        | tag!("COMMAND_WITH_FLAG(")
-                    )
-                ) ~
-                signature: dbg_dmp!(quoted_string) ~
-                helpstring: dbg_dmp!(quoted_string) ~
-                blanks ~
-                module_name: dbg_dmp!(module) ~
-                permissions: dbg_dmp!(permissions) ~
-                availability: dbg_dmp!(availability) ~
-                flags: opt!(flags)~
-                dbg_dmp!(tag!(")"))~
-                blanks,
-            ||{
-                Command{
+        map(tuple((blanks,
+                   alt((tag("COMMAND("), tag("COMMAND_WITH_FLAG("))),
+                   quoted_string,
+                   quoted_string,
+                   blanks,
+                   module,
+                   permissions,
+                   opt(availability),
+                   opt(spelled_flags),
+                   tag(")"),
+                   blanks)),
+            |(_, _, signature, helpstring, _, module_name, permissions, availability, flags, _, _)| {
+                let syntax = Syntax {
+                    availability: availability.is_some(),
+                    bare_noforward: flags.iter().flatten().any(|&(_, bare)| bare),
+                };
+                Command {
                     signature: Signature::parse(signature),
-                    helpstring: helpstring.to_string(),
-                    module_name: module_name,
-                    permissions: permissions,
-                    availability: availability,
-                    flags: flags,
+                    helpstring: join_literals(helpstring),
+                    module_name,
+                    permissions,
+                    availability: availability.unwrap_or(Availability::Both),
+                    flags: flags.map(|f| f.into_iter().map(|(flag, _)| flag).collect()),
+                    target: Target::Mon,
+                    syntax,
                 }
+            })(input)
+    }
+
+    /// Does this command accept format=json?  Either it declares a format parameter
+    /// with json as a choice or it is a read only command, which the monitors let
+    /// format their output.
+    pub fn supports_json(&self) -> bool {
+        match self.signature.parameters.get("format") {
+            Some(&CephType{variant: CephEnum::CephChoices{ref choices, ..}, ..}) => {
+                choices.iter().any(|c| c == "json")
             }
-        )
+            Some(_) => false,
+            None => self.permissions.is_read_only(),
+        }
     }
 
-    pub fn to_string(&self) -> String {
-        let mut output = String::new();
-        let prefix_method_name: String;
+    /// Can every required parameter be given a sample value?  The generated
+    /// tests leave out commands that can't.
+    pub fn samplable(&self) -> bool {
+        self.signature.parameters.values().all(|t| !t.req || t.variant.sample().is_some())
+    }
 
+    /// Name of the generated method for this command
+    pub fn method_name(&self) -> String {
         //Add a suffix if this function is a duplicate of another function
         match self.signature.duplicate{
-            true => {
-                prefix_method_name = format!("{}_2", self.signature.prefix.replace(" ", "_").replace("-", "_"));
-            },
-            false => {
-                prefix_method_name = self.signature.prefix.replace(" ", "_").replace("-", "_");
-            },
-        };
-        let num_of_params = self.signature.parameters.len();
+            true => format!("{}_2", self.signature.prefix.replace(" ", "_").replace("-", "_")),
+            false => self.signature.prefix.replace(" ", "_").replace("-", "_"),
+        }
+    }
 
-        output.push_str(&format!("    def {}(self", prefix_method_name));
-        output.push_str(&generate_param_list(&self.signature.parameters));
-        output.push_str("):\n");
+    /// The method for this command indented to sit in a class
+    pub fn to_python(&self, options: &GeneratorOptions) -> String {
+        let mut w = CodeWriter::python();
+        w.indent();
+        self.write_python(&mut w, options);
+        w.into_string()
+    }
+
+    pub fn write_python(&self, w: &mut CodeWriter, options: &GeneratorOptions) {
+        let decode_json = options.json_output && self.supports_json();
+        let schema = options.schemas.get(&self.signature.prefix);
+        let run_args = vec!["self.rados_config_file".to_string(),
+                            "cmd".to_string(),
+                            "inbuf=''".to_string(),
+                            format!("target={}", self.target.to_python())];
+
+        let mut params = vec!["self".to_string()];
+        if let Some(id_param) = self.target.id_param(){
+            params.push(id_param.to_string());
+        }
+        params.extend(python_params(&self.signature));
+        w.call(&format!("def {}", self.method_name()), &params, ":");
+        w.indent();
 
         //Help strings
-        output.push_str("        \"\"\"\n");
-        output.push_str("        ");
-        output.push_str(&wrap_string(&self.helpstring));
-        output.push_str("\n\n");
-        let params: Vec<String> = self.signature.parameters.iter()
-                            .map(|(key, ceph_type)| format!("        :param {}: {}", key, ceph_type.variant.to_string()))
-                            .collect();
-        output.push_str(&params.join("\n"));
-        output.push_str("\n        :return: (string outbuf, string outs)");
-        output.push_str("\n        :raise CephError: Raises CephError on command execution errors");
-        output.push_str("\n        :raise rados.Error: Raises on rados errors");
-        output.push_str("\n        \"\"\"\n\n");
-        //Help strings
+        w.line("\"\"\"");
+        w.fill(&self.helpstring, 0);
+        w.blank();
+        if let Some(id_param) = self.target.id_param(){
+            w.fill(&format!(":param {}: id of the daemon to send the command to", id_param), 4);
+        }
+        for (key, ceph_type) in self.signature.ordered_parameters().into_iter(){
+            w.fill(&format!(":param {}: {}", python_field_name(self.signature.api_name(key)), ceph_type.variant.to_string()), 4);
+        }
+        if decode_json{
+            match schema{
+                Some(shape) => w.fill(&format!(":return: {}", shape.python_type()), 4),
+                None => w.line(":return: dict decoded from the json output"),
+            }
+        }else{
+            w.line(":return: (string outbuf, string outs)");
+        }
+        w.line(":raise CephError: Raises CephError on command execution errors");
+        w.line(":raise rados.Error: Raises on rados errors");
+        w.line("\"\"\"");
+        w.blank();
 
         //Validate the parameters
-        for (key, ceph_type) in self.signature.parameters.iter(){
+        for (key, ceph_type) in self.signature.ordered_parameters().into_iter(){
             if ceph_type.req{
-                let validate_string = ceph_type.variant.validate_string(&key, String::from("        "));
-                output.push_str(&format!("{}\n", validate_string));
+                ceph_type.variant.write_validation(w, &python_field_name(self.signature.api_name(key)));
             }
         }
 
-        //Create the cmd dictionary
-        if num_of_params == 0{
-            output.push_str(&format!("        cmd={{'prefix': '{}'}}", self.signature.prefix));
-        }else{
-            output.push_str(&format!("        cmd={{'prefix': '{}'", self.signature.prefix));
-        }
-
-        //Mandatory parameters
-        for (key, ceph_type) in self.signature.parameters.iter(){
+        //Create the cmd dictionary with the mandatory parameters
+        let mut entries = vec![format!("'prefix': '{}'", self.signature.prefix)];
+        for (key, ceph_type) in self.signature.ordered_parameters().into_iter(){
             if ceph_type.req{
-                output.push_str(&format!(", '{}':{}", key, key));
+                entries.push(format!("'{}': {}", key, python_field_name(self.signature.api_name(key))));
             }
         }
-        if num_of_params > 0{
-            output.push_str("\n        }");
-        }
+        w.collection("cmd = {", &entries, "}");
 
         //Optional parameters with checks to see if they are used
-        for (key, ceph_type) in self.signature.parameters.iter(){
+        for (key, ceph_type) in self.signature.ordered_parameters().into_iter(){
             if !ceph_type.req{
-                let validate_string = ceph_type.variant.validate_string(&key, String::from("            "));
-                output.push_str("\n");
-                output.push_str(&format!("\n        if {} is not None:", key));
-                output.push_str(&format!("\n{}", validate_string));
-                output.push_str(&format!("\n            cmd['{}']={}", key, key));
+                let api_name = python_field_name(self.signature.api_name(key));
+                w.blank();
+                w.block(&format!("if {} is not None:", api_name), |w| {
+                    ceph_type.variant.write_validation(w, &api_name);
+                    w.assign(&format!("cmd['{}']", key), &api_name);
+                });
+            }
+        }
+
+        //Ask for json unless the caller picked another format
+        if decode_json{
+            if self.signature.parameters.contains_key("format"){
+                w.line("cmd.setdefault('format', 'json')");
+            }else{
+                w.line("cmd['format'] = 'json'");
             }
         }
 
         //Connect to rados and run the command
-        output.push_str("\n        return run_ceph_command(self.rados_config_file, cmd, inbuf='')");
-        output.push_str("\n");
+        if decode_json{
+            w.call("outbuf, outs = run_ceph_command", &run_args, "");
+            match schema{
+                Some(shape) => {
+                    w.line("data = decode_json_result(cmd, outbuf)");
+                    w.line(&format!("return {}", shape.python_decode("data")));
+                }
+                None => w.line("return decode_json_result(cmd, outbuf)"),
+            }
+        }else{
+            w.call("return run_ceph_command", &run_args, "");
+        }
+        w.dedent();
+    }
+}
+
+#[test]
+fn test_group_by_module() {
+    let cmds = ::test_fixtures::commands();
+    let grouped = group_by_module(&cmds, &Module::all());
+    assert_eq!(vec![Module::Pg, Module::Mds, Module::Osd, Module::Mgr],
+               grouped.iter().map(|g| g.0.clone()).collect::<Vec<Module>>());
+    assert_eq!("osd pool create", grouped[2].1[0].signature.prefix);
+
+    let mut modules = Module::all();
+    modules.push(Module::Unknown);
+    let grouped = group_by_module(&cmds, &modules);
+    assert_eq!(vec![Module::Pg, Module::Mds, Module::Osd, Module::Mgr, Module::Unknown],
+               grouped.iter().map(|g| g.0.clone()).collect::<Vec<Module>>());
+    assert_eq!("config set", grouped[4].1[0].signature.prefix);
+}
+
+/// The commands of each of modules that has any, in that order and with
+/// duplicate names renamed.  Commands from any other module are left out with
+/// a warning.
+pub fn group_by_module(cmds: &[Command], modules: &[Module]) -> Vec<(Module, Vec<Command>)> {
+    for cmd in cmds.iter().filter(|c| !modules.contains(&c.module_name)) {
+        warn!("Leaving out {:?}: it's in a module that isn't generated", cmd.signature.prefix);
+    }
+    let mut grouped = Vec::new();
+    for module in modules {
+        let mut module_commands: Vec<Command> = cmds.iter().filter(|&c| c.module_name == *module).cloned().collect();
+        rename_duplicate_functions(&mut module_commands);
+        if !module_commands.is_empty() {
+            grouped.push((module.clone(), module_commands));
+        }
+    }
+    grouped
+}
+
+/// This tries to find ceph_api commands that are going to have duplicate names
+/// and rename them with a _2 or _3 suffix.  Python doesn't allow method overloading
+/// so this is a hacky workaround.
+pub fn rename_duplicate_functions(v: &mut [Command]) {
+    let mut h = HashSet::new();
+
+    for i in v.iter_mut() {
+        let prefix_method_name = i.signature.prefix.replace(" ", "_").replace("-", "_");
+        let already_present = h.insert(prefix_method_name.clone());
+
+        if !already_present {
+            // rename
+            i.signature.duplicate = true;
+        }
+    }
+}
+
+pub fn parse_commands(input: &[u8]) -> IResult<&[u8], Vec<Command>> {
+    many0(Command::parse)(input)
+}
+
+#[cfg(test)]
+/// Hands out a few bytes per read so commands get split across reads
+pub struct Trickle<'a>(pub &'a [u8]);
+
+#[cfg(test)]
+impl<'a> Read for Trickle<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = ::std::cmp::min(3, ::std::cmp::min(buf.len(), self.0.len()));
+        buf[..n].copy_from_slice(&self.0[..n]);
+        self.0 = &self.0[n..];
+        Ok(n)
+    }
+}
+
+#[test]
+fn test_command_reader() {
+    let input = r#"/* (comment) */
+COMMAND("pg stat", "show placement group status (\"pg\").", "pg", "r", "cli,rest")
+// FLAG(
+COMMAND_WITH_FLAG("compact", "cause compaction of monitor's leveldb storage", \
+             "mon", "rw", "cli,rest", \
+             FLAG(NOFORWARD)|FLAG(DEPRECATED))
+"#;
+    let streamed: Result<Vec<Command>, String> = CommandReader::new("t", Trickle(input.as_bytes())).collect();
+    assert_eq!(::parse_input("t", input.as_bytes()), streamed);
+    assert_eq!(2, streamed.unwrap().len());
+
+    // Errors point at the same line as they do when parsing all at once
+    let bad = format!("{}# comment\nCOMMAND(\"pg dump\", \"dump\", \"pg\")\n", input);
+    let streamed: Vec<Result<Command, String>> = CommandReader::new("t", Trickle(bad.as_bytes())).collect();
+    assert_eq!(3, streamed.len());
+    assert_eq!(::parse_input("t", bad.as_bytes()).unwrap_err(), streamed[2].clone().unwrap_err());
+    assert_eq!("t:8: failed to parse \"COMMAND(\\\"pg dump\\\", \\\"dump\\\", \\\"pg\\\")\"",
+               streamed[2].clone().unwrap_err());
+
+    let unfinished = "COMMAND(\"pg stat\", \"show placement group status.\", \"pg\", \"r\"";
+    let streamed: Vec<Result<Command, String>> = CommandReader::new("t", Trickle(unfinished.as_bytes())).collect();
+    assert_eq!(1, streamed.len());
+    assert_eq!(::parse_input("t", unfinished.as_bytes()).unwrap_err(), streamed[0].clone().unwrap_err());
+
+    // A comment that's only half read mustn't be skipped over as junk
+    let commented = "COMMAND(\"versions\", \"check versions\",/* \"mon\", \"r\")\n/*\n * OSD commands\n */\n";
+    let streamed: Result<Vec<Command>, String> = CommandReader::new("t", Trickle(commented.as_bytes())).collect();
+    assert_eq!(::parse_input("t", commented.as_bytes()), streamed);
+    assert!(streamed.is_err());
+
+    // A command that doesn't parse is reported without reading any further
+    let streamed: Vec<Result<Command, String>> =
+        CommandReader::new("t", Trickle(bad.as_bytes()).chain(Broken)).collect();
+    assert_eq!(3, streamed.len());
+    assert_eq!(::parse_input("t", bad.as_bytes()).unwrap_err(), streamed[2].clone().unwrap_err());
+    let junk = "COMMAND(\"pg stat\", \"show placement group status.\", \"pg\", \"r\")\n/* ) */ junk(\n";
+    let streamed: Vec<Result<Command, String>> =
+        CommandReader::new("t", Trickle(junk.as_bytes()).chain(Broken)).collect();
+    assert_eq!(2, streamed.len());
+    assert_eq!(Err(::parse_input("t", junk.as_bytes()).unwrap_err()), streamed[1]);
+    assert_eq!(Err("t:2: failed to parse \"junk(\"".to_string()), streamed[1]);
+}
+
+#[cfg(test)]
+/// Fails every read, for input that mustn't be read to the end
+struct Broken;
+
+#[cfg(test)]
+impl Read for Broken {
+    fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+        Err(io::Error::other("read too far"))
+    }
+}
 
-        output
+#[test]
+fn test_scan() {
+    let input = b"// COMMAND(\n# )\n/* ( */ COMMAND(\"a (\\\")\", /* ) */ FLAG(X)) // (\nCOMMAND(";
+    let mut scan = Scan::new();
+    for end in 1..input.len() + 1 {
+        scan.advance(&input[..end], false);
+        if scan.complete {
+            // Complete at the end of the line the command closes on
+            assert_eq!(input.len() - 8, end);
+            break;
+        }
     }
+    assert!(scan.complete);
+    // and it stops at the next command
+    scan.advance(input, false);
+    assert_eq!(input.len() - 8, scan.pos);
+
+    let mut scan = Scan::new();
+    scan.advance(b"COMMAND(\"a\") /", false);
+    assert!(scan.closed && !scan.in_comment());
+    scan.advance(b"COMMAND(\"a\") /* ", false);
+    assert!(scan.in_comment());
+    scan.advance(b"COMMAND(\"a\") /* \n", false);
+    assert!(scan.complete && scan.in_comment());
+    scan.advance(b"COMMAND(\"a\") /* \n*/", false);
+    assert!(!scan.in_comment());
+
+    let mut scan = Scan::new();
+    scan.advance(b"COMMAND_WITH", false);
+    assert!(!scan.closed);
+    scan.advance(b"COMMAND_WITHOUT", false);
+    assert!(scan.closed && !scan.complete);
+    scan.advance(b"COMMAND_WITHOUT(\n", false);
+    assert!(scan.complete);
 }
 
-pub fn parse_commands(input: &[u8]) -> nom::IResult<&[u8], Vec<Command>> {
-    chain!(
-        input,
-        commands: many0!(
-            dbg!(
-                call!(Command::parse)
-            )),
-        ||{
-            commands
+/// Where a scan of the unparsed input is at
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Lexeme {
+    /// Between commands
+    Blank,
+    /// COMMAND( or COMMAND_WITH_FLAG(, starting at the given offset
+    Word(usize),
+    /// Inside the parentheses of a command
+    Body,
+    Quoted,
+    Escaped,
+    LineComment,
+    BlockComment,
+    /// Something that can't start a command, up to the end of its line
+    Junk,
+}
+
+/// Follows the unparsed input far enough to tell when a whole COMMAND(...)
+/// has been read, or something that can't be the start of one.  Bytes are
+/// only looked at once however the input arrives.
+#[derive(Clone, Debug)]
+struct Scan {
+    /// How much of the unparsed input has been looked at
+    pos: usize,
+    lexeme: Lexeme,
+    /// Parentheses open in the command
+    depth: usize,
+    /// A command has been closed or junk found
+    closed: bool,
+    /// So has the rest of that line, which goes in any error.  Nothing after
+    /// the next blanks matters.
+    complete: bool,
+}
+
+impl Scan {
+    fn new() -> Scan {
+        Scan { pos: 0, lexeme: Lexeme::Blank, depth: 0, closed: false, complete: false }
+    }
+
+    /// Is the scan stopped in a block comment that isn't closed yet?
+    fn in_comment(&self) -> bool {
+        self.lexeme == Lexeme::BlockComment
+    }
+
+    /// What a comment goes back to once it's over
+    fn after_comment(&self) -> Lexeme {
+        if self.depth > 0 { Lexeme::Body } else { Lexeme::Blank }
+    }
+
+    /// Look at whatever has been read since the last call.  A / or * at the
+    /// end is left for next time unless this is the end of the input.
+    fn advance(&mut self, input: &[u8], eof: bool) {
+        const TAGS: [&[u8]; 2] = [b"COMMAND(", b"COMMAND_WITH_FLAG("];
+        while self.pos < input.len() {
+            let c = input[self.pos];
+            let next = input.get(self.pos + 1).cloned();
+            if next.is_none() && !eof && (c == b'/' || (c == b'*' && self.lexeme == Lexeme::BlockComment)) {
+                return;
+            }
+            let mut step = 1;
+            self.lexeme = match (self.lexeme, c) {
+                (Lexeme::Blank, b' ') | (Lexeme::Blank, b'\t') | (Lexeme::Blank, b'\r') |
+                (Lexeme::Blank, b'\n') | (Lexeme::Blank, b'\\') => Lexeme::Blank,
+                (Lexeme::Blank, b'#') => Lexeme::LineComment,
+                (Lexeme::Body, b'/') | (Lexeme::Blank, b'/') if next == Some(b'/') => Lexeme::LineComment,
+                (Lexeme::Body, b'/') | (Lexeme::Blank, b'/') if next == Some(b'*') => {
+                    step = 2;
+                    Lexeme::BlockComment
+                }
+                (Lexeme::Blank, _) if self.complete => return,
+                (Lexeme::Blank, _) => Lexeme::Word(self.pos),
+                (Lexeme::Word(start), _) => Lexeme::Word(start),
+                (Lexeme::Body, b'"') => Lexeme::Quoted,
+                (Lexeme::Body, b'(') => {
+                    self.depth += 1;
+                    Lexeme::Body
+                }
+                (Lexeme::Body, b')') => {
+                    self.depth -= 1;
+                    if self.depth == 0 {
+                        self.closed = true;
+                        Lexeme::Blank
+                    } else {
+                        Lexeme::Body
+                    }
+                }
+                (Lexeme::Body, _) => Lexeme::Body,
+                (Lexeme::Quoted, b'\\') => Lexeme::Escaped,
+                (Lexeme::Quoted, b'"') => Lexeme::Body,
+                (Lexeme::Escaped, _) => Lexeme::Quoted,
+                (Lexeme::Quoted, _) => Lexeme::Quoted,
+                (Lexeme::LineComment, b'\n') => self.after_comment(),
+                (Lexeme::LineComment, _) => Lexeme::LineComment,
+                (Lexeme::BlockComment, b'*') if next == Some(b'/') => {
+                    step = 2;
+                    self.after_comment()
+                }
+                (Lexeme::BlockComment, _) => Lexeme::BlockComment,
+                (Lexeme::Junk, b'\n') => Lexeme::Blank,
+                (Lexeme::Junk, _) => Lexeme::Junk,
+            };
+            self.pos += step;
+            if c == b'\n' && self.closed {
+                self.complete = true;
+            }
+            if let Lexeme::Word(start) = self.lexeme {
+                let word = &input[start..self.pos];
+                if let Some(tag) = TAGS.iter().find(|tag| tag.starts_with(word)) {
+                    if tag.len() == word.len() {
+                        self.depth = 1;
+                        self.lexeme = Lexeme::Body;
+                    }
+                } else {
+                    // Won't parse however much more is read
+                    self.closed = true;
+                    self.lexeme = Lexeme::Junk;
+                }
+            }
         }
-    )
+    }
+}
+
+/// How much is read at a time by CommandReader
+const READ_SIZE: usize = 64 * 1024;
+
+/// Parses commands as they're read so a large command table doesn't have to
+/// be in memory all at once.  Nothing is parsed until a whole COMMAND(...)
+/// has been read.  A command is only taken once the parser stops short of the
+/// end of what has been read, so more input can't change it, and one that
+/// doesn't parse is reported there and then rather than at the end of the
+/// input.  Iteration stops after the first error.
+pub struct CommandReader<R> {
+    reader: R,
+    name: String,
+    buf: Vec<u8>,
+    /// Where the unparsed input starts in buf
+    start: usize,
+    /// How far buf[start..] has been scanned
+    scan: Scan,
+    /// Line number of buf[start]
+    line: usize,
+    parsed: usize,
+    eof: bool,
+    done: bool,
+}
+
+impl<R: Read> CommandReader<R> {
+    pub fn new(name: &str, reader: R) -> CommandReader<R> {
+        CommandReader {
+            reader,
+            name: name.to_string(),
+            buf: Vec::new(),
+            start: 0,
+            scan: Scan::new(),
+            line: 1,
+            parsed: 0,
+            eof: false,
+            done: false,
+        }
+    }
+
+    fn fill(&mut self) -> Result<(), String> {
+        self.buf.drain(..self.start);
+        self.start = 0;
+        let len = self.buf.len();
+        self.buf.resize(len + READ_SIZE, 0);
+        loop {
+            match self.reader.read(&mut self.buf[len..]) {
+                Ok(n) => {
+                    self.buf.truncate(len + n);
+                    self.eof = n == 0;
+                    return Ok(());
+                }
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => {
+                    self.buf.truncate(len);
+                    return Err(format!("Failed to read {}: {}", self.name, e));
+                }
+            }
+        }
+    }
+
+    /// The same error parse_input gives for input that doesn't parse.  The
+    /// blanks after a command belong to it so they're skipped.
+    fn error(&self, input: &[u8]) -> String {
+        let skipped = match blanks(input) {
+            Ok((rest, _)) if self.parsed > 0 => input.len() - rest.len(),
+            _ => 0,
+        };
+        let line = self.line + input[..skipped].iter().filter(|&&c| c == b'\n').count();
+        let rest = String::from_utf8_lossy(&input[skipped..]);
+        let context = rest.trim().lines().next().unwrap_or("");
+        format!("{}:{}: failed to parse {:?}", self.name, line, context)
+    }
+
+    /// Is everything left over blank the way parse_input sees it?
+    fn only_blanks(&self, input: &[u8]) -> bool {
+        let rest = match blanks(input) {
+            Ok((rest, _)) if self.parsed > 0 => rest,
+            _ => input,
+        };
+        String::from_utf8_lossy(rest).trim().is_empty()
+    }
+}
+
+impl<R: Read> Iterator for CommandReader<R> {
+    type Item = Result<Command, String>;
+
+    fn next(&mut self) -> Option<Result<Command, String>> {
+        if self.done {
+            return None;
+        }
+        loop {
+            self.scan.advance(&self.buf[self.start..], self.eof);
+            let parsed = if !self.scan.complete && !self.eof {
+                None
+            } else {
+                match Command::parse(&self.buf[self.start..]) {
+                    // Blanks after a command run to the end of what's been read so
+                    // there could be more of them still to come
+                    Ok((rest, _)) if rest.is_empty() && !self.eof => None,
+                    // A block comment that isn't closed yet stops blanks early and
+                    // quoted_string would skip over it instead
+                    Ok(_) if !self.eof && self.scan.in_comment() => None,
+                    Ok((rest, cmd)) => Some(Ok((rest.len(), cmd))),
+                    Err(_) => Some(Err(())),
+                }
+            };
+            match parsed {
+                Some(Ok((left, cmd))) => {
+                    let end = self.buf.len() - left;
+                    self.line += self.buf[self.start..end].iter().filter(|&&c| c == b'\n').count();
+                    self.start = end;
+                    self.scan = Scan::new();
+                    self.parsed += 1;
+                    return Some(Ok(cmd));
+                }
+                Some(Err(())) => {
+                    self.done = true;
+                    let rest = &self.buf[self.start..];
+                    if self.only_blanks(rest) {
+                        return None;
+                    }
+                    return Some(Err(self.error(rest)));
+                }
+                None => {}
+            }
+            if let Err(e) = self.fill() {
+                self.done = true;
+                return Some(Err(e));
+            }
+        }
+    }
 }
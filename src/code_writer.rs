@@ -0,0 +1,314 @@
+//! Indentation aware output for the generators.  Lines are written at the
+//! current indent, never end in whitespace and are wrapped to fit the width
+//! where the language allows it.
+
+#[test]
+fn test_lines() {
+    let mut w = CodeWriter::python();
+    w.line("class A:");
+    w.block("def f(self):", |w| {
+        w.line("x = 1  ");
+        w.blank();
+        w.line("if x:\n    return x\n");
+    });
+    w.blank_lines(2);
+    w.blank_lines(2);
+    w.line("y = 2");
+    w.blank();
+    assert_eq!("class A:\ndef f(self):\n    x = 1\n\n    if x:\n        return x\n\n\ny = 2\n", w.into_string());
+}
+
+#[test]
+fn test_fill() {
+    let mut w = CodeWriter::new("    ", 20);
+    w.indent();
+    w.fill(":param pool: the name of the pool", 4);
+    assert_eq!("    :param pool: the\n        name of the\n        pool\n", w.into_string());
+}
+
+#[test]
+fn test_call() {
+    let args: Vec<String> = vec!["self".to_string(), "pool".to_string(), "pg_num=None".to_string()];
+    let mut w = CodeWriter::new("    ", 40);
+    w.indent();
+    w.call("def f", &args, ":");
+    w.call("def osd_pool_create", &args, ":");
+    w.call("def a_much_longer_method_name", &args, ":");
+    assert_eq!("    def f(self, pool, pg_num=None):\n    \
+                def osd_pool_create(self, pool,\n                        pg_num=None):\n    \
+                def a_much_longer_method_name(\n            self, pool, pg_num=None):\n",
+               w.into_string());
+
+    let mut w = CodeWriter::new("    ", 40);
+    w.call("v = CephChoices", &["strings=\"max_mds|max_file_size|allow_new_snaps\"".to_string()], "");
+    assert_eq!("v = CephChoices(\n    strings=\"max_mds|max_file_size|\"\n            \"allow_new_snaps\")\n",
+               w.into_string());
+}
+
+#[test]
+fn test_collection() {
+    let items: Vec<String> = vec!["'prefix': 'osd pool create'".to_string(), "'pool': pool".to_string()];
+    let mut w = CodeWriter::new("    ", 79);
+    w.collection("cmd = {", &items, "}");
+    let mut narrow = CodeWriter::new("    ", 40);
+    narrow.collection("cmd = {", &items, "}");
+    assert_eq!("cmd = {'prefix': 'osd pool create', 'pool': pool}\n", w.into_string());
+    assert_eq!("cmd = {\n    'prefix': 'osd pool create',\n    'pool': pool,\n}\n", narrow.into_string());
+
+    let mut w = CodeWriter::new("    ", 30);
+    w.assign("cmd['pool']", "pool");
+    w.assign("cmd['yes_i_really_mean_it']", "yes_i_really_mean_it");
+    assert_eq!("cmd['pool'] = pool\ncmd['yes_i_really_mean_it'] = (\n    yes_i_really_mean_it)\n", w.into_string());
+}
+
+/// Source code built a line at a time
+pub struct CodeWriter {
+    output: String,
+    unit: &'static str,
+    width: usize,
+    level: usize,
+}
+
+impl CodeWriter {
+    /// A writer indenting by unit that keeps lines to width characters
+    pub fn new(unit: &'static str, width: usize) -> CodeWriter {
+        CodeWriter {
+            output: String::new(),
+            unit,
+            width,
+            level: 0,
+        }
+    }
+
+    /// Four space indents and 79 character lines as PEP 8 asks for
+    pub fn python() -> CodeWriter {
+        CodeWriter::new("    ", 79)
+    }
+
+    pub fn indent(&mut self) {
+        self.level += 1;
+    }
+
+    pub fn dedent(&mut self) {
+        self.level -= 1;
+    }
+
+    fn prefix(&self) -> String {
+        self.unit.repeat(self.level)
+    }
+
+    /// Room left on a line at the current indent
+    pub fn available(&self) -> usize {
+        self.width.saturating_sub(self.prefix().len())
+    }
+
+    /// Write text at the current indent.  Each line of a multi line text is
+    /// indented and keeps its own indentation on top.
+    pub fn line(&mut self, text: &str) {
+        let prefix = self.prefix();
+        for line in text.lines() {
+            let line = line.trim_end();
+            if !line.is_empty() {
+                self.output.push_str(&prefix);
+                self.output.push_str(line);
+            }
+            self.output.push('\n');
+        }
+        if text.is_empty() {
+            self.output.push('\n');
+        }
+    }
+
+    pub fn blank(&mut self) {
+        self.output.push('\n');
+    }
+
+    /// Exactly n blank lines before whatever comes next.  Nothing at the start
+    /// of the output.
+    pub fn blank_lines(&mut self, n: usize) {
+        while self.output.ends_with("\n\n") {
+            self.output.pop();
+        }
+        if !self.output.is_empty() {
+            for _ in 0..n {
+                self.output.push('\n');
+            }
+        }
+    }
+
+    /// A header line with body written one indent further in
+    pub fn block<F: FnOnce(&mut CodeWriter)>(&mut self, header: &str, body: F) {
+        self.line(header);
+        self.indent();
+        body(self);
+        self.dedent();
+    }
+
+    /// Word wrap text to the width.  Lines after the first are indented by
+    /// hanging more spaces.
+    pub fn fill(&mut self, text: &str, hanging: usize) {
+        let width = self.available();
+        let mut lines: Vec<String> = Vec::new();
+        let mut line = String::new();
+        for word in text.split_whitespace() {
+            let indent = if lines.is_empty() { 0 } else { hanging };
+            if !line.is_empty() && indent + line.len() + 1 + word.len() > width {
+                lines.push(line.clone());
+                line.clear();
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(word);
+        }
+        if !line.is_empty() || lines.is_empty() {
+            lines.push(line);
+        }
+        for (i, line) in lines.iter().enumerate() {
+            let indent = if i == 0 { String::new() } else { " ".repeat(hanging) };
+            self.line(&format!("{}{}", indent, line));
+        }
+    }
+
+    /// `head(args)tail`.  When that's too long the arguments are lined up
+    /// under the first one, or if that leaves no room they start on the next
+    /// line.
+    pub fn call(&mut self, head: &str, args: &[String], tail: &str) {
+        let width = self.available();
+        let close = format!("){}", tail);
+        let one_line = format!("{}({}{}", head, args.join(", "), close);
+        if one_line.len() <= width {
+            self.line(&one_line);
+            return;
+        }
+
+        let column = head.len() + 1;
+        let fits_aligned = args.iter().all(|arg| column + arg.len() + close.len() < width);
+        if fits_aligned {
+            let groups: Vec<Vec<String>> = args.iter().map(|arg| vec![arg.clone()]).collect();
+            let pad = " ".repeat(column);
+            let lines = CodeWriter::pack(&groups, width - column, close.len());
+            for (i, line) in lines.iter().enumerate() {
+                let start = if i == 0 { format!("{}(", head) } else { pad.clone() };
+                let end = if i + 1 == lines.len() { close.clone() } else { String::new() };
+                self.line(&format!("{}{}{}", start, line, end));
+            }
+            return;
+        }
+
+        // A def or if gets a second indent so its arguments don't line up with its body
+        let hanging = if head.starts_with("def ") || head.starts_with("if ") { 2 } else { 1 };
+        let pad = self.unit.repeat(hanging);
+        let room = width.saturating_sub(pad.len());
+        let groups: Vec<Vec<String>> = args.iter()
+            .map(|arg| CodeWriter::split_string(arg, room.saturating_sub(close.len() + 1)))
+            .collect();
+        self.line(&format!("{}(", head));
+        let lines = CodeWriter::pack(&groups, room, close.len());
+        for (i, line) in lines.iter().enumerate() {
+            let end = if i + 1 == lines.len() { close.clone() } else { String::new() };
+            self.line(&format!("{}{}{}", pad, line, end));
+        }
+    }
+
+    /// `target = value`, with the value in parentheses on the next line if
+    /// that's too long
+    pub fn assign(&mut self, target: &str, value: &str) {
+        let one_line = format!("{} = {}", target, value);
+        if one_line.len() <= self.available() {
+            self.line(&one_line);
+            return;
+        }
+        self.line(&format!("{} = (", target));
+        self.indent();
+        self.line(&format!("{})", value));
+        self.dedent();
+    }
+
+    /// `head items close` on one line, or an item a line with trailing commas
+    pub fn collection(&mut self, head: &str, items: &[String], close: &str) {
+        let one_line = format!("{}{}{}", head, items.join(", "), close);
+        if one_line.len() <= self.available() {
+            self.line(&one_line);
+            return;
+        }
+        self.line(head);
+        self.indent();
+        for item in items.iter() {
+            self.line(&format!("{},", item));
+        }
+        self.dedent();
+        self.line(close);
+    }
+
+    /// Arguments joined with ", " into as few lines of width as possible.  The
+    /// last line also needs room for last more characters.  An argument split
+    /// over several lines starts a line of its own.
+    fn pack(groups: &[Vec<String>], width: usize, last: usize) -> Vec<String> {
+        let mut lines: Vec<String> = Vec::new();
+        let mut line = String::new();
+        for (i, group) in groups.iter().enumerate() {
+            let needed = if i + 1 == groups.len() { last } else { 1 };
+            let first = &group[0];
+            if !line.is_empty() && (group.len() > 1 || line.len() + 2 + first.len() + needed > width) {
+                lines.push(format!("{},", line));
+                line.clear();
+            }
+            if !line.is_empty() {
+                line.push_str(", ");
+            }
+            line.push_str(first);
+            for piece in group[1..].iter() {
+                lines.push(line.clone());
+                line = piece.clone();
+            }
+        }
+        lines.push(line);
+        lines
+    }
+
+    /// A double quoted string argument split into adjacent literals that fit
+    /// in width, breaking after a `|` or a space.  The literals after the
+    /// first are padded to line up with it.  Anything else comes back whole.
+    fn split_string(arg: &str, width: usize) -> Vec<String> {
+        let quote = match arg.find('"') {
+            Some(quote) if arg.len() > width && arg.ends_with('"') && quote + 1 < arg.len() - 1 => quote,
+            _ => return vec![arg.to_string()],
+        };
+        let content = &arg[quote + 1..arg.len() - 1];
+        let room = width.saturating_sub(quote + 2);
+        let mut pieces: Vec<String> = Vec::new();
+        let mut piece = String::new();
+        let mut chunk = String::new();
+        for c in content.chars() {
+            chunk.push(c);
+            if c == '|' || c == ' ' {
+                if !piece.is_empty() && piece.len() + chunk.len() > room {
+                    pieces.push(piece.clone());
+                    piece.clear();
+                }
+                piece.push_str(&chunk);
+                chunk.clear();
+            }
+        }
+        if !piece.is_empty() && piece.len() + chunk.len() > room {
+            pieces.push(piece.clone());
+            piece.clear();
+        }
+        piece.push_str(&chunk);
+        pieces.push(piece);
+        let pad = " ".repeat(quote);
+        pieces.iter()
+            .enumerate()
+            .map(|(i, piece)| format!("{}\"{}\"", if i == 0 { &arg[..quote] } else { &pad }, piece))
+            .collect()
+    }
+
+    /// The output, ending in a single newline
+    pub fn into_string(mut self) -> String {
+        while self.output.ends_with("\n\n") {
+            self.output.pop();
+        }
+        self.output
+    }
+}
@@ -0,0 +1,224 @@
+//! Golden file tests over the command tables in fixtures/.  Set UPDATE_GOLDEN
+//! to rewrite the expected output after an intended change.  Complete upstream
+//! headers in fixtures/full/ have to parse, print back to the same commands and
+//! match the golden files next to them.  That test is ignored unless asked for
+//! as the headers aren't checked in.
+
+use std::env;
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+use ceph_command::{self, CommandReader, GeneratorOptions, Trickle};
+use header;
+use model;
+use python;
+
+/// Every release in the corpus with the number of commands it should parse to
+const RELEASES: &[(&str, usize)] = &[("hammer", 19),
+                                     ("jewel", 16),
+                                     ("luminous", 15),
+                                     ("mimic", 12),
+                                     ("nautilus", 10),
+                                     ("reef", 10)];
+
+fn fixture(release: &str, name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures").join(release).join(name)
+}
+
+/// The complete headers in fixtures/full/<release>/MonCommands.h, sorted by
+/// release.  fixtures/fetch-full.sh copies them out of a ceph checkout.
+fn full_headers() -> Vec<(String, PathBuf)> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures").join("full");
+    let mut headers: Vec<(String, PathBuf)> = match fs::read_dir(&dir) {
+        Ok(entries) => {
+            entries.filter_map(|e| e.ok())
+                .map(|e| (e.file_name().to_string_lossy().into_owned(), e.path().join("MonCommands.h")))
+                .filter(|(_, path)| path.is_file())
+                .collect()
+        }
+        Err(_) => Vec::new(),
+    };
+    headers.sort();
+    headers
+}
+
+/// Upstream puts each command on a line of its own, so this counts them
+/// without the parser's help.  Commented out commands aren't counted.
+fn count_commands(input: &str) -> usize {
+    let mut in_comment = false;
+    let mut count = 0;
+    for line in input.lines() {
+        let line = line.trim();
+        if in_comment {
+            in_comment = !line.contains("*/");
+            continue;
+        }
+        if line.starts_with("/*") {
+            in_comment = !line.contains("*/");
+            continue;
+        }
+        if line.starts_with("COMMAND(") || line.starts_with("COMMAND_WITH_FLAG(") {
+            count += 1;
+        }
+    }
+    count
+}
+
+#[test]
+fn test_count_commands() {
+    assert_eq!(2, count_commands("/*\n * COMMAND(signature, helpstring, modulename, req perms, availability)\n */\n\
+                                  COMMAND(\"pg stat\", \"show\", \"pg\", \"r\", \"cli,rest\")\n\
+                                  // COMMAND(\"pg old\", \"old\", \"pg\", \"r\", \"cli,rest\")\n\
+                                  /* COMMAND(\"pg older\", \"older\", \"pg\", \"r\", \"cli,rest\") */\n\
+                                  COMMAND_WITH_FLAG(\"compact\", \"compact\", \"mon\", \"rw\", \"cli,rest\", \
+                                  FLAG(NOFORWARD))\n"));
+}
+
+fn read(path: &Path) -> String {
+    let mut contents = String::new();
+    File::open(path)
+        .and_then(|mut f| f.read_to_string(&mut contents))
+        .unwrap_or_else(|e| panic!("Failed to read {}: {}", path.display(), e));
+    contents
+}
+
+/// Compare output with the golden file, or write it when UPDATE_GOLDEN is set.
+/// Returns a description of the mismatch.
+fn check_golden(release: &str, name: &str, output: &str) -> Option<String> {
+    let path = fixture(release, name);
+    if env::var("UPDATE_GOLDEN").is_ok() {
+        File::create(&path)
+            .and_then(|mut f| f.write_all(output.as_bytes()))
+            .unwrap_or_else(|e| panic!("Failed to write {}: {}", path.display(), e));
+        return None;
+    }
+    let expected = read(&path);
+    if expected == output {
+        return None;
+    }
+    let line = expected.lines()
+        .zip(output.lines())
+        .position(|(e, o)| e != o)
+        .unwrap_or(expected.lines().count().min(output.lines().count()));
+    Some(format!("{}/{} differs from line {}:\n  expected: {:?}\n  got:      {:?}",
+                 release,
+                 name,
+                 line + 1,
+                 expected.lines().nth(line).unwrap_or("<end of file>"),
+                 output.lines().nth(line).unwrap_or("<end of file>")))
+}
+
+#[test]
+fn test_corpus() {
+    let mut failures: Vec<String> = Vec::new();
+    for &(release, count) in RELEASES.iter() {
+        let input = read(&fixture(release, "MonCommands.h"));
+        let cmds = match ::parse_input(release, input.as_bytes()) {
+            Ok(cmds) => cmds,
+            Err(e) => {
+                failures.push(e);
+                continue;
+            }
+        };
+        if cmds.len() != count {
+            failures.push(format!("{}: expected {} commands, parsed {}", release, count, cmds.len()));
+        }
+
+        let dump = format!("{}\n", model::to_json(&cmds).pretty());
+        failures.extend(check_golden(release, "commands.json", &dump));
+        let mut renamed = cmds.clone();
+        ceph_command::rename_duplicate_functions(&mut renamed);
+        let py = python::generate(&renamed, &GeneratorOptions::default());
+        failures.extend(check_golden(release, "ceph_command.py", &py));
+
+        let streamed: Result<Vec<ceph_command::Command>, String> =
+            CommandReader::new(release, Trickle(input.as_bytes())).collect();
+        if streamed.as_ref() != Ok(&cmds) {
+            failures.push(format!("{}: reading the commands one at a time gave {:?}", release, streamed.map(|c| c.len())));
+        }
+
+        // The printer has to give back exactly what was parsed, in the syntax
+        // of the release it came from
+        let printed = header::generate(&cmds);
+        failures.extend(check_golden(release, "printed.h", &printed));
+        match ::parse_input(release, printed.as_bytes()) {
+            Ok(ref printed) if *printed == cmds => {}
+            Ok(_) => failures.push(format!("{}: printing and parsing again changed the commands", release)),
+            Err(e) => failures.push(format!("{}: failed to parse the printed commands: {}", release, e)),
+        }
+    }
+    assert!(failures.is_empty(), "\n{}", failures.join("\n"));
+}
+
+#[test]
+fn test_print_release_syntax() {
+    // Nautilus dropped the availability column and hammer spelled flags bare,
+    // printing either one back in the other's syntax isn't what upstream has
+    let print = |release: &str, prefix: &str| {
+        let input = read(&fixture(release, "MonCommands.h"));
+        let cmds = ::parse_input(release, input.as_bytes()).unwrap();
+        let cmd = cmds.iter().find(|c| c.signature.prefix == prefix).unwrap();
+        header::generate(::std::slice::from_ref(cmd))
+    };
+    assert_eq!("COMMAND_WITH_FLAG(\"fsid\", \"show cluster FSID/UUID\", \"mon\", \"r\", \\\n\tFLAG(NOFORWARD))\n",
+               print("reef", "fsid"));
+    assert_eq!("COMMAND(\"pg repeer \" \\\n\t\"name=pgid,type=CephPgid \", \\\n\t\"force a PG to repeer\", \"osd\", \"rw\")\n",
+               print("reef", "pg repeer"));
+    assert_eq!("COMMAND_WITH_FLAG(\"compact\", \"cause compaction of monitor's leveldb storage\", \"mon\", \"rw\", \
+                \"cli,rest\", \\\n\tNOFORWARD)\n",
+               print("hammer", "compact"));
+}
+
+/// Only runs with `cargo test -- --ignored` once fixtures/fetch-full.sh has
+/// copied the headers in.
+#[test]
+#[ignore]
+fn test_full_headers() {
+    let headers = full_headers();
+    assert!(!headers.is_empty(), "No complete headers in fixtures/full, run fixtures/fetch-full.sh to add them");
+    let mut failures: Vec<String> = Vec::new();
+    for (release, path) in headers {
+        let input = read(&path);
+        let cmds = match ::parse_input(&release, input.as_bytes()) {
+            Ok(cmds) => cmds,
+            Err(e) => {
+                failures.push(e);
+                continue;
+            }
+        };
+        let expected = count_commands(&input);
+        if cmds.len() != expected {
+            failures.push(format!("{}: {} commands in the file, parsed {}", release, expected, cmds.len()));
+        }
+
+        let golden = format!("full/{}", release);
+        let dump = format!("{}\n", model::to_json(&cmds).pretty());
+        failures.extend(check_golden(&golden, "commands.json", &dump));
+        let mut renamed = cmds.clone();
+        ceph_command::rename_duplicate_functions(&mut renamed);
+        let py = python::generate(&renamed, &GeneratorOptions::default());
+        failures.extend(check_golden(&golden, "ceph_command.py", &py));
+
+        let printed = header::generate(&cmds);
+        failures.extend(check_golden(&golden, "printed.h", &printed));
+        match ::parse_input(&release, printed.as_bytes()) {
+            Ok(ref again) if *again == cmds => {
+                if header::generate(again) != printed {
+                    failures.push(format!("{}: printing the printed commands changed them", release));
+                }
+            }
+            Ok(ref again) => {
+                let first = cmds.iter()
+                    .zip(again.iter())
+                    .position(|(a, b)| a != b)
+                    .unwrap_or(cmds.len().min(again.len()));
+                failures.push(format!("{}: printing and parsing again changed the commands from {:?}",
+                                      release,
+                                      cmds.get(first).map(|c| &c.signature.prefix)));
+            }
+            Err(e) => failures.push(format!("{}: failed to parse the printed commands: {}", release, e)),
+        }
+    }
+    assert!(failures.is_empty(), "\n{}", failures.join("\n"));
+}
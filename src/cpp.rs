@@ -0,0 +1,329 @@
+use rustc_serialize::json::Json;
+
+use ceph_command::{self, CephEnum, CephType, Command, Flag, GeneratorOptions, Module, Target};
+use generator::Generator;
+use schema::{camel_case, cpp_field_name};
+
+#[cfg(test)]
+use test_fixtures::{command, pool_create};
+
+#[test]
+fn test_cpp_builder() {
+    // Keywords get an underscore but are sent under their own name
+    let keywords = builder(&command("osd crush rule create-replicated"), &GeneratorOptions::default());
+    assert!(keywords.contains(", const char* class_ = nullptr) {\n"));
+    assert!(keywords.contains("  if (class_ != nullptr) {\n    detail::append(json, \"class\", class_);\n  }\n"));
+
+    let builder = builder(&pool_create(), &GeneratorOptions::default());
+    assert!(builder.contains("enum class OsdPoolCreatePoolType { Replicated, Erasure };\n"));
+    assert!(builder.contains("    case OsdPoolCreatePoolType::Erasure: return \"erasure\";\n"));
+    assert!(builder.contains("/// create pool\n[[deprecated]] inline std::string osd_pool_create(const char* pool, \
+                              int64_t pg_num, std::optional<OsdPoolCreatePoolType> pool_type = std::nullopt, \
+                              const std::vector<std::string>& crush_rule = {}) {\n"));
+    assert!(builder.contains("  assert(pg_num >= 0 && pg_num <= 65536 && \"pg_num is out of range\");\n"));
+    assert!(builder.contains("  std::string json = \"{\\\"prefix\\\":\";\n  \
+                              detail::append_value(json, \"osd pool create\");\n"));
+    assert!(builder.contains("  if (pool_type) {\n    detail::append(json, \"pool_type\", to_string(*pool_type));\n  }\n"));
+    assert!(builder.contains("  if (!crush_rule.empty()) {\n    detail::append(json, \"crush-rule\", crush_rule);\n  }\n"));
+}
+
+#[test]
+fn test_cpp_choices() {
+    let choices = vec!["--yes-i-really-mean-it".to_string(), "1".to_string(), "yes_i_really_mean_it".to_string()];
+    assert_eq!(vec!["YesIReallyMeanIt", "Value1", "YesIReallyMeanIt2"], enumerators(&choices));
+
+    let mut cmd = pool_create();
+    cmd.target = Target::Osd;
+    let builder = builder(&cmd, &GeneratorOptions::default());
+    assert!(builder.contains("/// Send with rados_osd_command\n[[deprecated]] inline std::string osd_pool_create("));
+}
+
+fn preamble() -> String {
+    let mut output = String::new();
+    output.push_str("// Generated by command_parser from the Ceph command tables.  Do not edit.\n");
+    output.push_str("// Needs C++17.  Every builder returns the json command to pass to\n");
+    output.push_str("// rados_mon_command, or the call named in its comment if it goes elsewhere.\n\n");
+    output.push_str("#pragma once\n\n");
+    output.push_str("#include <cassert>\n");
+    output.push_str("#include <cstdint>\n");
+    output.push_str("#include <cstdio>\n");
+    output.push_str("#include <optional>\n");
+    output.push_str("#include <string>\n");
+    output.push_str("#include <vector>\n\n");
+    output.push_str("namespace ceph_command {\n\n");
+    output.push_str("namespace detail {\n\n");
+    output.push_str("/// Append s as a quoted json string\n");
+    output.push_str("inline void append_value(std::string& json, const char* s) {\n");
+    output.push_str("  json += '\"';\n");
+    output.push_str("  for (; *s != '\\0'; ++s) {\n");
+    output.push_str("    unsigned char c = static_cast<unsigned char>(*s);\n");
+    output.push_str("    if (c == '\"' || c == '\\\\') {\n");
+    output.push_str("      json += '\\\\';\n");
+    output.push_str("      json += static_cast<char>(c);\n");
+    output.push_str("    } else if (c < 0x20) {\n");
+    output.push_str("      char escaped[7];\n");
+    output.push_str("      std::snprintf(escaped, sizeof(escaped), \"\\\\u%04x\", c);\n");
+    output.push_str("      json += escaped;\n");
+    output.push_str("    } else {\n");
+    output.push_str("      json += static_cast<char>(c);\n");
+    output.push_str("    }\n");
+    output.push_str("  }\n");
+    output.push_str("  json += '\"';\n");
+    output.push_str("}\n\n");
+    output.push_str("inline void append_value(std::string& json, const std::string& s) { append_value(json, s.c_str()); }\n\n");
+    output.push_str("inline void append_value(std::string& json, int64_t value) { json += std::to_string(value); }\n\n");
+    output.push_str("inline void append_value(std::string& json, double value) {\n");
+    output.push_str("  char formatted[32];\n");
+    output.push_str("  std::snprintf(formatted, sizeof(formatted), \"%.17g\", value);\n");
+    output.push_str("  json += formatted;\n");
+    output.push_str("}\n\n");
+    output.push_str("inline void append_value(std::string& json, bool value) { json += value ? \"true\" : \"false\"; }\n\n");
+    output.push_str("template <typename T>\n");
+    output.push_str("inline void append_value(std::string& json, const std::vector<T>& values) {\n");
+    output.push_str("  json += '[';\n");
+    output.push_str("  for (size_t i = 0; i < values.size(); ++i) {\n");
+    output.push_str("    if (i > 0) {\n");
+    output.push_str("      json += ',';\n");
+    output.push_str("    }\n");
+    output.push_str("    append_value(json, values[i]);\n");
+    output.push_str("  }\n");
+    output.push_str("  json += ']';\n");
+    output.push_str("}\n\n");
+    output.push_str("/// Append ,\"key\":value\n");
+    output.push_str("template <typename T>\n");
+    output.push_str("inline void append(std::string& json, const char* key, const T& value) {\n");
+    output.push_str("  json += ',';\n");
+    output.push_str("  append_value(json, key);\n");
+    output.push_str("  json += ':';\n");
+    output.push_str("  append_value(json, value);\n");
+    output.push_str("}\n\n");
+    output.push_str("}  // namespace detail\n\n");
+    output
+}
+
+/// A C++ string literal.  Json escapes are close enough for the printable
+/// text in the command tables.
+fn string_literal(s: &str) -> String {
+    Json::String(s.to_string()).to_string()
+}
+
+/// A float as a double literal
+fn double_literal(value: f32) -> String {
+    let literal = value.to_string();
+    if literal.contains('.') || literal.contains('e') {
+        literal
+    } else {
+        format!("{}.0", literal)
+    }
+}
+
+/// Enumerator names for choices, unique and never starting with a digit
+fn enumerators(choices: &[String]) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for choice in choices.iter() {
+        let mut name = camel_case(choice);
+        if name.chars().next().is_none_or(|c| c.is_numeric()) {
+            name = format!("Value{}", name);
+        }
+        let mut unique = name.clone();
+        let mut n = 2;
+        while names.contains(&unique) {
+            unique = format!("{}{}", name, n);
+            n += 1;
+        }
+        names.push(unique);
+    }
+    names
+}
+
+/// The enum for a CephChoices parameter and a to_string for it
+fn choices_enum(name: &str, choices: &[String]) -> String {
+    let names = enumerators(choices);
+    let mut output = String::new();
+    output.push_str(&format!("enum class {} {{ {} }};\n\n", name, names.join(", ")));
+    output.push_str(&format!("inline const char* to_string({} value) {{\n", name));
+    output.push_str("  switch (value) {\n");
+    for (enumerator, choice) in names.iter().zip(choices.iter()) {
+        output.push_str(&format!("    case {}::{}: return {};\n", name, enumerator, string_literal(choice)));
+    }
+    output.push_str("  }\n");
+    output.push_str("  return \"\";\n");
+    output.push_str("}\n\n");
+    output
+}
+
+/// The C++ type of a single value of a parameter
+fn value_type(enum_name: &str, variant: &CephEnum) -> String {
+    match variant {
+        &CephEnum::CephInt { .. } => "int64_t".to_string(),
+        &CephEnum::CephFloat { .. } => "double".to_string(),
+        &CephEnum::CephBool => "bool".to_string(),
+        CephEnum::CephChoices { choices, .. } if !choices.is_empty() => enum_name.to_string(),
+        _ if variant.is_many() => "std::string".to_string(),
+        _ => "const char*".to_string(),
+    }
+}
+
+/// Is a single value passed as a const char*?  Those are optional by being
+/// nullptr rather than std::optional.
+fn is_c_string(variant: &CephEnum) -> bool {
+    !variant.is_many() && value_type("", variant) == "const char*"
+}
+
+/// The argument declaration for a parameter.  Optional ones get a default.
+fn argument(ident: &str, enum_name: &str, ceph_type: &CephType) -> String {
+    let value = value_type(enum_name, &ceph_type.variant);
+    if ceph_type.variant.is_many() {
+        let default = if ceph_type.req { "" } else { " = {}" };
+        format!("const std::vector<{}>& {}{}", value, ident, default)
+    } else if ceph_type.req {
+        format!("{} {}", value, ident)
+    } else if is_c_string(&ceph_type.variant) {
+        format!("const char* {} = nullptr", ident)
+    } else {
+        format!("std::optional<{}> {} = std::nullopt", value, ident)
+    }
+}
+
+/// An assert for the range of a value.  Empty if it isn't bounded.
+fn range_assert(name: &str, value: &str, variant: &CephEnum) -> String {
+    let (min, max) = match *variant {
+        CephEnum::CephInt { min, max } => (min.map(|m| m.to_string()), max.map(|m| m.to_string())),
+        CephEnum::CephFloat { min, max } => (min.map(double_literal), max.map(double_literal)),
+        _ => (None, None),
+    };
+    let mut checks: Vec<String> = Vec::new();
+    if let Some(min) = min {
+        checks.push(format!("{} >= {}", value, min));
+    }
+    if let Some(max) = max {
+        checks.push(format!("{} <= {}", value, max));
+    }
+    if checks.is_empty() {
+        return String::new();
+    }
+    format!("assert({} && \"{} is out of range\");", checks.join(" && "), name)
+}
+
+/// Add a parameter to the json
+fn add_param(output: &mut String, indent: &str, key: &str, value: &str, ceph_type: &CephType) {
+    let is_enum = match ceph_type.variant {
+        CephEnum::CephChoices { ref choices, .. } => !choices.is_empty(),
+        _ => false,
+    };
+    let check = range_assert(key, value, &ceph_type.variant);
+    if !check.is_empty() {
+        output.push_str(&format!("{}{}\n", indent, check));
+    }
+    if is_enum && ceph_type.variant.is_many() {
+        output.push_str(&format!("{}std::vector<const char*> values;\n", indent));
+        output.push_str(&format!("{}for (auto v : {}) {{\n", indent, value));
+        output.push_str(&format!("{}  values.push_back(to_string(v));\n", indent));
+        output.push_str(&format!("{}}}\n", indent));
+        output.push_str(&format!("{}detail::append(json, {}, values);\n", indent, string_literal(key)));
+    } else if is_enum {
+        output.push_str(&format!("{}detail::append(json, {}, to_string({}));\n", indent, string_literal(key), value));
+    } else {
+        output.push_str(&format!("{}detail::append(json, {}, {});\n", indent, string_literal(key), value));
+    }
+}
+
+/// The enums and the builder function for a command
+fn builder(cmd: &Command, options: &GeneratorOptions) -> String {
+    let mut output = String::new();
+    let name = cpp_field_name(&cmd.method_name());
+    let type_prefix = camel_case(&cmd.method_name());
+
+    // Required parameters first so the optional ones can have defaults
+    let mut params = cmd.signature.ordered_parameters();
+    params.sort_by_key(|&(_, ceph_type)| !ceph_type.req);
+
+    let mut args: Vec<String> = Vec::new();
+    for &(key, ceph_type) in params.iter() {
+        let enum_name = format!("{}{}", type_prefix, camel_case(key));
+        if let CephEnum::CephChoices { ref choices, .. } = ceph_type.variant {
+            if !choices.is_empty() {
+                output.push_str(&choices_enum(&enum_name, choices));
+            }
+        }
+        args.push(argument(&cpp_field_name(cmd.signature.api_name(key)), &enum_name, ceph_type));
+    }
+
+    for line in cmd.helpstring.lines() {
+        output.push_str(&format!("/// {}\n", line.trim()));
+    }
+    match cmd.target {
+        Target::Mon => {}
+        Target::Mgr => output.push_str("/// Send with rados_mgr_command\n"),
+        Target::Osd => output.push_str("/// Send with rados_osd_command\n"),
+        Target::Mds => output.push_str("/// Send with ceph_mds_command from libcephfs\n"),
+    }
+    let deprecated = cmd.flags.as_ref().is_some_and(|flags| flags.contains(&Flag::Deprecated));
+    output.push_str(&format!("{}inline std::string {}({}) {{\n",
+                             if deprecated { "[[deprecated]] " } else { "" },
+                             name,
+                             args.join(", ")));
+    for &(key, ceph_type) in params.iter() {
+        if ceph_type.req && is_c_string(&ceph_type.variant) {
+            output.push_str(&format!("  assert({} != nullptr);\n", cpp_field_name(cmd.signature.api_name(key))));
+        }
+    }
+    output.push_str("  std::string json = \"{\\\"prefix\\\":\";\n");
+    output.push_str(&format!("  detail::append_value(json, {});\n", string_literal(&cmd.signature.prefix)));
+    for &(key, ceph_type) in params.iter() {
+        let ident = cpp_field_name(cmd.signature.api_name(key));
+        if ceph_type.req {
+            add_param(&mut output, "  ", key, &ident, ceph_type);
+        } else if ceph_type.variant.is_many() {
+            output.push_str(&format!("  if (!{}.empty()) {{\n", ident));
+            add_param(&mut output, "    ", key, &ident, ceph_type);
+            output.push_str("  }\n");
+        } else if is_c_string(&ceph_type.variant) {
+            output.push_str(&format!("  if ({} != nullptr) {{\n", ident));
+            add_param(&mut output, "    ", key, &ident, ceph_type);
+            output.push_str("  }\n");
+        } else {
+            output.push_str(&format!("  if ({}) {{\n", ident));
+            add_param(&mut output, "    ", key, &format!("*{}", ident), ceph_type);
+            output.push_str("  }\n");
+        }
+    }
+    if options.json_output && cmd.supports_json() && !cmd.signature.parameters.contains_key("format") {
+        output.push_str("  json += \",\\\"format\\\":\\\"json\\\"\";\n");
+    }
+    output.push_str("  json += '}';\n");
+    output.push_str("  return json;\n");
+    output.push_str("}\n");
+    output
+}
+
+/// Generate a C++ header for cmds with a namespace of builders per Module
+pub fn generate(cmds: &[Command], options: &GeneratorOptions) -> String {
+    let mut output = preamble();
+
+    for (module, module_commands) in ceph_command::group_by_module(cmds, &Module::all()) {
+        let namespace = cpp_field_name(module.name());
+        output.push_str(&format!("namespace {} {{\n", namespace));
+        for cmd in module_commands.iter() {
+            output.push('\n');
+            output.push_str(&builder(cmd, options));
+        }
+        output.push_str(&format!("\n}}  // namespace {}\n\n", namespace));
+    }
+    output.push_str("}  // namespace ceph_command\n");
+    output
+}
+
+/// The C++ header
+pub struct Cpp;
+
+impl Generator for Cpp {
+    fn name(&self) -> &'static str {
+        "cpp"
+    }
+
+    fn files(&self, cmds: &[Command], options: &GeneratorOptions) -> Result<Vec<(String, String)>, String> {
+        Ok(vec![("ceph_command.hpp".to_string(), generate(cmds, options))])
+    }
+}
+
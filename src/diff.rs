@@ -0,0 +1,121 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use ceph_command::Command;
+
+#[test]
+fn test_diff() {
+    let old = ::parse_input("old",
+                            b"COMMAND(\"osd ls\", \"show all osds\", \"osd\", \"r\", \"cli,rest\")\n\
+                              COMMAND(\"osd tree\", \"print osd tree\", \"osd\", \"r\", \"cli,rest\")\n\
+                              COMMAND(\"osd stat\", \"print osd map summary\", \"osd\", \"r\", \"cli,rest\")")
+        .unwrap();
+    let new = ::parse_input("new",
+                            b"COMMAND(\"osd ls\", \"show all osds\", \"osd\", \"r\", \"cli,rest\")\n\
+                              COMMAND(\"osd tree name=states,type=CephChoices,strings=up,n=N,req=false\", \
+                              \"print OSD tree\", \"osd\", \"r\", \"cli,rest\")\n\
+                              COMMAND(\"osd df\", \"show osd utilization\", \"osd\", \"r\", \"cli,rest\")")
+        .unwrap();
+
+    let changes = diff(&old, &new);
+    assert_eq!(vec![Change::Added("osd df".to_string()),
+                    Change::Removed("osd stat".to_string()),
+                    Change::Changed("osd tree".to_string(),
+                                    vec!["helpstring \"print osd tree\" -> \"print OSD tree\"".to_string(),
+                                         "parameter states added".to_string()])],
+               changes);
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Change {
+    Added(String),
+    Removed(String),
+    /// Prefix and a description of everything that changed
+    Changed(String, Vec<String>),
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Change::Added(prefix) => write!(f, "+ {}", prefix),
+            Change::Removed(prefix) => write!(f, "- {}", prefix),
+            Change::Changed(prefix, what) => {
+                write!(f, "~ {}", prefix)?;
+                for w in what.iter() {
+                    write!(f, "\n    {}", w)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+fn compare(old: &Command, new: &Command) -> Vec<String> {
+    let mut changes = Vec::new();
+    if old.helpstring != new.helpstring {
+        changes.push(format!("helpstring {:?} -> {:?}", old.helpstring, new.helpstring));
+    }
+    if old.module_name != new.module_name {
+        changes.push(format!("module {:?} -> {:?}", old.module_name, new.module_name));
+    }
+    if old.permissions != new.permissions {
+        changes.push(format!("permissions {:?} -> {:?}", old.permissions, new.permissions));
+    }
+    if old.availability != new.availability {
+        changes.push(format!("availability {:?} -> {:?}", old.availability, new.availability));
+    }
+    if old.flags != new.flags {
+        changes.push(format!("flags {:?} -> {:?}", old.flags, new.flags));
+    }
+    if old.target != new.target {
+        changes.push(format!("target {:?} -> {:?}", old.target, new.target));
+    }
+
+    let old_params: BTreeMap<&String, _> = old.signature.parameters.iter().collect();
+    let new_params: BTreeMap<&String, _> = new.signature.parameters.iter().collect();
+    for (name, old_type) in old_params.iter() {
+        match new_params.get(name) {
+            None => changes.push(format!("parameter {} removed", name)),
+            Some(new_type) if new_type != old_type => {
+                changes.push(format!("parameter {} {:?} -> {:?}", name, old_type, new_type))
+            }
+            Some(_) => {}
+        }
+    }
+    for name in new_params.keys() {
+        if !old_params.contains_key(name) {
+            changes.push(format!("parameter {} added", name));
+        }
+    }
+    changes
+}
+
+/// Compare two command tables by prefix.  The result is sorted by prefix.
+/// Commands that share a prefix are compared in the order they appear.
+pub fn diff(old: &[Command], new: &[Command]) -> Vec<Change> {
+    let mut by_prefix: BTreeMap<&str, (Vec<&Command>, Vec<&Command>)> = BTreeMap::new();
+    for cmd in old.iter() {
+        by_prefix.entry(&cmd.signature.prefix).or_insert((Vec::new(), Vec::new())).0.push(cmd);
+    }
+    for cmd in new.iter() {
+        by_prefix.entry(&cmd.signature.prefix).or_insert((Vec::new(), Vec::new())).1.push(cmd);
+    }
+
+    let mut changes = Vec::new();
+    for (prefix, (old_cmds, new_cmds)) in by_prefix.iter() {
+        for i in 0..old_cmds.len().max(new_cmds.len()) {
+            match (old_cmds.get(i), new_cmds.get(i)) {
+                (Some(_), None) => changes.push(Change::Removed(prefix.to_string())),
+                (None, Some(_)) => changes.push(Change::Added(prefix.to_string())),
+                (Some(o), Some(n)) => {
+                    let what = compare(o, n);
+                    if !what.is_empty() {
+                        changes.push(Change::Changed(prefix.to_string(), what));
+                    }
+                }
+                (None, None) => {}
+            }
+        }
+    }
+    changes
+}
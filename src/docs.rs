@@ -0,0 +1,142 @@
+use ceph_command::{self, AllowedRepeats, Availability, CephEnum, CephType, Command, Module};
+
+#[test]
+fn test_describe() {
+    assert_eq!("CephInt, range 0..", describe(&CephEnum::CephInt { min: Some(0), max: None }));
+    assert_eq!("CephFloat, range 0..1", describe(&CephEnum::CephFloat { min: Some(0.0), max: Some(1.0) }));
+    assert_eq!("CephChoices, one or more of `a`, `b`",
+               describe(&CephEnum::CephChoices {
+                   choices: vec!["a".to_string(), "b".to_string()],
+                   allowed_repeats: AllowedRepeats::Many,
+               }));
+    assert_eq!("CephString, characters [A-Za-z0-9-_.]",
+               describe(&CephEnum::CephString {
+                   goodchars: Some("A-Za-z0-9-_.".to_string()),
+                   badchars: None,
+                   allowed_repeats: AllowedRepeats::One,
+               }));
+}
+
+fn range<T: ::std::fmt::Display>(min: &Option<T>, max: &Option<T>) -> String {
+    match (min, max) {
+        (&None, &None) => String::new(),
+        (Some(min), &None) => format!(", range {}..", min),
+        (&None, Some(max)) => format!(", range ..{}", max),
+        (Some(min), Some(max)) => format!(", range {}..{}", min, max),
+    }
+}
+
+fn repeats(allowed_repeats: &AllowedRepeats) -> &'static str {
+    match *allowed_repeats {
+        AllowedRepeats::One => "",
+        AllowedRepeats::Many => ", repeatable",
+    }
+}
+
+/// A human readable description of a parameter type
+pub fn describe(variant: &CephEnum) -> String {
+    match variant {
+        CephEnum::CephInt { min, max } => format!("CephInt{}", range(min, max)),
+        CephEnum::CephFloat { min, max } => format!("CephFloat{}", range(min, max)),
+        CephEnum::CephString { goodchars, badchars, allowed_repeats } => {
+            let mut out = String::from("CephString");
+            if let Some(chars) = goodchars {
+                out.push_str(&format!(", characters [{}]", chars));
+            }
+            if let Some(chars) = badchars {
+                out.push_str(&format!(", not [{}]", chars));
+            }
+            out.push_str(repeats(allowed_repeats));
+            out
+        }
+        &CephEnum::CephSocketpath => "CephSocketpath, path to a unix socket".to_string(),
+        &CephEnum::CephIPAddr => "CephIPAddr, v4 or v6 address with optional port".to_string(),
+        &CephEnum::CephEntityAddr => "CephEntityAddr, CephIPAddr plus optional /nonce".to_string(),
+        CephEnum::CephPoolname { allowed_repeats } => {
+            format!("CephPoolname{}", repeats(allowed_repeats.as_ref().unwrap_or(&AllowedRepeats::One)))
+        }
+        &CephEnum::CephObjectname => "CephObjectname".to_string(),
+        &CephEnum::CephPgid => "CephPgid, `<pool>.<pg hex>`".to_string(),
+        &CephEnum::CephName => "CephName, `*` or `<type>.<id>`".to_string(),
+        &CephEnum::CephOsdName => "CephOsdName, `*`, `<id>` or `osd.<id>`".to_string(),
+        CephEnum::CephChoices { choices, allowed_repeats } => {
+            let quoted: Vec<String> = choices.iter().map(|c| format!("`{}`", c)).collect();
+            match *allowed_repeats {
+                AllowedRepeats::One => format!("CephChoices, one of {}", quoted.join(", ")),
+                AllowedRepeats::Many => format!("CephChoices, one or more of {}", quoted.join(", ")),
+            }
+        }
+        &CephEnum::CephFilepath => "CephFilepath, path to an openable file".to_string(),
+        &CephEnum::CephFragment => "CephFragment, `<hex value>/<bits>`".to_string(),
+        &CephEnum::CephUUID => "CephUUID".to_string(),
+        &CephEnum::CephPrefix => "CephPrefix".to_string(),
+        &CephEnum::CephBool => "CephBool, `true` or `false`".to_string(),
+        &CephEnum::CephTimespan => "CephTimespan, seconds or a duration such as `1h30m`".to_string(),
+        &CephEnum::CephPgidList => "CephPgidList, list of `<pool>.<pg hex>`".to_string(),
+        &CephEnum::CephArgtype => "CephArgtype".to_string(),
+        &CephEnum::Unknown => "unknown".to_string(),
+    }
+}
+
+fn describe_param(ceph_type: &CephType) -> String {
+    let mut out = describe(&ceph_type.variant);
+    if !ceph_type.positional {
+        out.push_str(", by name only");
+    }
+    if ceph_type.allow_empty {
+        out.push_str(", may be empty");
+    }
+    out
+}
+
+fn availability(a: &Availability) -> &'static str {
+    match *a {
+        Availability::Cli => "cli",
+        Availability::Rest => "rest",
+        Availability::Both => "cli, rest",
+        Availability::Unknown => "unknown",
+    }
+}
+
+fn command(cmd: &Command) -> String {
+    let mut output = String::new();
+    output.push_str(&format!("### `{}`\n\n", cmd.signature.prefix));
+    output.push_str(&format!("{}\n\n", cmd.helpstring));
+
+    let mut params: Vec<(&String, &CephType)> = cmd.signature.parameters.iter().collect();
+    params.sort_by(|a, b| a.0.cmp(b.0));
+    if !params.is_empty() {
+        output.push_str("| Parameter | Type | Required |\n");
+        output.push_str("|-----------|------|----------|\n");
+        for (name, ceph_type) in params {
+            output.push_str(&format!("| `{}` | {} | {} |\n",
+                                     name,
+                                     describe_param(ceph_type),
+                                     if ceph_type.req { "yes" } else { "no" }));
+        }
+        output.push('\n');
+    }
+    output.push_str(&format!("Permissions: `{}`, available from: {}, sent to: {}",
+                             cmd.permissions.to_string(),
+                             availability(&cmd.availability),
+                             cmd.target.name()));
+    if let Some(ref flags) = cmd.flags {
+        let names: Vec<&str> = flags.iter().map(|f| f.name()).collect();
+        output.push_str(&format!(", flags: {}", names.join(", ")));
+    }
+    output.push_str("\n\n");
+    output
+}
+
+/// Markdown reference for cmds with a section per Module
+pub fn generate(cmds: &[Command]) -> String {
+    let mut output = String::from("# Ceph commands\n\n");
+    for (module, mut module_commands) in ceph_command::group_by_module(cmds, &Module::all()) {
+        module_commands.sort_by(|a, b| a.signature.prefix.cmp(&b.signature.prefix));
+        output.push_str(&format!("## {}\n\n", module.to_string()));
+        for cmd in module_commands.iter() {
+            output.push_str(&command(cmd));
+        }
+    }
+    output
+}
@@ -0,0 +1,107 @@
+use glob::Pattern;
+
+use ceph_command::{Availability, Command, Flag, Module};
+
+#[test]
+fn test_module_names() {
+    for name in MODULE_NAMES.iter() {
+        assert_eq!(*name, Module::from_str(name).name());
+    }
+}
+
+#[test]
+fn test_prefix_pattern() {
+    let matches = |glob: &str, prefix: &str| prefix_pattern(glob).unwrap().matches(prefix);
+    assert!(matches("osd pool *", "osd pool ls"));
+    assert!(matches("osd pool *", "osd pool ls detail"));
+    assert!(!matches("osd pool *", "osd pool"));
+    assert!(matches("pg dump*", "pg dump_json"));
+    assert!(matches("osd ?", "osd x"));
+    assert!(!matches("osd ?", "osd ls"));
+    assert!(matches("osd [lp]s", "osd ps"));
+    assert!(!matches("mon", "mon stat"));
+    assert_eq!(Err("\"osd [\": Pattern syntax error near position 4: invalid range pattern".to_string()),
+               prefix_pattern("osd [").map(|p| p.to_string()));
+}
+
+#[test]
+fn test_filter() {
+    let cmds = ::parse_input("test",
+                             b"COMMAND(\"osd pool ls\", \"list pools\", \"osd\", \"r\", \"cli,rest\")\n\
+                               COMMAND(\"osd pool create\", \"create pool\", \"osd\", \"rw\", \"cli,rest\")\n\
+                               COMMAND_WITH_FLAG(\"mon scrub\", \"scrub\", \"mon\", \"rw\", \"cli,rest\", FLAG(DEPRECATED))\n\
+                               COMMAND(\"pg stat\", \"pg status\", \"pg\", \"r\", \"cli,rest\")")
+        .unwrap();
+
+    let read_only = Filter { read_only: true, ..Filter::default() };
+    assert_eq!(vec!["osd pool ls", "pg stat"],
+               read_only.apply(&cmds).iter().map(|c| c.signature.prefix.clone()).collect::<Vec<String>>());
+
+    let no_osd = Filter { exclude_modules: vec![Module::Osd], exclude_deprecated: true, ..Filter::default() };
+    assert_eq!(vec!["pg stat"],
+               no_osd.apply(&cmds).iter().map(|c| c.signature.prefix.clone()).collect::<Vec<String>>());
+
+    let pools = Filter { prefixes: vec![prefix_pattern("osd pool *").unwrap()], ..Filter::default() };
+    assert_eq!(2, pools.apply(&cmds).len());
+
+    let mon = Filter { modules: vec![Module::Mon], ..Filter::default() };
+    assert_eq!(1, mon.apply(&cmds).len());
+}
+
+/// A --prefix glob.  A pattern glob can't parse, like an unclosed [, is an
+/// error rather than a literal that matches nothing.
+pub fn prefix_pattern(glob: &str) -> Result<Pattern, String> {
+    Pattern::new(glob).map_err(|e| format!("{:?}: {}", glob, e))
+}
+
+/// The modules --module and --exclude-module can pick.  Anything else would
+/// be Module::Unknown and match nothing.
+pub const MODULE_NAMES: &[&str] = &["auth", "config-key", "log", "mds", "mgr", "mon", "osd", "pg"];
+
+/// Selects which commands get generated.  The default lets everything through.
+#[derive(Clone, Debug, Default)]
+pub struct Filter {
+    /// Only these modules.  Empty means every module
+    pub modules: Vec<Module>,
+    pub exclude_modules: Vec<Module>,
+    /// Only commands that need nothing more than read permission
+    pub read_only: bool,
+    /// Only commands available over the rest api
+    pub rest_only: bool,
+    /// Leave out commands flagged DEPRECATED or OBSOLETE
+    pub exclude_deprecated: bool,
+    /// Globs a Signature.prefix has to match one of.  Empty means every prefix
+    pub prefixes: Vec<Pattern>,
+}
+
+impl Filter {
+    pub fn matches(&self, cmd: &Command) -> bool {
+        if !self.modules.is_empty() && !self.modules.contains(&cmd.module_name) {
+            return false;
+        }
+        if self.exclude_modules.contains(&cmd.module_name) {
+            return false;
+        }
+        if self.read_only && !cmd.permissions.is_read_only() {
+            return false;
+        }
+        if self.rest_only && cmd.availability != Availability::Rest && cmd.availability != Availability::Both {
+            return false;
+        }
+        if self.exclude_deprecated {
+            if let Some(ref flags) = cmd.flags {
+                if flags.iter().any(|f| *f == Flag::Deprecated || *f == Flag::Obsolete) {
+                    return false;
+                }
+            }
+        }
+        if !self.prefixes.is_empty() && !self.prefixes.iter().any(|p| p.matches(&cmd.signature.prefix)) {
+            return false;
+        }
+        true
+    }
+
+    pub fn apply(&self, cmds: &[Command]) -> Vec<Command> {
+        cmds.iter().filter(|c| self.matches(c)).cloned().collect()
+    }
+}
@@ -0,0 +1,120 @@
+//! The interface every output language implements.  A crate with its own
+//! backend implements Generator and registers it alongside the built in ones.
+
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use ceph_command::{Command, GeneratorOptions};
+use cpp;
+use proto;
+use python;
+use rust;
+use typescript;
+
+#[cfg(test)]
+struct Stub;
+
+#[cfg(test)]
+impl Generator for Stub {
+    fn name(&self) -> &'static str {
+        "stub"
+    }
+
+    fn files(&self, cmds: &[Command], _options: &GeneratorOptions) -> Result<Vec<(String, String)>, String> {
+        Ok(vec![("stub.txt".to_string(), format!("{}\n", cmds.len())), ("sub/empty.txt".to_string(), String::new())])
+    }
+}
+
+#[test]
+fn test_registry() {
+    let mut registry = Registry::default();
+    assert_eq!(vec!["python", "python-package", "python-tests", "rust", "typescript", "cpp", "proto"], registry.names());
+    assert!(registry.get("stub").is_none());
+
+    registry.register(Box::new(Stub));
+    assert_eq!("stub", registry.get("stub").unwrap().name());
+    assert_eq!(8, registry.names().len());
+
+    // A later registration replaces one with the same name
+    registry.register(Box::new(Stub));
+    assert_eq!(8, registry.names().len());
+}
+
+#[test]
+fn test_write() {
+    let dir = ::std::env::temp_dir().join(format!("command_parser_generator_{}", ::std::process::id()));
+    let written = Stub.write(&[], &GeneratorOptions::default(), &dir).unwrap();
+    assert_eq!(vec![dir.join("stub.txt"), dir.join("sub/empty.txt")], written);
+    assert_eq!("0\n", fs::read_to_string(dir.join("stub.txt")).unwrap());
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+/// Turns the parsed commands into source code
+pub trait Generator {
+    /// What the language is called on the command line
+    fn name(&self) -> &'static str;
+
+    /// The files to write as (path relative to the output directory, contents)
+    fn files(&self, cmds: &[Command], options: &GeneratorOptions) -> Result<Vec<(String, String)>, String>;
+
+    /// Write the files into out_dir, creating directories as needed.  Returns
+    /// the paths written.
+    fn write(&self, cmds: &[Command], options: &GeneratorOptions, out_dir: &Path) -> Result<Vec<PathBuf>, String> {
+        let mut written = Vec::new();
+        for (name, contents) in self.files(cmds, options)? {
+            let path = out_dir.join(&name);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent).map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+            }
+            File::create(&path)
+                .and_then(|mut f| f.write_all(contents.as_bytes()))
+                .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+            written.push(path);
+        }
+        Ok(written)
+    }
+}
+
+/// Generators by name
+pub struct Registry {
+    generators: Vec<Box<dyn Generator>>,
+}
+
+impl Registry {
+    /// A registry without any generators
+    pub fn new() -> Registry {
+        Registry { generators: Vec::new() }
+    }
+
+    /// Add a generator.  It replaces any generator already registered under
+    /// the same name.
+    pub fn register(&mut self, generator: Box<dyn Generator>) {
+        self.generators.retain(|g| g.name() != generator.name());
+        self.generators.push(generator);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&dyn Generator> {
+        self.generators.iter().find(|g| g.name() == name).map(|g| &**g)
+    }
+
+    /// Names in the order they were registered
+    pub fn names(&self) -> Vec<&'static str> {
+        self.generators.iter().map(|g| g.name()).collect()
+    }
+}
+
+impl Default for Registry {
+    /// Every built in language
+    fn default() -> Registry {
+        let mut registry = Registry::new();
+        registry.register(Box::new(python::Python));
+        registry.register(Box::new(python::PythonPackage));
+        registry.register(Box::new(python::PythonTests));
+        registry.register(Box::new(rust::Rust));
+        registry.register(Box::new(typescript::TypeScript));
+        registry.register(Box::new(cpp::Cpp));
+        registry.register(Box::new(proto::Proto));
+        registry
+    }
+}
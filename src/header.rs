@@ -0,0 +1,213 @@
+use ceph_command::{AllowedRepeats, CephEnum, CephType, Command, Flag};
+
+#[cfg(test)]
+use ceph_command::parse_commands;
+
+#[cfg(test)]
+const SAMPLE: &str = r#"/*
+ * Comments
+ */
+COMMAND("pg stat", "show placement group status.", "pg", "r", "cli,rest")
+COMMAND("pg ls-by-osd " \
+        "name=osd,type=CephOsdName " \
+        "name=pool,type=CephInt,req=false " \
+        "name=states,type=CephChoices,strings=active|clean|down,n=N,req=false ", \
+        "list pg on osd [osd]", "pg", "r", "cli,rest")
+// osd
+COMMAND("osd pool create " \
+        "name=pool,type=CephPoolname " \
+        "name=pg_num,type=CephInt,range=0 " \
+        "name=size,type=CephInt,range=|1048576,req=false " \
+        "name=ratio,type=CephFloat,range=-1.5|1.5,req=false " \
+        "name=rule,type=CephString,goodchars=[A-Za-z0-9-_.],badchars=[/],allowempty=true,positional=false " \
+        "name=yes_i_really_mean_it,type=CephBool,req=false,by=hand", \
+        "create pool", "osd", "rw", "cli,rest")
+COMMAND_WITH_FLAG("compact", "cause compaction of monitor's leveldb storage", \
+             "mon", "rw", "cli,rest", \
+             FLAG(NOFORWARD)|FLAG(DEPRECATED))
+COMMAND("osd tier add " \
+        "name=pool,type=CephPoolname,n=N " \
+        "name=when,type=CephWhenever,req=false", \
+        "add the tier \"tierpool\" to base pool <pool>", "osd", "rw", "cli,rest")
+"#;
+
+#[test]
+fn test_round_trip() {
+    let first = match parse_commands(SAMPLE.as_bytes()) {
+        Ok((_, cmds)) => cmds,
+        other => panic!("Failed to parse the sample: {:?}", other),
+    };
+    assert_eq!(5, first.len());
+    let printed = generate(&first);
+    let second = match parse_commands(printed.as_bytes()) {
+        Ok((remaining, cmds)) => {
+            assert!(remaining.is_empty(), "Left over: {}", String::from_utf8_lossy(remaining));
+            cmds
+        }
+        other => panic!("Failed to parse the printed commands: {:?}", other),
+    };
+    assert_eq!(first, second);
+    // Printing is a normalizer so doing it twice changes nothing
+    assert_eq!(printed, generate(&second));
+}
+
+#[test]
+fn test_round_trip_malformed() {
+    // Descriptors like these are wrong but they should still print back to
+    // something that parses the same.  They're the first lines of
+    // fuzz/corpus/parse_commands/malformed.h so the fuzzer starts from them.
+    let input = r#"COMMAND("health name=detail,type=CephChoices,req=false", "h", "mon", "r")
+COMMAND("pg x name=gid,type=CephPgid(,", "h", "pg", "r", "cli,rest")
+COMMAND("osd x name=id,type=CephInt,type name=y,type=CephInt,req,req=false", "h", "osd", "r")
+"#;
+    let first = match parse_commands(input.as_bytes()) {
+        Ok((_, cmds)) => cmds,
+        other => panic!("Failed to parse the input: {:?}", other),
+    };
+    assert_eq!(3, first.len());
+    match parse_commands(generate(&first).as_bytes()) {
+        Ok((_, second)) => assert_eq!(first, second),
+        other => panic!("Failed to parse the printed commands: {:?}", other),
+    }
+}
+
+#[test]
+fn test_print_command() {
+    let cmds = match parse_commands(SAMPLE.as_bytes()) {
+        Ok((_, cmds)) => cmds,
+        other => panic!("Failed to parse the sample: {:?}", other),
+    };
+    assert_eq!("COMMAND(\"pg stat\", \"show placement group status.\", \"pg\", \"r\", \"cli,rest\")\n",
+               command(&cmds[0]));
+    assert_eq!("COMMAND_WITH_FLAG(\"compact\", \"cause compaction of monitor's leveldb storage\", \"mon\", \"rw\", \
+                \"cli,rest\", \\\n\tFLAG(NOFORWARD)|FLAG(DEPRECATED))\n",
+               command(&cmds[3]));
+    assert!(command(&cmds[2]).contains("\t\"name=size,type=CephInt,range=|1048576,req=false \" \\\n"));
+}
+
+fn range<T: ::std::fmt::Display>(min: &Option<T>, max: &Option<T>) -> Option<String> {
+    match (min, max) {
+        (&None, &None) => None,
+        (Some(min), &None) => Some(format!("range={}", min)),
+        (&None, Some(max)) => Some(format!("range=|{}", max)),
+        (Some(min), Some(max)) => Some(format!("range={}|{}", min, max)),
+    }
+}
+
+fn repeats(allowed_repeats: &AllowedRepeats) -> Option<String> {
+    match *allowed_repeats {
+        AllowedRepeats::One => None,
+        AllowedRepeats::Many => Some("n=N".to_string()),
+    }
+}
+
+/// The name=..,type=.. descriptor for one parameter
+fn descriptor(name: &str, ceph_type: &CephType) -> String {
+    let type_name = match ceph_type.variant {
+        CephEnum::Unknown => ceph_type.extra.get("type").map(|t| &t[..]).unwrap_or("Unknown"),
+        ref v => v.type_name(),
+    };
+    let mut attrs = vec![format!("name={}", name), format!("type={}", type_name)];
+    let mut add = |attr: Option<String>| {
+        if let Some(a) = attr {
+            attrs.push(a);
+        }
+    };
+    match ceph_type.variant {
+        CephEnum::CephInt { ref min, ref max } => add(range(min, max)),
+        CephEnum::CephFloat { ref min, ref max } => add(range(min, max)),
+        CephEnum::CephString { ref goodchars, ref badchars, ref allowed_repeats } => {
+            add(goodchars.as_ref().map(|c| format!("goodchars=[{}]", c)));
+            add(badchars.as_ref().map(|c| format!("badchars=[{}]", c)));
+            add(repeats(allowed_repeats));
+        }
+        CephEnum::CephPoolname { ref allowed_repeats } => {
+            match *allowed_repeats {
+                Some(AllowedRepeats::Many) => add(Some("n=N".to_string())),
+                Some(AllowedRepeats::One) => add(Some("n=1".to_string())),
+                None => {}
+            }
+        }
+        CephEnum::CephChoices { ref choices, ref allowed_repeats } => {
+            if !choices.is_empty() {
+                add(Some(format!("strings={}", choices.join("|"))));
+            }
+            add(repeats(allowed_repeats));
+        }
+        _ => {}
+    }
+    if !ceph_type.req {
+        add(Some("req=false".to_string()));
+    }
+    if !ceph_type.positional {
+        add(Some("positional=false".to_string()));
+    }
+    if ceph_type.allow_empty {
+        add(Some("allowempty=true".to_string()));
+    }
+    for (key, value) in ceph_type.extra.iter() {
+        if key != "type" {
+            add(Some(format!("{}={}", key, value)));
+        }
+    }
+    attrs.join(",")
+}
+
+// Helpstrings are kept escaped the way they were in the header so only
+// quotes that aren't already escaped need it
+fn escape(s: &str) -> String {
+    let mut out = String::new();
+    let mut escaped = false;
+    for c in s.chars() {
+        if c == '"' && !escaped {
+            out.push('\\');
+        }
+        escaped = c == '\\' && !escaped;
+        out.push(c);
+    }
+    out
+}
+
+/// One COMMAND(...) or COMMAND_WITH_FLAG(...) entry in MonCommands.h syntax
+pub fn command(cmd: &Command) -> String {
+    let name = if cmd.flags.is_some() { "COMMAND_WITH_FLAG" } else { "COMMAND" };
+    let params = cmd.signature.ordered_parameters();
+    let mut output = String::new();
+    if params.is_empty() {
+        output.push_str(&format!("{}(\"{}\", ", name, cmd.signature.prefix));
+    } else {
+        output.push_str(&format!("{}(\"{} \" \\\n", name, cmd.signature.prefix));
+        for (i, &(param_name, ceph_type)) in params.iter().enumerate() {
+            let end = if i + 1 == params.len() { ", \\\n" } else { " \\\n" };
+            output.push_str(&format!("\t\"{} \"{}", descriptor(param_name, ceph_type), end));
+        }
+        output.push('\t');
+    }
+    output.push_str(&format!("\"{}\", \"{}\", \"{}\"",
+                             escape(&cmd.helpstring),
+                             cmd.module_name.name(),
+                             cmd.permissions.to_string()));
+    if cmd.syntax.availability {
+        output.push_str(&format!(", \"{}\"", cmd.availability.name()));
+    }
+    if let Some(ref flags) = cmd.flags {
+        let names: Vec<String> = flags.iter()
+            .map(|f| match *f {
+                Flag::NoForward if cmd.syntax.bare_noforward => "NOFORWARD".to_string(),
+                _ => format!("FLAG({})", f.name()),
+            })
+            .collect();
+        output.push_str(&format!(", \\\n\t{}", names.join("|")));
+    }
+    output.push_str(")\n");
+    output
+}
+
+/// A whole command table in MonCommands.h syntax
+pub fn generate(cmds: &[Command]) -> String {
+    let mut output = String::new();
+    for cmd in cmds.iter() {
+        output.push_str(&command(cmd));
+    }
+    output
+}
@@ -0,0 +1,55 @@
+//! Parser for Ceph's MonCommands.h command table and the generators built on
+//! the parsed commands.  The command line tool in main.rs is a thin wrapper
+//! around this library.
+
+#[macro_use]
+extern crate log;
+extern crate glob;
+extern crate minijinja;
+extern crate nom;
+extern crate rustc_serialize;
+
+pub mod caps;
+pub mod ceph_command;
+pub mod code_writer;
+#[cfg(test)]
+mod corpus;
+pub mod cpp;
+pub mod diff;
+pub mod docs;
+pub mod filter;
+pub mod generator;
+pub mod header;
+pub mod model;
+pub mod overrides;
+pub mod proto;
+pub mod python;
+pub mod release;
+pub mod rust;
+pub mod schema;
+pub mod serve;
+pub mod template;
+#[cfg(test)]
+pub mod test_fixtures;
+pub mod typescript;
+pub mod validate;
+
+/// Parse a whole command table.  Anything left over that isn't whitespace is
+/// reported as an error along with the line it starts on.
+pub fn parse_input(name: &str, input: &[u8]) -> Result<Vec<ceph_command::Command>, String> {
+    match ceph_command::parse_commands(input) {
+        Ok((remaining, cmds)) => {
+            let rest = String::from_utf8_lossy(remaining);
+            if rest.trim().is_empty() {
+                Ok(cmds)
+            } else {
+                let consumed = &input[..input.len() - remaining.len()];
+                let line = consumed.iter().filter(|&&c| c == b'\n').count() + 1;
+                let context = rest.trim().lines().next().unwrap_or("");
+                Err(format!("{}:{}: failed to parse {:?}", name, line, context))
+            }
+        }
+        Err(nom::Err::Incomplete(needed)) => Err(format!("{}: incomplete input, needed {:?}", name, needed)),
+        Err(_) => Err(format!("{}: failed to parse", name)),
+    }
+}
@@ -1,205 +1,559 @@
+#[macro_use]
+extern crate clap;
+extern crate command_parser;
+extern crate glob;
 extern crate itertools;
 #[macro_use]
 extern crate log;
-#[macro_use]
-extern crate nom;
 extern crate simple_logger;
 
-use std::io::{self, Read};
-use std::collections::HashSet;
-
-mod ceph_command;
-
-fn print_run_command() {
-    println!("def run_ceph_command(conffile, cmd, inbuf):");
-    println!("    \"\"\"Run a ceph command and return the results");
-    println!("");
-    println!("    :param conffile: The ceph.conf configuration location");
-    println!("    :param cmd: The json command to run");
-    println!("    :param inbuf:");
-    println!("    :return: (string outbuf, string outs)");
-    println!("    :raise rados.Error: Raises on rados errors");
-    println!("    \"\"\"");
-    println!("    cluster = rados.Rados(conffile=conffile)");
-    println!("    try:");
-    println!("        cluster.connect()");
-    println!("        result = cluster.mon_command(json.dumps(cmd), inbuf=inbuf)");
-    println!("        if result[0] is not 0:");
-    println!("            raise CephError(cmd=cmd, msg=os.strerror(abs(result[0])))");
-    println!("        return result[1], result[2]");
-    println!("    except rados.Error as e:");
-    println!("        raise e");
-}
-
-fn print_exception_class() {
-    println!("class CephError(Exception):");
-    println!("    \"\"\"Exception raised for errors with running a Ceph command");
-    println!("");
-    println!("        :param cmd: cmd in which the error occurred");
-    println!("        :param msg: explanation of the error");
-    println!("    \"\"\"");
-    println!("    def __init__(self, cmd, msg):");
-    println!("        self.cmd = cmd");
-    println!("        self.msg = msg");
-    println!("");
-}
-
-fn print_init() {
-    println!("    def __init__(self, rados_config_file):");
-    println!("        self.rados_config_file = rados_config_file");
-    println!("");
-}
-
-fn print_imports() {
-    println!("import ceph_argparse");
-    println!("import json");
-    println!("import os");
-    println!("import rados");
-    println!("import six");
-    println!("");
-}
-
-/// This tries to find ceph_api commands that are going to have duplicate names
-/// and rename them with a _2 or _3 suffix.  Python doesn't allow method overloading
-/// so this is a hacky workaround.
-fn rename_duplicate_functions(v: &mut Vec<ceph_command::Command>) {
-    let mut h = HashSet::new();
-
-    for i in v.iter_mut() {
-        let prefix_method_name = i.signature.prefix.replace(" ", "_").replace("-", "_");
-        let already_present = h.insert(prefix_method_name.clone());
-
-        if !already_present {
-            // rename
-            i.signature.duplicate = true;
-        }
-    }
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::str::FromStr;
+
+use command_parser::{caps, ceph_command, diff, docs, filter, generator, header, model, overrides, release, schema,
+                     serve, template, validate};
+
+/// An input file or one of the sidecar files couldn't be read or parsed
+const EXIT_PARSE_ERROR: i32 = 2;
+/// Parsing worked but the output couldn't be generated or written
+const EXIT_GENERATE_ERROR: i32 = 3;
+/// serve couldn't listen on the address or stopped accepting connections
+const EXIT_SERVE_ERROR: i32 = 4;
+/// check-commands found commands the daemons would refuse
+const EXIT_INVALID_COMMANDS: i32 = 5;
+
+fn read_file(path: &str) -> Result<String, String> {
+    let mut contents = String::new();
+    File::open(path)
+        .and_then(|mut f| f.read_to_string(&mut contents))
+        .map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    Ok(contents)
 }
 
-fn main() {
-    // Read in the MonCommands.h file and produce ceph-commands.py file
-    simple_logger::init_with_level(log::LogLevel::Warn).unwrap();
-    let mut buffer: Vec<u8> = vec![];
-    match io::stdin().read_to_end(&mut buffer) {
-        Ok(_) => trace!("Read input from STDIN"),
-        Err(e) => trace!("Failed to read STDIN: {:?}", e),
-    };
+fn load_target_overrides(path: &str) -> Result<HashMap<String, ceph_command::Target>, String> {
+    read_file(path).map(|contents| ceph_command::parse_target_overrides(&contents))
+}
 
-    let input: &[u8] = &buffer.as_slice();
-    let commands = ceph_command::parse_commands(input);
-
-    match commands {
-        nom::IResult::Done(_, cmds) => {
-
-            // NOTE: Classes are grouped here.  Add more if needed
-            // Group commands by module name
-
-            // TODO: Optimize me for less brute force crap
-            print_imports();
-            print_exception_class();
-            print_run_command();
-
-            let mut pg_commands: Vec<ceph_command::Command> = cmds.iter()
-                .cloned()
-                .filter(|c| c.module_name == ceph_command::Module::Pg)
-                .collect::<Vec<ceph_command::Command>>();
-            rename_duplicate_functions(&mut pg_commands);
-            if pg_commands.len() > 0 {
-                println!("class {}:", ceph_command::Module::Pg.to_string());
-                print_init();
-                for result in pg_commands.iter() {
-                    let r = result.to_string();
-                    println!("{}", r);
-                }
-            }
+fn load_schemas(path: &str) -> Result<schema::SchemaRegistry, String> {
+    let contents = read_file(path)?;
+    schema::SchemaRegistry::parse(&contents).map_err(|e| format!("Failed to parse schema registry {}: {}", path, e))
+}
 
-            let mut mds_commands: Vec<ceph_command::Command> = cmds.iter()
-                .cloned()
-                .filter(|c| c.module_name == ceph_command::Module::Mds)
-                .collect::<Vec<ceph_command::Command>>();
-            rename_duplicate_functions(&mut mds_commands);
-            if mds_commands.len() > 0 {
-                println!("class {}:", ceph_command::Module::Mds.to_string());
-                print_init();
-                for result in mds_commands.iter() {
-                    let r = result.to_string();
-                    println!("{}", r);
-                }
-            }
+fn load_overrides(path: &str) -> Result<overrides::Overrides, String> {
+    let contents = read_file(path)?;
+    overrides::Overrides::parse(&contents).map_err(|e| format!("Failed to parse overrides {}: {}", path, e))
+}
 
-            let mut osd_commands: Vec<ceph_command::Command> = cmds.iter()
-                .cloned()
-                .filter(|c| c.module_name == ceph_command::Module::Osd)
-                .collect::<Vec<ceph_command::Command>>();
-            rename_duplicate_functions(&mut osd_commands);
-            if osd_commands.len() > 0 {
-                println!("class {}:", ceph_command::Module::Osd.to_string());
-                print_init();
-                for result in osd_commands.iter() {
-                    let r = result.to_string();
-                    println!("{}", r);
+/// Parse a command table a command at a time, or load it from a json model
+/// written by `dump`
+fn read_or_load<R: Read>(name: &str, mut input: R) -> Result<(Vec<ceph_command::Command>, bool), String> {
+    // Look for the first thing that isn't whitespace to tell a model apart
+    let mut head: Vec<u8> = Vec::new();
+    loop {
+        let mut byte = [0u8];
+        match input.read(&mut byte) {
+            Ok(0) => break,
+            Ok(_) => {
+                head.push(byte[0]);
+                if !(byte[0] as char).is_whitespace() {
+                    break;
                 }
             }
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(format!("Failed to read {}: {}", name, e)),
+        }
+    }
+    let is_model = name.ends_with(".json") || head.last() == Some(&b'{');
+    let mut input = io::Cursor::new(head).chain(input);
+    if is_model {
+        let mut contents: Vec<u8> = Vec::new();
+        input.read_to_end(&mut contents).map_err(|e| format!("Failed to read {}: {}", name, e))?;
+        model::from_json(&String::from_utf8_lossy(&contents)).map(|cmds| (cmds, true)).map_err(|e| format!("{}: {}", name, e))
+    } else {
+        ceph_command::CommandReader::new(name, input).collect::<Result<Vec<_>, _>>().map(|cmds| (cmds, false))
+    }
+}
 
-            let mut mon_commands: Vec<ceph_command::Command> = cmds.iter()
-                .cloned()
-                .filter(|c| c.module_name == ceph_command::Module::Mon)
-                .collect::<Vec<ceph_command::Command>>();
-            rename_duplicate_functions(&mut mon_commands);
-            if mon_commands.len() > 0 {
-                println!("class {}:", ceph_command::Module::Mon.to_string());
-                print_init();
-                for result in mon_commands.iter() {
-                    let r = result.to_string();
-                    println!("{}", r);
-                }
-            }
+/// Expand the --input globs into file names
+fn input_paths(patterns: &[&str]) -> Result<Vec<String>, String> {
+    let mut paths = Vec::new();
+    for pattern in patterns.iter() {
+        let matches = glob::glob(pattern).map_err(|e| format!("Bad input pattern {}: {}", pattern, e))?;
+        let mut found = false;
+        for entry in matches {
+            let path = entry.map_err(|e| format!("Failed to read {}: {}", pattern, e))?;
+            paths.push(path.to_string_lossy().into_owned());
+            found = true;
+        }
+        if !found {
+            return Err(format!("No input files match {}", pattern));
+        }
+    }
+    Ok(paths)
+}
 
-            let mut auth_commands: Vec<ceph_command::Command> = cmds.iter()
-                .cloned()
-                .filter(|c| c.module_name == ceph_command::Module::Auth)
-                .collect::<Vec<ceph_command::Command>>();
-            rename_duplicate_functions(&mut auth_commands);
-            if auth_commands.len() > 0 {
-                println!("class {}:", ceph_command::Module::Auth.to_string());
-                print_init();
-                for result in auth_commands.iter() {
-                    let r = result.to_string();
-                    println!("{}", r);
-                }
+/// Read every input, apply the overrides, filters and targets and return the
+/// commands that should be generated
+fn load_commands(matches: &ArgMatches, inputs: &[&str]) -> Result<Vec<ceph_command::Command>, String> {
+    let source = matches.value_of("source").and_then(ceph_command::Target::from_source);
+    let mut parsed: Vec<ceph_command::Command> = Vec::new();
+    if inputs.is_empty() {
+        trace!("Reading input from STDIN");
+        let stdin = io::stdin();
+        let (mut cmds, is_model) = read_or_load("<stdin>", stdin.lock())?;
+        if !is_model {
+            ceph_command::assign_targets(&mut cmds, source, &HashMap::new());
+        }
+        parsed.extend(cmds);
+    } else {
+        for path in input_paths(inputs)? {
+            let file = File::open(&path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+            let (mut cmds, is_model) = read_or_load(&path, BufReader::new(file))?;
+            info!("{}: {} commands", path, cmds.len());
+            // A dumped model already knows where each command goes
+            if !is_model {
+                let file_source = ceph_command::Target::from_source(&path).or(source.clone());
+                ceph_command::assign_targets(&mut cmds, file_source, &HashMap::new());
             }
+            parsed.extend(cmds);
+        }
+    }
 
-            let mut log_commands: Vec<ceph_command::Command> = cmds.iter()
-                .cloned()
-                .filter(|c| c.module_name == ceph_command::Module::Log)
-                .collect::<Vec<ceph_command::Command>>();
-            rename_duplicate_functions(&mut log_commands);
-            if log_commands.len() > 0 {
-                println!("class {}:", ceph_command::Module::Log.to_string());
-                print_init();
-                for result in log_commands.iter() {
-                    let r = result.to_string();
-                    println!("{}", r);
-                }
+    if let Some(path) = matches.value_of("overrides") {
+        let unmatched = load_overrides(path)?.apply(&mut parsed)
+            .map_err(|e| format!("Failed to apply overrides {}: {}", path, e))?;
+        for unmatched in unmatched {
+            warn!("Override no longer matches upstream: {}", unmatched);
+        }
+    }
+    let module_list = |name: &str| -> Vec<ceph_command::Module> {
+        matches.values_of(name)
+            .map(|v| v.map(ceph_command::Module::from_str).collect())
+            .unwrap_or_default()
+    };
+    let command_filter = filter::Filter {
+        modules: module_list("module"),
+        exclude_modules: module_list("exclude-module"),
+        read_only: matches.is_present("read-only"),
+        rest_only: matches.is_present("rest-only"),
+        exclude_deprecated: matches.is_present("no-deprecated"),
+        prefixes: matches.values_of("prefix")
+            .map(|v| v.map(filter::prefix_pattern).collect::<Result<Vec<glob::Pattern>, String>>())
+            .unwrap_or(Ok(Vec::new()))?,
+    };
+    let mut cmds = command_filter.apply(&parsed);
+    if let Some(path) = matches.value_of("targets") {
+        let target_overrides = load_target_overrides(path)?;
+        ceph_command::override_targets(&mut cmds, &target_overrides);
+    }
+    Ok(cmds)
+}
+
+/// Write to --output, into --out-dir as default_name or to stdout
+fn write_output(matches: &ArgMatches, default_name: &str, contents: &str) -> Result<(), String> {
+    let path = match (matches.value_of("output"), matches.value_of("out-dir")) {
+        (Some(output), _) => Some(Path::new(output).to_path_buf()),
+        (None, Some(dir)) => {
+            fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir, e))?;
+            Some(Path::new(dir).join(default_name))
+        }
+        (None, None) => None,
+    };
+    match path {
+        Some(p) => {
+            File::create(&p)
+                .and_then(|mut f| f.write_all(contents.as_bytes()))
+                .map_err(|e| format!("Failed to write {}: {}", p.display(), e))
+        }
+        None => {
+            io::stdout()
+                .write_all(contents.as_bytes())
+                .map_err(|e| format!("Failed to write to STDOUT: {}", e))
+        }
+    }
+}
+
+/// Arguments that pick and adjust the commands.  Shared by every subcommand.
+fn input_args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    vec![Arg::with_name("input")
+             .long("input")
+             .short("i")
+             .takes_value(true)
+             .multiple(true)
+             .number_of_values(1)
+             .help("Command table or dumped json model to read, globs are allowed.  Can be repeated.  Reads STDIN if \
+                    not given"),
+         Arg::with_name("source")
+             .long("source")
+             .takes_value(true)
+             .help("Name of the file piped in on STDIN.  Decides which daemon the commands are sent to"),
+         Arg::with_name("targets")
+             .long("targets")
+             .takes_value(true)
+             .help("File of `prefix = mon|mgr|osd|mds` lines overriding where commands are sent"),
+         Arg::with_name("overrides")
+             .long("overrides")
+             .takes_value(true)
+             .help("Json file correcting the upstream command definitions"),
+         Arg::with_name("module")
+             .long("module")
+             .takes_value(true)
+             .multiple(true)
+             .number_of_values(1)
+             .possible_values(filter::MODULE_NAMES)
+             .help("Only commands from this module.  Can be repeated"),
+         Arg::with_name("exclude-module")
+             .long("exclude-module")
+             .takes_value(true)
+             .multiple(true)
+             .number_of_values(1)
+             .possible_values(filter::MODULE_NAMES)
+             .help("Leave out commands from this module.  Can be repeated"),
+         Arg::with_name("prefix")
+             .long("prefix")
+             .takes_value(true)
+             .multiple(true)
+             .number_of_values(1)
+             .validator(|glob| filter::prefix_pattern(&glob).map(|_| ()))
+             .help("Only commands whose prefix matches this glob.  Can be repeated"),
+         Arg::with_name("read-only").long("read-only").help("Only commands that need read permission alone"),
+         Arg::with_name("rest-only").long("rest-only").help("Only commands available over the rest api"),
+         Arg::with_name("no-deprecated")
+             .long("no-deprecated")
+             .help("Leave out DEPRECATED and OBSOLETE commands")]
+}
+
+fn output_args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    vec![Arg::with_name("output")
+             .long("output")
+             .short("o")
+             .takes_value(true)
+             .conflicts_with("out-dir")
+             .help("File to write to.  Defaults to STDOUT"),
+         Arg::with_name("out-dir")
+             .long("out-dir")
+             .takes_value(true)
+             .help("Directory to write to using the default file name")]
+}
+
+fn generate(matches: &ArgMatches, cmds: &[ceph_command::Command]) -> Result<(), (i32, String)> {
+    let schemas = match matches.value_of("schemas") {
+        Some(path) => {
+            let schemas = load_schemas(path).map_err(|e| (EXIT_PARSE_ERROR, e))?;
+            // Without a registry every command would be missing one
+            for prefix in schemas.missing(cmds) {
+                warn!("No response schema for {:?}", prefix);
             }
+            schemas
+        }
+        None => schema::SchemaRegistry::default(),
+    };
+    let options = ceph_command::GeneratorOptions {
+        json_output: matches.is_present("json") || matches.is_present("schemas"),
+        schemas,
+        release: matches.value_of("release").map(|r| r.to_string()),
+    };
+    if let Some(path) = matches.value_of("template") {
+        let source = read_file(path).map_err(|e| (EXIT_PARSE_ERROR, e))?;
+        let template_path = Path::new(path);
+        let dir = template_path.parent().map(|d| d.to_path_buf());
+        let output = template::render(&source, dir, cmds, &options)
+            .map_err(|e| (EXIT_GENERATE_ERROR, format!("{}: {}", path, e)))?;
+        // client.go.j2 writes client.go
+        let file_name = match template_path.extension().and_then(|e| e.to_str()) {
+            Some("j2") | Some("jinja") => template_path.file_stem(),
+            _ => template_path.file_name(),
+        };
+        let file_name = file_name.map(|n| n.to_string_lossy().into_owned()).unwrap_or(path.to_string());
+        return write_output(matches, &file_name, &output).map_err(|e| (EXIT_GENERATE_ERROR, e));
+    }
+    let registry = generator::Registry::default();
+    let lang = matches.value_of("lang").unwrap_or("python");
+    let generator = registry.get(lang).ok_or((EXIT_GENERATE_ERROR, format!("Unknown language {}", lang)))?;
+    if let Some(dir) = matches.value_of("out-dir") {
+        return generator.write(cmds, &options, Path::new(dir)).map(|_| ()).map_err(|e| (EXIT_GENERATE_ERROR, e));
+    }
+    let mut files = generator.files(cmds, &options).map_err(|e| (EXIT_GENERATE_ERROR, e))?;
+    if files.len() != 1 {
+        return Err((EXIT_GENERATE_ERROR, format!("{} writes {} files, pass --out-dir", lang, files.len())));
+    }
+    let (file_name, output) = files.remove(0);
+    write_output(matches, &file_name, &output).map_err(|e| (EXIT_GENERATE_ERROR, e))
+}
 
-            let mut configkey_commands: Vec<ceph_command::Command> = cmds.iter()
-                .cloned()
-                .filter(|c| c.module_name == ceph_command::Module::ConfigKey)
-                .collect::<Vec<ceph_command::Command>>();
-            rename_duplicate_functions(&mut configkey_commands);
-            if configkey_commands.len() > 0 {
-                println!("class {}:", ceph_command::Module::ConfigKey.to_string());
-                print_init();
-                for result in configkey_commands.iter() {
-                    let r = result.to_string();
-                    println!("{}", r);
+/// Parse every input on its own and report how many commands it has and
+/// anything the generators won't understand
+fn check(matches: &ArgMatches) -> Result<(), (i32, String)> {
+    let inputs: Vec<&str> = matches.values_of("input").map(|v| v.collect()).unwrap_or_default();
+    if inputs.is_empty() {
+        let cmds = load_commands(matches, &inputs).map_err(|e| (EXIT_PARSE_ERROR, e))?;
+        println!("<stdin>: {} commands", cmds.len());
+        return Ok(());
+    }
+    let mut failed = false;
+    for path in input_paths(&inputs).map_err(|e| (EXIT_PARSE_ERROR, e))? {
+        match load_commands(matches, &[&path]) {
+            Ok(cmds) => {
+                println!("{}: {} commands", path, cmds.len());
+                for cmd in cmds.iter() {
+                    if cmd.module_name == ceph_command::Module::Unknown {
+                        println!("{}: {:?} has an unknown module", path, cmd.signature.prefix);
+                    }
+                    for (name, ceph_type) in cmd.signature.parameters.iter() {
+                        if ceph_type.variant == ceph_command::CephEnum::Unknown {
+                            println!("{}: {:?} parameter {} has an unknown type {}",
+                                     path,
+                                     cmd.signature.prefix,
+                                     name,
+                                     ceph_type.extra.get("type").map(|t| &t[..]).unwrap_or("?"));
+                        }
+                    }
                 }
             }
+            Err(e) => {
+                println!("{}", e);
+                failed = true;
+            }
+        }
+    }
+    if failed {
+        return Err((EXIT_PARSE_ERROR, "Some inputs failed to parse".to_string()));
+    }
+    Ok(())
+}
+
+fn check_commands(matches: &ArgMatches, cmds: &[ceph_command::Command]) -> Result<(), (i32, String)> {
+    let release = matches.value_of("release").map(|r| release::name(r).unwrap_or(r));
+    let mut output = String::new();
+    let mut total = validate::LogReport::default();
+    for path in matches.values_of("commands").unwrap() {
+        let report = if path == "-" {
+            let stdin = io::stdin();
+            validate::check_log(cmds, "<stdin>", stdin.lock(), release).map_err(|e| (EXIT_PARSE_ERROR, e))?
+        } else {
+            let file = File::open(path).map_err(|e| (EXIT_PARSE_ERROR, format!("Failed to read {}: {}", path, e)))?;
+            validate::check_log(cmds, path, BufReader::new(file), release).map_err(|e| (EXIT_PARSE_ERROR, e))?
+        };
+        for problem in report.problems {
+            output.push_str(&format!("{}\n", problem));
+        }
+        total.checked += report.checked;
+        total.invalid += report.invalid;
+        total.deprecated += report.deprecated;
+    }
+    output.push_str(&format!("{} commands, {} invalid, {} deprecated\n", total.checked, total.invalid, total.deprecated));
+    write_output(matches, "check-commands.txt", &output).map_err(|e| (EXIT_GENERATE_ERROR, e))?;
+    if total.invalid > 0 {
+        return Err((EXIT_INVALID_COMMANDS, format!("{} of {} commands are invalid", total.invalid, total.checked)));
+    }
+    Ok(())
+}
 
+/// Caps from --caps DAEMON CAP pairs.  None without any, which allows every
+/// command.
+fn serve_caps(matches: &ArgMatches) -> Result<Option<caps::Caps>, String> {
+    let values: Vec<&str> = match matches.values_of("caps") {
+        Some(values) => values.collect(),
+        None => return Ok(None),
+    };
+    let mut caps = caps::Caps { mon: None, mgr: None, osd: None, mds: None };
+    for pair in values.chunks(2) {
+        let cap = Some(pair[1].to_string());
+        match pair[0] {
+            "mon" => caps.mon = cap,
+            "mgr" => caps.mgr = cap,
+            "osd" => caps.osd = cap,
+            "mds" => caps.mds = cap,
+            daemon => return Err(format!("Unknown daemon {} in --caps", daemon)),
+        }
+    }
+    Ok(Some(caps))
+}
+
+fn serve(matches: &ArgMatches, cmds: Vec<ceph_command::Command>) -> Result<(), (i32, String)> {
+    let caps = serve_caps(matches).map_err(|e| (EXIT_PARSE_ERROR, e))?;
+    let fixtures = matches.value_of("fixtures").map(PathBuf::from);
+    let address = matches.value_of("listen").unwrap();
+    let listener = TcpListener::bind(address)
+        .map_err(|e| (EXIT_SERVE_ERROR, format!("Failed to listen on {}: {}", address, e)))?;
+    // Print the real address so a port of 0 can be used
+    if let Ok(addr) = listener.local_addr() {
+        println!("Listening on {}", addr);
+        let _ = io::stdout().flush();
+    }
+    serve::FakeMonitor::new(cmds, fixtures, caps)
+        .serve(listener)
+        .map_err(|e| (EXIT_SERVE_ERROR, format!("Failed to accept a connection: {}", e)))
+}
+
+fn run(matches: &ArgMatches) -> Result<(), (i32, String)> {
+    match matches.subcommand() {
+        ("check", Some(sub)) => check(sub),
+        ("diff", Some(sub)) => {
+            let old = load_commands(sub, &[sub.value_of("old").unwrap()]).map_err(|e| (EXIT_PARSE_ERROR, e))?;
+            let new = load_commands(sub, &[sub.value_of("new").unwrap()]).map_err(|e| (EXIT_PARSE_ERROR, e))?;
+            let changes: Vec<String> = diff::diff(&old, &new).iter().map(|c| c.to_string()).collect();
+            let mut output = changes.join("\n");
+            if !output.is_empty() {
+                output.push('\n');
+            }
+            write_output(sub, "ceph_command.diff", &output).map_err(|e| (EXIT_GENERATE_ERROR, e))
         }
-        _ => {
-            println!("Failed to parse commands");
+        (name, Some(sub)) => {
+            let inputs: Vec<&str> = sub.values_of("input").map(|v| v.collect()).unwrap_or(Vec::new());
+            if name == "check-commands" && inputs.is_empty() && sub.values_of("commands").unwrap().any(|p| p == "-") {
+                return Err((EXIT_PARSE_ERROR, "Pass the command table with --input to read commands from STDIN".to_string()));
+            }
+            let cmds = load_commands(sub, &inputs).map_err(|e| (EXIT_PARSE_ERROR, e))?;
+            match name {
+                "generate" => generate(sub, &cmds),
+                "dump" => {
+                    let (file_name, output) = match sub.value_of("format") {
+                        Some("debug") => ("ceph_command.txt", format!("{:#?}\n", cmds)),
+                        _ => ("ceph_command.json", format!("{}\n", model::to_json(&cmds).pretty())),
+                    };
+                    write_output(sub, file_name, &output).map_err(|e| (EXIT_GENERATE_ERROR, e))
+                }
+                "print" => write_output(sub, "MonCommands.h", &header::generate(&cmds)).map_err(|e| (EXIT_GENERATE_ERROR, e)),
+                "docs" => write_output(sub, "ceph_command.md", &docs::generate(&cmds)).map_err(|e| (EXIT_GENERATE_ERROR, e)),
+                "caps" => {
+                    let line = caps::Caps::from_commands(&cmds).auth_get_or_create(sub.value_of("entity").unwrap());
+                    write_output(sub, "caps.txt", &format!("{}\n", line)).map_err(|e| (EXIT_GENERATE_ERROR, e))
+                }
+                "serve" => serve(sub, cmds),
+                "check-commands" => check_commands(sub, &cmds),
+                _ => unreachable!(),
+            }
         }
+        _ => unreachable!(),
+    }
+}
+
+fn main() {
+    // Read in the MonCommands.h file and produce ceph-commands.py file
+    let languages = generator::Registry::default().names();
+    let matches = App::new("command_parser")
+        .version(crate_version!())
+        .about("Parses the Ceph command tables and generates client code from them")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .setting(AppSettings::VersionlessSubcommands)
+        .arg(Arg::with_name("log-level")
+            .long("log-level")
+            .takes_value(true)
+            .global(true)
+            .default_value("warn")
+            .possible_values(&["error", "warn", "info", "debug", "trace"]))
+        .subcommand(SubCommand::with_name("generate")
+            .about("Generate a client library")
+            .args(&input_args())
+            .args(&output_args())
+            .arg(Arg::with_name("lang")
+                .long("lang")
+                .takes_value(true)
+                .default_value("python")
+                .possible_values(&languages))
+            .arg(Arg::with_name("template")
+                .long("template")
+                .takes_value(true)
+                .help("Render this minijinja template instead of a built in language"))
+            .arg(Arg::with_name("json")
+                .long("json")
+                .help("Send format=json to commands that support it and decode the output"))
+            .arg(Arg::with_name("schemas")
+                .long("schemas")
+                .takes_value(true)
+                .help("Json response schema registry.  Implies --json"))
+            .arg(Arg::with_name("release")
+                .long("release")
+                .takes_value(true)
+                .help("Ceph release the commands come from, such as reef or 18.2.1.  Versions the python-package")))
+        .subcommand(SubCommand::with_name("check")
+            .about("Parse the inputs and report problems without generating anything")
+            .args(&input_args()))
+        .subcommand(SubCommand::with_name("diff")
+            .about("Show the commands added, removed or changed between two command tables")
+            .args(&input_args()[1..])
+            .args(&output_args())
+            .arg(Arg::with_name("old").required(true).index(1))
+            .arg(Arg::with_name("new").required(true).index(2)))
+        .subcommand(SubCommand::with_name("dump")
+            .about("Print the parsed command model")
+            .args(&input_args())
+            .args(&output_args())
+            .arg(Arg::with_name("format")
+                .long("format")
+                .takes_value(true)
+                .default_value("json")
+                .possible_values(&["json", "debug"])
+                .help("json can be passed back in with --input, debug is the Rust structs")))
+        .subcommand(SubCommand::with_name("print")
+            .about("Print the commands back out in MonCommands.h syntax")
+            .args(&input_args())
+            .args(&output_args()))
+        .subcommand(SubCommand::with_name("docs")
+            .about("Generate markdown documentation")
+            .args(&input_args())
+            .args(&output_args()))
+        .subcommand(SubCommand::with_name("caps")
+            .about("Print the ceph auth command granting exactly the selected commands")
+            .args(&input_args())
+            .args(&output_args())
+            .arg(Arg::with_name("entity")
+                .long("entity")
+                .takes_value(true)
+                .required(true)
+                .help("Name of the cephx user, for example client.monitoring")))
+        .subcommand(SubCommand::with_name("check-commands")
+            .about("Check newline delimited json commands, such as an audit log, against the command table")
+            .args(&input_args())
+            .args(&output_args())
+            .arg(Arg::with_name("commands")
+                .required(true)
+                .multiple(true)
+                .help("Files of json commands, one per line.  - reads STDIN"))
+            .arg(Arg::with_name("release")
+                .long("release")
+                .takes_value(true)
+                .help("Ceph release the command table comes from, such as reef or 18.2.1.  Named in the report")))
+        .subcommand(SubCommand::with_name("serve")
+            .about("Answer commands like a monitor would so clients can be tested without a cluster")
+            .args(&input_args())
+            .arg(Arg::with_name("listen")
+                .long("listen")
+                .takes_value(true)
+                .default_value("127.0.0.1:8003")
+                .help("Address to listen on.  Speaks newline delimited json and HTTP POST"))
+            .arg(Arg::with_name("fixtures")
+                .long("fixtures")
+                .takes_value(true)
+                .help("Directory of canned responses, one <prefix_with_underscores>.json per command"))
+            .arg(Arg::with_name("caps")
+                .long("caps")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(2)
+                .value_names(&["DAEMON", "CAP"])
+                .help("Caps of the connecting client, like --caps mon 'allow r'.  Can be repeated.  Everything is \
+                       allowed if not given")))
+        .get_matches();
+
+    // --log-level can come before or after the subcommand
+    let level = matches.subcommand()
+        .1
+        .and_then(|sub| sub.value_of("log-level"))
+        .or(matches.value_of("log-level"))
+        .and_then(|l| log::LogLevel::from_str(l).ok());
+    simple_logger::init_with_level(level.unwrap_or(log::LogLevel::Warn)).unwrap();
+
+    if let Err((code, msg)) = run(&matches) {
+        let _ = writeln!(io::stderr(), "{}", msg);
+        process::exit(code);
     }
 }
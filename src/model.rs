@@ -0,0 +1,395 @@
+extern crate rustc_serialize;
+
+use rustc_serialize::json::{Json, Object};
+
+use std::collections::{BTreeMap, HashMap};
+
+use ceph_command::{AllowedRepeats, Availability, CephEnum, CephType, Command, Flag, Module, Permissions, Signature,
+                   Syntax, Target};
+
+/// Version of the dump format.  Bump it whenever a key changes meaning or is
+/// removed.  Adding a key doesn't need a new version.
+pub const MODEL_VERSION: u64 = 1;
+
+#[test]
+fn test_model_round_trip() {
+    let mut params = HashMap::new();
+    params.insert("pool".to_string(), CephType::new(true, CephEnum::CephPoolname { allowed_repeats: None }));
+    params.insert("pg_num".to_string(), CephType::new(false, CephEnum::CephInt { min: Some(0), max: None }));
+    params.insert("ratio".to_string(), CephType::new(false, CephEnum::CephFloat { min: Some(0.0), max: Some(1.0) }));
+    params.insert("name".to_string(), CephType::new(true, CephEnum::CephString {
+        goodchars: Some("A-Za-z0-9-_.".to_string()), badchars: Some("/".to_string()),
+        allowed_repeats: AllowedRepeats::Many }));
+    params.insert("mode".to_string(), CephType::new(true, CephEnum::CephChoices {
+        choices: vec!["on".to_string(), "off".to_string()], allowed_repeats: AllowedRepeats::One }));
+    params.get_mut("name").unwrap().extra.insert("by".to_string(), "user".to_string());
+    params.get_mut("mode").unwrap().positional = false;
+    let mut renames = HashMap::new();
+    renames.insert("pg_num".to_string(), "placement_groups".to_string());
+    let cmds = vec![Command {
+        signature: Signature {
+            prefix: "osd pool create".to_string(),
+            duplicate: false,
+            parameters: params,
+            renames,
+            order: vec!["pool".to_string(), "pg_num".to_string(), "ratio".to_string(), "name".to_string(),
+                        "mode".to_string()],
+        },
+        helpstring: "create pool".to_string(),
+        module_name: Module::Osd,
+        permissions: Permissions { read: true, write: true, execute: false },
+        availability: Availability::Cli,
+        flags: Some(vec![Flag::NoForward, Flag::Deprecated]),
+        target: Target::Mgr,
+        syntax: Syntax { availability: false, bare_noforward: true },
+    }];
+
+    let dumped = to_json(&cmds).to_string();
+    assert!(dumped.starts_with("{\"commands\":[{"));
+    assert!(dumped.contains("\"params\":[{\"allowempty\":false,\"n\":\"1\",\"name\":\"pool\""));
+    assert_eq!(cmds, from_json(&dumped).unwrap());
+}
+
+#[test]
+fn test_model_version() {
+    assert_eq!(Err("model version 2 is newer than the supported version 1".to_string()),
+               from_json(r#"{"version": 2, "commands": []}"#));
+    assert_eq!(Err("commands[0]: module should be a string, found 1".to_string()),
+               from_json(r#"{"version": 1, "commands": [{"prefix": "osd ls", "helpstring": "", "module": 1}]}"#));
+}
+
+fn repeats_to_json(allowed_repeats: &AllowedRepeats) -> Json {
+    match *allowed_repeats {
+        AllowedRepeats::One => Json::String("1".to_string()),
+        AllowedRepeats::Many => Json::String("N".to_string()),
+    }
+}
+
+// f32 ranges are written with their shortest decimal form so 0.1 doesn't
+// come out as 0.10000000149011612
+fn float_to_json(f: f32) -> Json {
+    Json::F64(format!("{}", f).parse::<f64>().unwrap_or(f as f64))
+}
+
+fn param_to_json(name: &str, ceph_type: &CephType) -> Json {
+    let mut obj = Object::new();
+    obj.insert("name".to_string(), Json::String(name.to_string()));
+    obj.insert("type".to_string(), Json::String(ceph_type.variant.type_name().to_string()));
+    obj.insert("req".to_string(), Json::Boolean(ceph_type.req));
+    obj.insert("positional".to_string(), Json::Boolean(ceph_type.positional));
+    obj.insert("allowempty".to_string(), Json::Boolean(ceph_type.allow_empty));
+    if !ceph_type.extra.is_empty() {
+        let extra: Object = ceph_type.extra.iter().map(|(k, v)| (k.clone(), Json::String(v.clone()))).collect();
+        obj.insert("extra".to_string(), Json::Object(extra));
+    }
+    match ceph_type.variant {
+        CephEnum::CephInt { min, max } => {
+            if let Some(m) = min {
+                obj.insert("min".to_string(), Json::I64(m));
+            }
+            if let Some(m) = max {
+                obj.insert("max".to_string(), Json::I64(m));
+            }
+        }
+        CephEnum::CephFloat { min, max } => {
+            if let Some(m) = min {
+                obj.insert("min".to_string(), float_to_json(m));
+            }
+            if let Some(m) = max {
+                obj.insert("max".to_string(), float_to_json(m));
+            }
+        }
+        CephEnum::CephString { ref goodchars, ref badchars, ref allowed_repeats } => {
+            if let Some(chars) = goodchars {
+                obj.insert("goodchars".to_string(), Json::String(chars.clone()));
+            }
+            if let Some(chars) = badchars {
+                obj.insert("badchars".to_string(), Json::String(chars.clone()));
+            }
+            obj.insert("n".to_string(), repeats_to_json(allowed_repeats));
+        }
+        CephEnum::CephPoolname { ref allowed_repeats } => {
+            obj.insert("n".to_string(),
+                       repeats_to_json(allowed_repeats.as_ref().unwrap_or(&AllowedRepeats::One)));
+        }
+        CephEnum::CephChoices { ref choices, ref allowed_repeats } => {
+            obj.insert("strings".to_string(),
+                       Json::Array(choices.iter().map(|c| Json::String(c.clone())).collect()));
+            obj.insert("n".to_string(), repeats_to_json(allowed_repeats));
+        }
+        _ => {}
+    }
+    Json::Object(obj)
+}
+
+/// One command in the dump format
+pub fn command_to_json(cmd: &Command) -> Json {
+    let mut obj = Object::new();
+    obj.insert("prefix".to_string(), Json::String(cmd.signature.prefix.clone()));
+    obj.insert("duplicate".to_string(), Json::Boolean(cmd.signature.duplicate));
+    obj.insert("params".to_string(),
+               Json::Array(cmd.signature
+                   .ordered_parameters()
+                   .iter()
+                   .map(|&(name, ceph_type)| param_to_json(name, ceph_type))
+                   .collect()));
+    let renames: Object = cmd.signature
+        .renames
+        .iter()
+        .map(|(k, v)| (k.clone(), Json::String(v.clone())))
+        .collect();
+    obj.insert("renames".to_string(), Json::Object(renames));
+    obj.insert("helpstring".to_string(), Json::String(cmd.helpstring.clone()));
+    obj.insert("module".to_string(), Json::String(cmd.module_name.name().to_string()));
+    obj.insert("permissions".to_string(), Json::String(cmd.permissions.to_string()));
+    obj.insert("availability".to_string(), Json::String(cmd.availability.name().to_string()));
+    let flags = match cmd.flags {
+        Some(ref flags) => Json::Array(flags.iter().map(|f| Json::String(f.name().to_string())).collect()),
+        None => Json::Null,
+    };
+    obj.insert("flags".to_string(), flags);
+    obj.insert("target".to_string(), Json::String(cmd.target.name().to_string()));
+    let mut syntax = Object::new();
+    syntax.insert("availability".to_string(), Json::Boolean(cmd.syntax.availability));
+    syntax.insert("bare_noforward".to_string(), Json::Boolean(cmd.syntax.bare_noforward));
+    obj.insert("syntax".to_string(), Json::Object(syntax));
+    Json::Object(obj)
+}
+
+/// Serialize cmds to the versioned dump format.  See the README for the
+/// layout.
+pub fn to_json(cmds: &[Command]) -> Json {
+    let mut obj = BTreeMap::new();
+    obj.insert("version".to_string(), Json::U64(MODEL_VERSION));
+    obj.insert("commands".to_string(), Json::Array(cmds.iter().map(command_to_json).collect()));
+    Json::Object(obj)
+}
+
+fn get<'a>(obj: &'a Object, key: &str) -> Result<&'a Json, String> {
+    obj.get(key).ok_or(format!("missing {}", key))
+}
+
+fn get_string<'a>(obj: &'a Object, key: &str) -> Result<&'a str, String> {
+    let value = get(obj, key)?;
+    value.as_string().ok_or(format!("{} should be a string, found {}", key, value))
+}
+
+fn get_optional_string(obj: &Object, key: &str) -> Result<Option<String>, String> {
+    match obj.get(key) {
+        None | Some(&Json::Null) => Ok(None),
+        Some(Json::String(s)) => Ok(Some(s.clone())),
+        Some(other) => Err(format!("{} should be a string, found {}", key, other)),
+    }
+}
+
+fn get_bool(obj: &Object, key: &str, default: bool) -> Result<bool, String> {
+    match obj.get(key) {
+        None => Ok(default),
+        Some(&Json::Boolean(b)) => Ok(b),
+        Some(other) => Err(format!("{} should be true or false, found {}", key, other)),
+    }
+}
+
+fn get_repeats(obj: &Object) -> Result<AllowedRepeats, String> {
+    match obj.get("n") {
+        None => Ok(AllowedRepeats::One),
+        Some(Json::String(n)) if n == "1" || n == "N" => Ok(AllowedRepeats::from_str(n)),
+        Some(other) => Err(format!("n should be \"1\" or \"N\", found {}", other)),
+    }
+}
+
+fn get_range<T, F>(obj: &Object, key: &str, convert: F) -> Result<Option<T>, String>
+    where F: Fn(&Json) -> Option<T>
+{
+    match obj.get(key) {
+        None | Some(&Json::Null) => Ok(None),
+        Some(value) => convert(value).map(Some).ok_or(format!("{} out of range: {}", key, value)),
+    }
+}
+
+fn param_from_json(json: &Json) -> Result<(String, CephType), String> {
+    let obj = json.as_object().ok_or("param should be an object".to_string())?;
+    let name = get_string(obj, "name")?.to_string();
+    let type_name = get_string(obj, "type").map_err(|e| format!("{}: {}", name, e))?;
+    let variant = match type_name {
+        "CephInt" => {
+            let to_i64 = |j: &Json| j.as_i64();
+            CephEnum::CephInt {
+                min: get_range(obj, "min", to_i64)?,
+                max: get_range(obj, "max", to_i64)?,
+            }
+        }
+        "CephFloat" => {
+            let to_f32 = |j: &Json| j.as_f64().map(|f| f as f32);
+            CephEnum::CephFloat {
+                min: get_range(obj, "min", to_f32)?,
+                max: get_range(obj, "max", to_f32)?,
+            }
+        }
+        "CephString" => {
+            CephEnum::CephString {
+                goodchars: get_optional_string(obj, "goodchars").map_err(|e| format!("{}: {}", name, e))?,
+                badchars: get_optional_string(obj, "badchars").map_err(|e| format!("{}: {}", name, e))?,
+                allowed_repeats: get_repeats(obj)?,
+            }
+        }
+        "CephSocketpath" => CephEnum::CephSocketpath,
+        "CephIPAddr" => CephEnum::CephIPAddr,
+        "CephEntityAddr" => CephEnum::CephEntityAddr,
+        "CephPoolname" => {
+            // The parser leaves this unset for n=1 so keep that on the way back in
+            let allowed_repeats = match get_repeats(obj)? {
+                AllowedRepeats::One => None,
+                many => Some(many),
+            };
+            CephEnum::CephPoolname { allowed_repeats }
+        }
+        "CephObjectname" => CephEnum::CephObjectname,
+        "CephPgid" => CephEnum::CephPgid,
+        "CephName" => CephEnum::CephName,
+        "CephOsdName" => CephEnum::CephOsdName,
+        "CephChoices" => {
+            let mut choices = Vec::new();
+            match obj.get("strings") {
+                Some(Json::Array(list)) => {
+                    for choice in list.iter() {
+                        match choice.as_string() {
+                            Some(s) => choices.push(s.to_string()),
+                            None => return Err(format!("{}: strings should be strings, found {}", name, choice)),
+                        }
+                    }
+                }
+                _ => return Err(format!("{}: CephChoices needs a list of strings", name)),
+            }
+            CephEnum::CephChoices { choices, allowed_repeats: get_repeats(obj)? }
+        }
+        "CephFilepath" => CephEnum::CephFilepath,
+        "CephFragment" => CephEnum::CephFragment,
+        "CephUUID" => CephEnum::CephUUID,
+        "CephPrefix" => CephEnum::CephPrefix,
+        "CephBool" => CephEnum::CephBool,
+        "CephTimespan" => CephEnum::CephTimespan,
+        "CephPgidList" => CephEnum::CephPgidList,
+        "CephArgtype" => CephEnum::CephArgtype,
+        _ => CephEnum::Unknown,
+    };
+    let mut extra = BTreeMap::new();
+    match obj.get("extra") {
+        None => {}
+        Some(Json::Object(map)) => {
+            for (key, value) in map.iter() {
+                let value = value.as_string()
+                    .ok_or(format!("{}: extra should map to strings, found {}", name, value))?;
+                extra.insert(key.clone(), value.to_string());
+            }
+        }
+        Some(other) => return Err(format!("{}: extra should be an object, found {}", name, other)),
+    }
+    Ok((name,
+        CephType {
+        req: get_bool(obj, "req", true)?,
+        variant,
+        positional: get_bool(obj, "positional", true)?,
+        allow_empty: get_bool(obj, "allowempty", false)?,
+        extra,
+    }))
+}
+
+fn command_from_json(json: &Json) -> Result<Command, String> {
+    let obj = json.as_object().ok_or("command should be an object".to_string())?;
+    let prefix = get_string(obj, "prefix")?.to_string();
+
+    let mut parameters = HashMap::new();
+    let mut order = Vec::new();
+    match obj.get("params") {
+        None => {}
+        Some(Json::Array(list)) => {
+            for param in list.iter() {
+                let (name, ceph_type) = param_from_json(param)?;
+                order.push(name.clone());
+                parameters.insert(name, ceph_type);
+            }
+        }
+        Some(other) => return Err(format!("params should be a list, found {}", other)),
+    }
+    let mut renames = HashMap::new();
+    match obj.get("renames") {
+        None => {}
+        Some(Json::Object(map)) => {
+            for (name, value) in map.iter() {
+                let api_name = value.as_string()
+                    .ok_or(format!("renames should map to strings, found {}", value))?;
+                renames.insert(name.clone(), api_name.to_string());
+            }
+        }
+        Some(other) => return Err(format!("renames should be an object, found {}", other)),
+    }
+    let flags = match obj.get("flags") {
+        None | Some(&Json::Null) => None,
+        Some(Json::Array(list)) => {
+            let mut flags = Vec::new();
+            for flag in list.iter() {
+                let name = flag.as_string().ok_or(format!("flags should be strings, found {}", flag))?;
+                flags.push(Flag::from_str(name));
+            }
+            Some(flags)
+        }
+        Some(other) => return Err(format!("flags should be a list, found {}", other)),
+    };
+    let target = match obj.get("target") {
+        None => Target::Mon,
+        Some(_) => {
+            let name = get_string(obj, "target")?;
+            Target::from_str(name).ok_or(format!("unknown target {}", name))?
+        }
+    };
+
+    let syntax = match obj.get("syntax") {
+        None => Syntax::default(),
+        Some(Json::Object(syntax)) => {
+            Syntax {
+                availability: get_bool(syntax, "availability", true)?,
+                bare_noforward: get_bool(syntax, "bare_noforward", false)?,
+            }
+        }
+        Some(other) => return Err(format!("syntax should be an object, found {}", other)),
+    };
+
+    Ok(Command {
+        signature: Signature {
+            prefix,
+            duplicate: get_bool(obj, "duplicate", false)?,
+            parameters,
+            renames,
+            order,
+        },
+        helpstring: get_string(obj, "helpstring")?.to_string(),
+        module_name: Module::from_str(get_string(obj, "module")?),
+        permissions: Permissions::from_str(get_string(obj, "permissions")?),
+        availability: Availability::from_str(get_string(obj, "availability")?),
+        flags,
+        target,
+        syntax,
+    })
+}
+
+/// Load a model written by to_json.  Dumps from a newer version are refused
+/// rather than half understood.
+pub fn from_json(input: &str) -> Result<Vec<Command>, String> {
+    let json = Json::from_str(input).map_err(|e| e.to_string())?;
+    let obj = json.as_object().ok_or("model should be a json object".to_string())?;
+    let version = obj.get("version")
+        .and_then(|v| v.as_u64())
+        .ok_or("model is missing its version".to_string())?;
+    if version > MODEL_VERSION {
+        return Err(format!("model version {} is newer than the supported version {}", version, MODEL_VERSION));
+    }
+    let list = obj.get("commands")
+        .and_then(|c| c.as_array())
+        .ok_or("model is missing its commands list".to_string())?;
+    let mut cmds = Vec::new();
+    for (i, cmd) in list.iter().enumerate() {
+        cmds.push(command_from_json(cmd).map_err(|e| format!("commands[{}]: {}", i, e))?);
+    }
+    Ok(cmds)
+}
@@ -0,0 +1,296 @@
+extern crate rustc_serialize;
+
+use rustc_serialize::json::{Json, Object};
+
+use std::collections::BTreeMap;
+
+use ceph_command::{CephEnum, CephType, Command};
+
+#[cfg(test)]
+use ceph_command::AllowedRepeats;
+
+#[cfg(test)]
+use test_fixtures::{commands, pool_create};
+
+#[test]
+fn test_apply_overrides() {
+    let input = r#"{
+        "osd pool create": {
+            "helpstring": "create a new pool",
+            "params": {
+                "pg_num": {"min": 1, "max": 65536, "rename": "placement_groups"},
+                "pool_type": {"choices": ["replicated"], "req": true}
+            }
+        },
+        "osd crush rule list": {"hide": true}
+    }"#;
+    let overrides = Overrides::parse(input).unwrap();
+    let mut cmds = commands();
+
+    let unmatched = overrides.apply(&mut cmds).unwrap();
+    assert!(unmatched.is_empty());
+    assert!(!cmds.iter().any(|c| c.signature.prefix == "osd crush rule list"));
+    assert_eq!("osd pool create", cmds[0].signature.prefix);
+    assert_eq!("create a new pool", cmds[0].helpstring);
+    assert_eq!("placement_groups", cmds[0].signature.api_name("pg_num"));
+    assert_eq!(CephType::new(true, CephEnum::CephInt { min: Some(1), max: Some(65536) }),
+               cmds[0].signature.parameters["pg_num"]);
+    assert_eq!(CephType::new(true, CephEnum::CephChoices {
+                   choices: vec!["replicated".to_string()], allowed_repeats: AllowedRepeats::One }),
+               cmds[0].signature.parameters["pool_type"]);
+}
+
+#[test]
+fn test_unmatched_overrides() {
+    let input = r#"{
+        "osd pool create": {"params": {"size": {"req": false}}},
+        "osd pool set-quota": {"helpstring": "set quota"}
+    }"#;
+    let overrides = Overrides::parse(input).unwrap();
+    let mut cmds = vec![pool_create()];
+    let unmatched = overrides.apply(&mut cmds);
+    assert_eq!(Ok(vec!["osd pool create: no parameter named size".to_string(),
+                       "osd pool set-quota: no command with this prefix".to_string()]),
+               unmatched);
+}
+
+#[test]
+fn test_bad_overrides() {
+    let apply = |input: &str| Overrides::parse(input).and_then(|o| o.apply(&mut commands()));
+    assert_eq!(Err("osd pool create: pool has no range to override".to_string()),
+               apply(r#"{"osd pool create": {"params": {"pool": {"min": 1}}}}"#));
+    assert_eq!(Err("osd pool create: pg_num is a CephInt so its range can't be 1.5".to_string()),
+               apply(r#"{"osd pool create": {"params": {"pg_num": {"max": 1.5}}}}"#));
+    assert_eq!(Err("osd pool create: pg_num: max should be a number no bigger than 9223372036854775807, \
+                    found 18446744073709551615".to_string()),
+               apply(r#"{"osd pool create": {"params": {"pg_num": {"max": 18446744073709551615}}}}"#));
+
+    // Integers are read as integers rather than going through a float
+    let mut cmds = commands();
+    Overrides::parse(r#"{"osd pool create": {"params": {"pg_num": {"max": 9007199254740993}}}}"#).unwrap()
+        .apply(&mut cmds).unwrap();
+    assert_eq!(CephType::new(true, CephEnum::CephInt { min: Some(0), max: Some(9007199254740993) }),
+               cmds[0].signature.parameters["pg_num"]);
+}
+
+/// A bound as it's written in the overrides file.  Whether a float is
+/// allowed depends on the parameter it's applied to.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Bound {
+    Int(i64),
+    Float(f64),
+}
+
+impl Bound {
+    fn to_int(self) -> Result<i64, String> {
+        match self {
+            Bound::Int(i) => Ok(i),
+            Bound::Float(f) => Err(format!("is a CephInt so its range can't be {}", f)),
+        }
+    }
+
+    fn to_float(self) -> f32 {
+        match self {
+            Bound::Int(i) => i as f32,
+            Bound::Float(f) => f as f32,
+        }
+    }
+}
+
+/// Changes to a single parameter of a command
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ParamOverride {
+    pub req: Option<bool>,
+    pub min: Option<Bound>,
+    pub max: Option<Bound>,
+    pub choices: Option<Vec<String>>,
+    /// Name to use for the parameter in the generated API.  The command
+    /// still sends it to Ceph under its real name.
+    pub rename: Option<String>,
+}
+
+/// Changes to a command, keyed by Signature.prefix in the overrides file
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CommandOverride {
+    /// Leave the command out of the generated code
+    pub hide: bool,
+    pub helpstring: Option<String>,
+    pub params: BTreeMap<String, ParamOverride>,
+}
+
+/// Corrections to the upstream command definitions that are applied after
+/// parse_commands.  The overrides file is json shaped like:
+/// {"prefix": {"hide": bool, "helpstring": "..", "params": {"name": {"req": bool,
+/// "min": n, "max": n, "choices": [".."], "rename": ".."}}}}
+/// Every key is optional.
+#[derive(Clone, Debug, Default)]
+pub struct Overrides {
+    commands: BTreeMap<String, CommandOverride>,
+}
+
+fn get_bool(obj: &Object, key: &str) -> Result<Option<bool>, String> {
+    match obj.get(key) {
+        None => Ok(None),
+        Some(&Json::Boolean(b)) => Ok(Some(b)),
+        Some(other) => Err(format!("{} should be true or false, found {}", key, other)),
+    }
+}
+
+fn get_bound(obj: &Object, key: &str) -> Result<Option<Bound>, String> {
+    match obj.get(key) {
+        None => Ok(None),
+        Some(&Json::I64(i)) => Ok(Some(Bound::Int(i))),
+        Some(&Json::U64(u)) if u <= i64::MAX as u64 => Ok(Some(Bound::Int(u as i64))),
+        Some(&Json::F64(f)) => Ok(Some(Bound::Float(f))),
+        Some(other) => Err(format!("{} should be a number no bigger than {}, found {}", key, i64::MAX, other)),
+    }
+}
+
+fn get_string(obj: &Object, key: &str) -> Result<Option<String>, String> {
+    match obj.get(key) {
+        None => Ok(None),
+        Some(Json::String(s)) => Ok(Some(s.clone())),
+        Some(other) => Err(format!("{} should be a string, found {}", key, other)),
+    }
+}
+
+impl ParamOverride {
+    fn from_json(json: &Json) -> Result<ParamOverride, String> {
+        let obj = json.as_object().ok_or("expected an object".to_string())?;
+        let choices = match obj.get("choices") {
+            None => None,
+            Some(Json::Array(list)) => {
+                let mut choices = Vec::new();
+                for choice in list.iter() {
+                    match choice {
+                        Json::String(s) => choices.push(s.clone()),
+                        _ => return Err(format!("choices should be strings, found {}", choice)),
+                    }
+                }
+                Some(choices)
+            }
+            Some(other) => return Err(format!("choices should be a list, found {}", other)),
+        };
+        Ok(ParamOverride {
+            req: get_bool(obj, "req")?,
+            min: get_bound(obj, "min")?,
+            max: get_bound(obj, "max")?,
+            choices,
+            rename: get_string(obj, "rename")?,
+        })
+    }
+
+    /// Apply to ceph_type.  Returns an error if the override doesn't fit the type
+    fn apply(&self, ceph_type: &mut CephType) -> Result<(), String> {
+        if let Some(req) = self.req {
+            ceph_type.req = req;
+        }
+        if self.min.is_some() || self.max.is_some() {
+            match ceph_type.variant {
+                CephEnum::CephInt { ref mut min, ref mut max } => {
+                    if let Some(m) = self.min {
+                        *min = Some(m.to_int()?);
+                    }
+                    if let Some(m) = self.max {
+                        *max = Some(m.to_int()?);
+                    }
+                }
+                CephEnum::CephFloat { ref mut min, ref mut max } => {
+                    if let Some(m) = self.min {
+                        *min = Some(m.to_float());
+                    }
+                    if let Some(m) = self.max {
+                        *max = Some(m.to_float());
+                    }
+                }
+                _ => return Err("has no range to override".to_string()),
+            }
+        }
+        if let Some(ref new_choices) = self.choices {
+            match ceph_type.variant {
+                CephEnum::CephChoices { ref mut choices, .. } => {
+                    *choices = new_choices.clone();
+                }
+                _ => return Err("has no choices to override".to_string()),
+            }
+        }
+        Ok(())
+    }
+}
+
+impl CommandOverride {
+    fn from_json(json: &Json) -> Result<CommandOverride, String> {
+        let obj = json.as_object().ok_or("expected an object".to_string())?;
+        let mut params = BTreeMap::new();
+        match obj.get("params") {
+            None => {}
+            Some(Json::Object(p)) => {
+                for (name, value) in p.iter() {
+                    let param = ParamOverride::from_json(value).map_err(|e| format!("{}: {}", name, e))?;
+                    params.insert(name.clone(), param);
+                }
+            }
+            Some(other) => return Err(format!("params should be an object, found {}", other)),
+        }
+        Ok(CommandOverride {
+            hide: get_bool(obj, "hide")?.unwrap_or(false),
+            helpstring: get_string(obj, "helpstring")?,
+            params,
+        })
+    }
+}
+
+impl Overrides {
+    pub fn parse(input: &str) -> Result<Overrides, String> {
+        let json = Json::from_str(input).map_err(|e| e.to_string())?;
+        let obj = json.as_object().ok_or("overrides must be a json object keyed by command prefix".to_string())?;
+        let mut commands = BTreeMap::new();
+        for (prefix, value) in obj.iter() {
+            let cmd = CommandOverride::from_json(value).map_err(|e| format!("{}: {}", prefix, e))?;
+            commands.insert(prefix.clone(), cmd);
+        }
+        Ok(Overrides { commands })
+    }
+
+    /// Apply the overrides to cmds, dropping hidden commands.  Returns a
+    /// description of every override that didn't match anything upstream, or
+    /// an error for one that matched a parameter it doesn't fit.
+    pub fn apply(&self, cmds: &mut Vec<Command>) -> Result<Vec<String>, String> {
+        let mut unmatched: Vec<String> = Vec::new();
+        for (prefix, cmd_override) in self.commands.iter() {
+            if !cmds.iter().any(|c| &c.signature.prefix == prefix) {
+                unmatched.push(format!("{}: no command with this prefix", prefix));
+                continue;
+            }
+            for (name, param_override) in cmd_override.params.iter() {
+                let mut found = false;
+                for cmd in cmds.iter_mut().filter(|c| &c.signature.prefix == prefix) {
+                    if let Some(ceph_type) = cmd.signature.parameters.get_mut(name) {
+                        found = true;
+                        param_override.apply(ceph_type).map_err(|e| format!("{}: {} {}", prefix, name, e))?;
+                    }
+                    if let Some(ref rename) = param_override.rename {
+                        if cmd.signature.parameters.contains_key(name) {
+                            cmd.signature.renames.insert(name.clone(), rename.clone());
+                        }
+                    }
+                }
+                if !found {
+                    unmatched.push(format!("{}: no parameter named {}", prefix, name));
+                }
+            }
+            if let Some(ref helpstring) = cmd_override.helpstring {
+                for cmd in cmds.iter_mut().filter(|c| &c.signature.prefix == prefix) {
+                    cmd.helpstring = helpstring.clone();
+                }
+            }
+        }
+        cmds.retain(|c| {
+            match self.commands.get(&c.signature.prefix) {
+                Some(cmd_override) => !cmd_override.hide,
+                None => true,
+            }
+        });
+        Ok(unmatched)
+    }
+}
@@ -0,0 +1,230 @@
+use rustc_serialize::json::Json;
+
+use ceph_command::{self, CephEnum, Command, Flag, GeneratorOptions, Module, Target};
+use generator::Generator;
+use schema::camel_case;
+
+#[cfg(test)]
+use test_fixtures::pool_create;
+
+#[test]
+fn test_proto_request() {
+    let mut cmd = pool_create();
+    // Sent to an osd so the request gains an osd_id
+    cmd.target = Target::Osd;
+    let request = request(&cmd);
+    assert!(request.starts_with("// create pool\nmessage OsdPoolCreateRequest {\n"));
+    assert!(request.contains("  enum PoolType {\n    POOL_TYPE_UNSPECIFIED = 0;\n    \
+                              POOL_TYPE_REPLICATED = 1 [(ceph_choice) = \"replicated\"];\n    \
+                              POOL_TYPE_ERASURE = 2 [(ceph_choice) = \"erasure\"];\n  }\n"));
+    assert!(request.contains("  string pool = 1;\n  int64 pg_num = 2;\n  optional PoolType pool_type = 3;\n"));
+    assert!(request.contains("  repeated string crush_rule = 4 [json_name = \"crush-rule\"];\n"));
+    assert!(request.contains("  // The osd to send the command to\n  int64 osd_id = 5;\n}\n"));
+}
+
+#[test]
+fn test_proto_service() {
+    let mut cmd = pool_create();
+    cmd.target = Target::Osd;
+    let output = generate(&[cmd], &GeneratorOptions::default());
+    assert!(output.contains("service OsdCommand {\n  // create pool\n  \
+                             rpc OsdPoolCreate(OsdPoolCreateRequest) returns (CommandResponse) {\n    \
+                             option (ceph_prefix) = \"osd pool create\";\n    option (ceph_target) = \"osd\";\n    \
+                             option deprecated = true;\n  }\n}\n"));
+}
+
+fn preamble() -> String {
+    let mut output = String::new();
+    output.push_str("// Generated by command_parser from the Ceph command tables.  Do not edit.\n");
+    output.push_str("// Field numbers follow the order parameters are declared upstream so they\n");
+    output.push_str("// aren't stable from one release to the next.\n\n");
+    output.push_str("syntax = \"proto3\";\n\n");
+    output.push_str("package ceph_command;\n\n");
+    output.push_str("import \"google/protobuf/descriptor.proto\";\n\n");
+    output.push_str("extend google.protobuf.MethodOptions {\n");
+    output.push_str("  // The prefix of the command an rpc runs\n");
+    output.push_str("  string ceph_prefix = 50000;\n");
+    output.push_str("  // The daemon the command is sent to: mon, mgr, osd or mds\n");
+    output.push_str("  string ceph_target = 50001;\n");
+    output.push_str("}\n\n");
+    output.push_str("extend google.protobuf.EnumValueOptions {\n");
+    output.push_str("  // The string ceph expects for a choice\n");
+    output.push_str("  string ceph_choice = 50002;\n");
+    output.push_str("}\n\n");
+    output.push_str("// What a daemon answered\n");
+    output.push_str("message CommandResponse {\n");
+    output.push_str("  // The output buffer\n");
+    output.push_str("  bytes outbuf = 1;\n");
+    output.push_str("  // The status string\n");
+    output.push_str("  string outs = 2;\n");
+    output.push_str("  // 0 or a negative errno\n");
+    output.push_str("  int32 return_code = 3;\n");
+    output.push_str("}\n\n");
+    output
+}
+
+fn string_literal(s: &str) -> String {
+    Json::String(s.to_string()).to_string()
+}
+
+/// A parameter name as a proto field name
+fn field_name(key: &str) -> String {
+    let name: String = key.chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '_' }).collect();
+    if name.chars().next().is_none_or(|c| !c.is_ascii_alphabetic()) {
+        format!("p{}", name)
+    } else {
+        name
+    }
+}
+
+/// UPPER_SNAKE_CASE of the words in name
+fn upper_snake(name: &str) -> String {
+    name.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_uppercase())
+        .collect::<Vec<String>>()
+        .join("_")
+}
+
+/// Enum values for choices.  Values share a scope with the message so they
+/// all start with the enum's name, and proto3 needs a zero value first.
+fn choices_enum(key: &str, choices: &[String]) -> String {
+    let name = camel_case(key);
+    let value_prefix = upper_snake(key);
+    let mut values: Vec<String> = vec![format!("{}_UNSPECIFIED", value_prefix)];
+    for choice in choices.iter() {
+        let value = format!("{}_{}", value_prefix, upper_snake(choice)).trim_end_matches('_').to_string();
+        let mut unique = value.clone();
+        let mut n = 2;
+        while values.contains(&unique) {
+            unique = format!("{}_{}", value, n);
+            n += 1;
+        }
+        values.push(unique);
+    }
+    let mut output = String::new();
+    output.push_str(&format!("  enum {} {{\n", name));
+    output.push_str(&format!("    {} = 0;\n", values[0]));
+    for (i, (value, choice)) in values[1..].iter().zip(choices.iter()).enumerate() {
+        output.push_str(&format!("    {} = {} [(ceph_choice) = {}];\n", value, i + 1, string_literal(choice)));
+    }
+    output.push_str("  }\n");
+    output
+}
+
+/// The proto type of a single value of a parameter
+fn proto_type(enum_name: &str, variant: &CephEnum) -> String {
+    match variant {
+        &CephEnum::CephInt { .. } => "int64".to_string(),
+        &CephEnum::CephFloat { .. } => "double".to_string(),
+        &CephEnum::CephBool => "bool".to_string(),
+        CephEnum::CephChoices { choices, .. } if !choices.is_empty() => enum_name.to_string(),
+        _ => "string".to_string(),
+    }
+}
+
+fn comment(output: &mut String, indent: &str, text: &str) {
+    for line in text.lines() {
+        output.push_str(&format!("{}// {}\n", indent, line.trim()).replace("// \n", "//\n"));
+    }
+}
+
+/// Name of the rpc for a command
+fn rpc_name(cmd: &Command) -> String {
+    camel_case(&cmd.method_name())
+}
+
+/// The request message for a command
+fn request(cmd: &Command) -> String {
+    let mut enums = String::new();
+    let mut fields = String::new();
+    let mut number = 0;
+    for (key, ceph_type) in cmd.signature.ordered_parameters() {
+        let enum_name = camel_case(key);
+        if let CephEnum::CephChoices { ref choices, .. } = ceph_type.variant {
+            if !choices.is_empty() {
+                enums.push_str(&choices_enum(key, choices));
+            }
+        }
+        let label = if ceph_type.variant.is_many() {
+            "repeated "
+        } else if !ceph_type.req {
+            "optional "
+        } else {
+            ""
+        };
+        let name = field_name(cmd.signature.api_name(key));
+        let json_name = if name != *key {
+            format!(" [json_name = {}]", string_literal(key))
+        } else {
+            String::new()
+        };
+        number += 1;
+        fields.push_str(&format!("  {}{} {} = {}{};\n",
+                                 label,
+                                 proto_type(&enum_name, &ceph_type.variant),
+                                 name,
+                                 number,
+                                 json_name));
+    }
+    match cmd.target {
+        Target::Osd => fields.push_str(&format!("  // The osd to send the command to\n  int64 osd_id = {};\n", number + 1)),
+        Target::Mds => fields.push_str(&format!("  // The mds to send the command to\n  string mds_id = {};\n", number + 1)),
+        _ => {}
+    }
+
+    let mut output = String::new();
+    comment(&mut output, "", &cmd.helpstring);
+    output.push_str(&format!("message {}Request {{\n", rpc_name(cmd)));
+    output.push_str(&enums);
+    output.push_str(&fields);
+    output.push_str("}\n");
+    output
+}
+
+/// The rpc for a command
+fn rpc(cmd: &Command) -> String {
+    let mut output = String::new();
+    comment(&mut output, "  ", &cmd.helpstring);
+    output.push_str(&format!("  rpc {}({}Request) returns (CommandResponse) {{\n", rpc_name(cmd), rpc_name(cmd)));
+    output.push_str(&format!("    option (ceph_prefix) = {};\n", string_literal(&cmd.signature.prefix)));
+    output.push_str(&format!("    option (ceph_target) = \"{}\";\n", cmd.target.name()));
+    if cmd.flags.as_ref().is_some_and(|flags| flags.contains(&Flag::Deprecated)) {
+        output.push_str("    option deprecated = true;\n");
+    }
+    output.push_str("  }\n");
+    output
+}
+
+/// Generate a .proto file for cmds with a service per Module and an rpc per
+/// command.  Every rpc returns the same CommandResponse.
+pub fn generate(cmds: &[Command], _options: &GeneratorOptions) -> String {
+    let mut output = preamble();
+
+    for (module, module_commands) in ceph_command::group_by_module(cmds, &Module::all()) {
+        output.push_str(&format!("service {} {{\n", module.to_string()));
+        let rpcs: Vec<String> = module_commands.iter().map(rpc).collect();
+        output.push_str(&rpcs.join("\n"));
+        output.push_str("}\n");
+        for cmd in module_commands.iter() {
+            output.push('\n');
+            output.push_str(&request(cmd));
+        }
+        output.push('\n');
+    }
+    output
+}
+
+/// The protobuf service definitions
+pub struct Proto;
+
+impl Generator for Proto {
+    fn name(&self) -> &'static str {
+        "proto"
+    }
+
+    fn files(&self, cmds: &[Command], options: &GeneratorOptions) -> Result<Vec<(String, String)>, String> {
+        Ok(vec![("ceph_command.proto".to_string(), generate(cmds, options))])
+    }
+}
+
@@ -0,0 +1,495 @@
+use ceph_command::{self, Command, GeneratorOptions, Module, Target};
+use code_writer::CodeWriter;
+use generator::Generator;
+use release;
+use rustc_serialize::json::Json;
+use schema::python_field_name;
+
+#[test]
+fn test_package() {
+    let cmds = ::parse_input("test",
+                             b"COMMAND(\"osd pool create name=pool,type=CephPoolname\", \"create pool\", \"osd\", \"rw\", \"cli,rest\")\n\
+                               COMMAND(\"config-key get name=key,type=CephString\", \"get key\", \"config-key\", \"r\", \"cli,rest\")")
+        .unwrap();
+    assert!(generate_package(&cmds, &GeneratorOptions::default()).unwrap_err().contains("--release"));
+
+    let options = GeneratorOptions { release: Some("18.2.1".to_string()), ..GeneratorOptions::default() };
+    let files = generate_package(&cmds, &options).unwrap();
+    let names: Vec<&str> = files.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(vec!["ceph_command/exceptions.py",
+                    "ceph_command/client.py",
+                    "ceph_command/osd.py",
+                    "ceph_command/config_key.py",
+                    "ceph_command/__init__.py",
+                    "ceph_command/py.typed",
+                    "tests/test_ceph_command.py",
+                    "pyproject.toml"],
+               names);
+    assert!(files[2].1.contains("from .client import run_ceph_command\n\n\nclass OsdCommand:\n"));
+    assert!(files[4].1.starts_with("\"\"\"Ceph reef commands\"\"\"\n"));
+    assert!(files[4].1.contains("from .config_key import ConfigKeyCommand\n\n__version__ = \"18.2.1\"\n"));
+    assert!(files[6].1.contains("    monkeypatch.setattr(ceph_command.osd, 'run_ceph_command', fake)\n"));
+    assert!(files[7].1.contains("version = \"18.2.1\"\n"));
+}
+
+#[test]
+fn test_generate_tests() {
+    let cmds = ::parse_input("test",
+                             b"COMMAND(\"osd pool set name=pool,type=CephPoolname \" \\\n\
+                               \"name=pg_num,type=CephInt,range=0|64\", \"set pool\", \"osd\", \"rw\", \"cli,rest\")\n\
+                               COMMAND(\"osd bind name=path,type=CephSocketpath\", \"bind\", \"osd\", \"rw\", \"cli,rest\")")
+        .unwrap();
+    assert_eq!(2, cmds.len());
+    let tests = generate_tests(&cmds, &GeneratorOptions::default(), false);
+    assert!(tests.contains("    monkeypatch.setattr(ceph_command, 'run_ceph_command', fake)\n"));
+    assert!(tests.contains("    def test_osd_pool_set(self, transport):\n        \
+                            client = ceph_command.OsdCommand('ceph.conf')\n        \
+                            client.osd_pool_set(pool='rbd', pg_num=0)\n        \
+                            expected = {'prefix': 'osd pool set', 'pool': 'rbd', 'pg_num': 0}\n        \
+                            assert transport.sent == [(expected, ('mon', ''))]\n"));
+    assert!(tests.contains("            client.osd_pool_set(pool='rbd', pg_num=-1)\n"));
+    assert!(tests.contains("def test_osd_pool_set_rejects_pool("));
+    // A socket path can't be made up so that command has no tests
+    assert!(!tests.contains("osd_bind"));
+}
+
+fn write_run_command(w: &mut CodeWriter) {
+    w.block("def run_ceph_command(conffile, cmd, inbuf, target=('mon', '')):", |w| {
+        w.line("\"\"\"Run a ceph command and return the results\n\
+                \n\
+                :param conffile: The ceph.conf configuration location\n\
+                :param cmd: The json command to run\n\
+                :param inbuf:\n\
+                :param target: (daemon type, daemon id) to send the command to.\n    \
+                    daemon type is one of mon, mgr, osd or mds\n\
+                :return: (string outbuf, string outs)\n\
+                :raise rados.Error: Raises on rados errors\n\
+                \"\"\"");
+        w.line("cluster = rados.Rados(conffile=conffile)");
+        w.block("try:", |w| {
+            w.line("cluster.connect()");
+            w.block("if target[0] == 'mgr':", |w| {
+                w.line("result = cluster.mgr_command(json.dumps(cmd), inbuf=inbuf)");
+            });
+            w.block("elif target[0] == 'osd':", |w| {
+                w.call("result = cluster.osd_command",
+                       &["int(target[1])".to_string(), "json.dumps(cmd)".to_string(), "inbuf=inbuf".to_string()],
+                       "");
+            });
+            w.block("elif target[0] == 'mds':", |w| {
+                w.line("import cephfs");
+                w.line("fs = cephfs.LibCephFS(rados_inst=cluster)");
+                w.line("fs.init()");
+                w.line("result = fs.mds_command(str(target[1]), [json.dumps(cmd)], inbuf)");
+            });
+            w.block("else:", |w| {
+                w.line("result = cluster.mon_command(json.dumps(cmd), inbuf=inbuf)");
+            });
+            w.block("if result[0] != 0:", |w| {
+                w.line("raise CephError(cmd=cmd, msg=os.strerror(abs(result[0])))");
+            });
+            w.line("return result[1], result[2]");
+        });
+        w.block("except rados.Error as e:", |w| {
+            w.line("raise e");
+        });
+    });
+}
+
+fn write_exception_class(w: &mut CodeWriter) {
+    w.block("class CephError(Exception):", |w| {
+        w.line("\"\"\"Exception raised for errors with running a Ceph command\n\
+                \n    \
+                    :param cmd: cmd in which the error occurred\n    \
+                    :param msg: explanation of the error\n\
+                \"\"\"");
+        w.blank();
+        w.block("def __init__(self, cmd, msg):", |w| {
+            w.line("self.cmd = cmd");
+            w.line("self.msg = msg");
+        });
+    });
+}
+
+fn write_decode_json(w: &mut CodeWriter) {
+    w.block("def decode_json_result(cmd, outbuf):", |w| {
+        w.line("\"\"\"Decode the json output of a command\n\
+                \n\
+                :param cmd: The json command that was run\n\
+                :param outbuf: The output of the command\n\
+                :return: dict\n\
+                :raise CephError: Raises CephError if the output isn't valid json\n\
+                \"\"\"");
+        w.block("try:", |w| {
+            w.line("return json.loads(outbuf)");
+        });
+        w.block("except ValueError as e:", |w| {
+            w.call("raise CephError",
+                   &["cmd=cmd".to_string(), "msg='Failed to decode json output: {}'.format(e)".to_string()],
+                   "");
+        });
+    });
+}
+
+/// Standard library imports then the ones that come with ceph
+fn write_imports(w: &mut CodeWriter, options: &GeneratorOptions) {
+    if !options.schemas.is_empty() {
+        // The dataclasses refer to each other in alphabetical order
+        w.line("from __future__ import annotations");
+        w.blank();
+    }
+    w.line("import json");
+    w.line("import os");
+    if !options.schemas.is_empty() {
+        w.line("from dataclasses import dataclass");
+        w.line("from typing import Any, List, Optional");
+    }
+    w.blank();
+    w.line("import ceph_argparse");
+    w.line("import rados");
+    w.line("import six");
+}
+
+/// The commands of each module that has any, with duplicate names renamed
+fn module_commands(cmds: &[Command]) -> Vec<(Module, Vec<Command>)> {
+    ceph_command::group_by_module(cmds, &Module::all())
+}
+
+/// The class holding a module's commands
+fn write_module_class(w: &mut CodeWriter, module: &Module, cmds: &[Command], options: &GeneratorOptions) {
+    w.block(&format!("class {}:", module.clone().to_string()), |w| {
+        w.block("def __init__(self, rados_config_file):", |w| {
+            w.line("self.rados_config_file = rados_config_file");
+        });
+        for cmd in cmds.iter() {
+            w.blank_lines(1);
+            cmd.write_python(w, options);
+        }
+    });
+}
+
+/// Generate the Python module for cmds.  Commands are grouped into one class per Module.
+pub fn generate(cmds: &[Command], options: &GeneratorOptions) -> String {
+    let mut w = CodeWriter::python();
+    write_imports(&mut w, options);
+    w.blank_lines(2);
+    write_exception_class(&mut w);
+    w.blank_lines(2);
+    write_run_command(&mut w);
+    if options.json_output {
+        w.blank_lines(2);
+        write_decode_json(&mut w);
+    }
+    if !options.schemas.is_empty() {
+        options.schemas.write_python(&mut w);
+    }
+    for (module, module_commands) in module_commands(cmds) {
+        w.blank_lines(2);
+        write_module_class(&mut w, &module, &module_commands, options);
+    }
+    w.into_string()
+}
+
+/// Name of the python module a Module's class is written to
+fn package_module(module: &Module) -> String {
+    module.name().replace('-', "_")
+}
+
+fn pyproject(version: &str, release: &str) -> String {
+    let mut output = String::new();
+    output.push_str("# Generated by command_parser from the Ceph command tables.  Do not edit.\n\n");
+    output.push_str("[build-system]\n");
+    output.push_str("requires = [\"setuptools>=61\"]\n");
+    output.push_str("build-backend = \"setuptools.build_meta\"\n\n");
+    output.push_str("[project]\n");
+    output.push_str("name = \"ceph-command\"\n");
+    output.push_str(&format!("version = \"{}\"\n", version));
+    output.push_str(&format!("description = \"Python bindings for the Ceph {} commands\"\n", release));
+    output.push_str("requires-python = \">=3.7\"\n");
+    output.push_str("# rados and ceph_argparse come with the ceph packages rather than from PyPI\n");
+    output.push_str("dependencies = [\"six\"]\n\n");
+    output.push_str("[tool.setuptools]\n");
+    output.push_str("packages = [\"ceph_command\"]\n\n");
+    output.push_str("[tool.setuptools.package-data]\n");
+    output.push_str("ceph_command = [\"py.typed\"]\n\n");
+    output.push_str("[tool.pytest.ini_options]\n");
+    output.push_str("pythonpath = [\".\"]\n");
+    output.push_str("testpaths = [\"tests\"]\n");
+    output
+}
+
+/// Generate a package directory for cmds: a module per Module class, the
+/// exception and rados helpers in modules of their own, and a pyproject.toml
+/// versioned from the release.
+pub fn generate_package(cmds: &[Command], options: &GeneratorOptions) -> Result<Vec<(String, String)>, String> {
+    let given = options.release.as_ref().ok_or("--release is needed to version the package".to_string())?;
+    let version = release::version(given).ok_or(format!("{} isn't a Ceph release name or version", given))?;
+    let release_name = release::name(given).unwrap_or(given);
+    let mut files = Vec::new();
+
+    let mut exceptions = CodeWriter::python();
+    exceptions.line("\"\"\"Errors raised by the Ceph commands\"\"\"");
+    exceptions.blank_lines(2);
+    write_exception_class(&mut exceptions);
+    files.push(("ceph_command/exceptions.py".to_string(), exceptions.into_string()));
+
+    let mut client = CodeWriter::python();
+    client.line("\"\"\"Sending commands to the cluster\"\"\"");
+    client.line("import json");
+    client.line("import os");
+    client.blank();
+    client.line("import rados");
+    client.blank();
+    client.line("from .exceptions import CephError");
+    client.blank_lines(2);
+    write_run_command(&mut client);
+    if options.json_output {
+        client.blank_lines(2);
+        write_decode_json(&mut client);
+    }
+    files.push(("ceph_command/client.py".to_string(), client.into_string()));
+
+    let mut helpers = vec!["run_ceph_command"];
+    if options.json_output {
+        helpers.insert(0, "decode_json_result");
+    }
+    if !options.schemas.is_empty() {
+        let mut models = CodeWriter::python();
+        models.line("\"\"\"Typed responses\"\"\"");
+        models.line("from __future__ import annotations");
+        models.blank();
+        models.line("from dataclasses import dataclass");
+        models.line("from typing import Any, List, Optional");
+        options.schemas.write_python(&mut models);
+        files.push(("ceph_command/models.py".to_string(), models.into_string()));
+    }
+    let model_names: Vec<String> = options.schemas.object_names().iter().map(|n| n.to_string()).collect();
+
+    let mut init = CodeWriter::python();
+    init.line(&format!("\"\"\"Ceph {} commands\"\"\"", release_name));
+    init.line("from .exceptions import CephError");
+    let mut exported = vec!["CephError".to_string()];
+    for (module, module_commands) in module_commands(cmds) {
+        let class_name = module.clone().to_string();
+        let mut w = CodeWriter::python();
+        w.line(&format!("\"\"\"{} commands\"\"\"", module.name()));
+        w.line("import ceph_argparse");
+        w.line("import six");
+        w.blank();
+        w.line(&format!("from .client import {}", helpers.join(", ")));
+        if !model_names.is_empty() {
+            // Parentheses only when the names need more than one line
+            let import = format!("from .models import {}", model_names.join(", "));
+            if import.len() <= 79 {
+                w.line(&import);
+            } else {
+                w.call("from .models import ", &model_names, "");
+            }
+        }
+        w.blank_lines(2);
+        write_module_class(&mut w, &module, &module_commands, options);
+        files.push((format!("ceph_command/{}.py", package_module(&module)), w.into_string()));
+        init.line(&format!("from .{} import {}", package_module(&module), class_name));
+        exported.push(class_name);
+    }
+    init.blank();
+    init.line(&format!("__version__ = \"{}\"", version));
+    let exported: Vec<String> = exported.iter().map(|name| format!("\"{}\"", name)).collect();
+    init.collection("__all__ = [", &exported, "]");
+    files.push(("ceph_command/__init__.py".to_string(), init.into_string()));
+    files.push(("ceph_command/py.typed".to_string(), String::new()));
+    files.push(("tests/test_ceph_command.py".to_string(), generate_tests(cmds, options, true)));
+    files.push(("pyproject.toml".to_string(), pyproject(&version, release_name)));
+    Ok(files)
+}
+
+/// A Python literal for a sample value
+fn python_literal(value: &Json) -> String {
+    match value {
+        Json::String(s) if !s.contains('\'') && !s.contains('\\') => format!("'{}'", s),
+        &Json::Boolean(b) => if b { "True" } else { "False" }.to_string(),
+        &Json::Null => "None".to_string(),
+        Json::Array(items) => format!("[{}]", items.iter().map(python_literal).collect::<Vec<String>>().join(", ")),
+        _ => value.to_string(),
+    }
+}
+
+/// Test one command: a call with every parameter set to a sample value and
+/// the exact cmd it sends, then a call per checked parameter with a bad value
+/// that mustn't send anything.
+fn write_command_tests(w: &mut CodeWriter, class_name: &str, cmd: &Command, options: &GeneratorOptions) {
+    let (id_arg, target) = match cmd.target {
+        Target::Mon => (None, "('mon', '')".to_string()),
+        Target::Mgr => (None, "('mgr', '')".to_string()),
+        Target::Osd => (Some("osd_id=0".to_string()), "('osd', 0)".to_string()),
+        Target::Mds => (Some("mds_id='a'".to_string()), "('mds', 'a')".to_string()),
+    };
+    let mut args = Vec::new();
+    let mut expected = vec![format!("'prefix': '{}'", cmd.signature.prefix)];
+    let mut required = Vec::new();
+    let mut bad = Vec::new();
+    for (key, ceph_type) in cmd.signature.ordered_parameters().into_iter() {
+        let name = python_field_name(cmd.signature.api_name(key));
+        if let Some(sample) = ceph_type.variant.sample() {
+            let arg = format!("{}={}", name, python_literal(&sample));
+            if ceph_type.req {
+                required.push((name.clone(), arg.clone()));
+            }
+            args.push(arg);
+            expected.push(format!("'{}': {}", key, python_literal(&sample)));
+        }
+        if let Some(value) = ceph_type.variant.bad_sample() {
+            let arg = format!("{}={}", name, python_literal(&value));
+            bad.push((name, arg));
+        }
+    }
+    let decode_json = options.json_output && cmd.supports_json();
+    if decode_json && !cmd.signature.parameters.contains_key("format") {
+        expected.push("'format': 'json'".to_string());
+    }
+    let client = format!("client = ceph_command.{}('ceph.conf')", class_name);
+    let method = format!("client.{}", cmd.method_name());
+
+    let test_args = vec!["self".to_string(), "transport".to_string()];
+    w.call(&format!("def test_{}", cmd.method_name()), &test_args, ":");
+    w.indent();
+    if let (true, Some(shape)) = (decode_json, options.schemas.get(&cmd.signature.prefix)) {
+        let outbuf = Json::String(shape.sample().to_string());
+        let line = format!("transport.outbuf = {}", python_literal(&outbuf));
+        if line.len() <= w.available() {
+            w.line(&line);
+        } else {
+            w.call("transport.outbuf = ", &[outbuf.to_string()], "");
+        }
+    }
+    w.line(&client);
+    let mut call_args: Vec<String> = id_arg.iter().cloned().collect();
+    call_args.extend(args.iter().cloned());
+    w.call(&method, &call_args, "");
+    w.collection("expected = {", &expected, "}");
+    w.line(&format!("assert transport.sent == [(expected, {})]", target));
+    w.dedent();
+    for (bad_name, bad_arg) in bad.iter() {
+        let mut call_args: Vec<String> = id_arg.iter().cloned().collect();
+        call_args.extend(required.iter().filter(|&(name, _)| name != bad_name).map(|(_, arg)| arg.clone()));
+        call_args.push(bad_arg.clone());
+        w.blank_lines(1);
+        w.call(&format!("def test_{}_rejects_{}", cmd.method_name(), bad_name), &test_args, ":");
+        w.indent();
+        w.line(&client);
+        w.block("with pytest.raises(INVALID):", |w| w.call(&method, &call_args, ""));
+        w.line("assert transport.sent == []");
+        w.dedent();
+    }
+}
+
+/// pytest tests for the module written by generate, or for the package when
+/// package is set.  run_ceph_command is replaced by a fake that records what
+/// would have been sent.
+pub fn generate_tests(cmds: &[Command], options: &GeneratorOptions, package: bool) -> String {
+    let grouped = module_commands(cmds);
+    let mut w = CodeWriter::python();
+    w.line("\"\"\"Generated by command_parser from the Ceph command tables.  Do not edit.\"\"\"");
+    w.line("import sys");
+    w.line("import types");
+    w.blank();
+    w.line("import pytest");
+    w.blank();
+    w.line("# Only run_ceph_command needs librados and the tests replace it");
+    w.block("try:", |w| w.line("import rados  # noqa: F401"));
+    w.block("except ImportError:", |w| w.line("sys.modules['rados'] = types.ModuleType('rados')"));
+    w.line("ceph_argparse = pytest.importorskip('ceph_argparse')");
+    w.blank();
+    w.line("import ceph_command  # noqa: E402");
+    w.blank_lines(2);
+    w.line("# What the checks raise for a bad argument");
+    w.line("INVALID = (ceph_argparse.ArgumentError, TypeError, ValueError)");
+    w.blank_lines(2);
+    w.block("class FakeTransport:", |w| {
+        w.line("\"\"\"Stands in for run_ceph_command, recording each (cmd, target)\"\"\"");
+        w.blank();
+        w.block("def __init__(self):", |w| {
+            w.line("self.sent = []");
+            w.line("self.outbuf = '{}'");
+        });
+        w.blank();
+        w.block("def __call__(self, conffile, cmd, inbuf, target=('mon', '')):", |w| {
+            w.line("self.sent.append((cmd, target))");
+            w.line("return self.outbuf, ''");
+        });
+    });
+    w.blank_lines(2);
+    w.line("@pytest.fixture");
+    w.block("def transport(monkeypatch):", |w| {
+        w.line("fake = FakeTransport()");
+        if package {
+            // Each module imported its own reference to run_ceph_command
+            for (module, _) in grouped.iter() {
+                w.call("monkeypatch.setattr",
+                       &[format!("ceph_command.{}", package_module(module)),
+                         "'run_ceph_command'".to_string(),
+                         "fake".to_string()],
+                       "");
+            }
+        } else {
+            w.line("monkeypatch.setattr(ceph_command, 'run_ceph_command', fake)");
+        }
+        w.line("return fake");
+    });
+    for (module, module_commands) in grouped.iter() {
+        let class_name = module.clone().to_string();
+        let tested: Vec<&Command> = module_commands.iter().filter(|c| c.samplable()).collect();
+        if tested.is_empty() {
+            continue;
+        }
+        w.blank_lines(2);
+        w.block(&format!("class Test{}:", class_name), |w| {
+            for (i, cmd) in tested.iter().enumerate() {
+                if i > 0 {
+                    w.blank_lines(1);
+                }
+                write_command_tests(w, &class_name, cmd, options);
+            }
+        });
+    }
+    w.into_string()
+}
+
+/// The Python module run through rados
+pub struct Python;
+
+impl Generator for Python {
+    fn name(&self) -> &'static str {
+        "python"
+    }
+
+    fn files(&self, cmds: &[Command], options: &GeneratorOptions) -> Result<Vec<(String, String)>, String> {
+        Ok(vec![("ceph_command.py".to_string(), generate(cmds, options))])
+    }
+}
+
+/// A pip installable package directory
+pub struct PythonPackage;
+
+impl Generator for PythonPackage {
+    fn name(&self) -> &'static str {
+        "python-package"
+    }
+
+    fn files(&self, cmds: &[Command], options: &GeneratorOptions) -> Result<Vec<(String, String)>, String> {
+        generate_package(cmds, options)
+    }
+}
+
+/// pytest tests for the python module
+pub struct PythonTests;
+
+impl Generator for PythonTests {
+    fn name(&self) -> &'static str {
+        "python-tests"
+    }
+
+    fn files(&self, cmds: &[Command], options: &GeneratorOptions) -> Result<Vec<(String, String)>, String> {
+        Ok(vec![("test_ceph_command.py".to_string(), generate_tests(cmds, options, false))])
+    }
+}
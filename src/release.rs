@@ -0,0 +1,64 @@
+//! Ceph releases by name and version number
+
+/// Every named release with its major version
+const RELEASES: &[(&str, &str)] = &[("argonaut", "0.48"),
+                                    ("bobtail", "0.56"),
+                                    ("cuttlefish", "0.61"),
+                                    ("dumpling", "0.67"),
+                                    ("emperor", "0.72"),
+                                    ("firefly", "0.80"),
+                                    ("giant", "0.87"),
+                                    ("hammer", "0.94"),
+                                    ("infernalis", "9"),
+                                    ("jewel", "10"),
+                                    ("kraken", "11"),
+                                    ("luminous", "12"),
+                                    ("mimic", "13"),
+                                    ("nautilus", "14"),
+                                    ("octopus", "15"),
+                                    ("pacific", "16"),
+                                    ("quincy", "17"),
+                                    ("reef", "18"),
+                                    ("squid", "19"),
+                                    ("tentacle", "20")];
+
+#[test]
+fn test_release() {
+    assert_eq!(Some("18.0.0".to_string()), version("reef"));
+    assert_eq!(Some("18.0.0".to_string()), version("Reef"));
+    assert_eq!(Some("0.94.0".to_string()), version("hammer"));
+    assert_eq!(Some("18.2.1".to_string()), version("v18.2.1"));
+    assert_eq!(Some("17.2".to_string()), version("17.2"));
+    assert_eq!(None, version("18.x"));
+    assert_eq!(None, version("nope"));
+
+    assert_eq!(Some("reef"), name("reef"));
+    assert_eq!(Some("reef"), name("18.2.1"));
+    assert_eq!(Some("hammer"), name("0.94.10"));
+    assert_eq!(None, name("99"));
+}
+
+/// The version number of a release given by name, such as reef, or by
+/// version, such as 18.2.1.  Names give the first version of the release.
+pub fn version(release: &str) -> Option<String> {
+    let release = release.trim().to_lowercase();
+    if let Some(&(_, major)) = RELEASES.iter().find(|&&(name, _)| name == release) {
+        // 0.94 was hammer's first version, 18.0.0 reef's
+        return Some(if major.contains('.') { format!("{}.0", major) } else { format!("{}.0.0", major) });
+    }
+    let number = release.trim_start_matches('v');
+    let valid = !number.is_empty() && number.split('.').all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit()));
+    if valid {
+        Some(number.to_string())
+    } else {
+        None
+    }
+}
+
+/// The name of a release given by name or by version
+pub fn name(release: &str) -> Option<&'static str> {
+    let number = version(release)?;
+    RELEASES.iter()
+        .find(|&&(_, major)| number == major || number.starts_with(&format!("{}.", major)))
+        .map(|&(name, _)| name)
+}
@@ -0,0 +1,468 @@
+use ceph_command::{self, CephEnum, CephType, Command, GeneratorOptions, Module, Target};
+use generator::Generator;
+use rustc_serialize::json::Json;
+use schema::rust_field_name;
+use std::collections::BTreeMap;
+
+#[cfg(test)]
+use test_fixtures::pool_create;
+
+#[test]
+fn test_rust_method() {
+    let method = method(&pool_create(), &GeneratorOptions::default());
+    assert!(method.contains("    pub fn osd_pool_create(&self, pg_num: i64, pool: &str, crush_rule: Option<&[&str]>, \
+                             pool_type: Option<&str>) -> Result<(Vec<u8>, String), CephError> {\n"));
+    assert!(method.contains("        check_int_range(\"pg_num\", pg_num, Some(0), Some(65536))?;\n"));
+    assert!(method.contains("        if let Some(crush_rule) = crush_rule {\n            \
+                             cmd.insert(\"crush-rule\".to_string(), serde_json::Value::from(crush_rule.to_vec()));\n"));
+    assert!(method.contains("        if let Some(pool_type) = pool_type {\n            \
+                             check_choice(\"pool_type\", pool_type, &[\"replicated\", \"erasure\"])?;\n"));
+    assert!(method.contains("        self.transport.mon_command(&cmd, &[])\n"));
+}
+
+#[test]
+fn test_rust_tell_method() {
+    let mut cmd = pool_create();
+    cmd.target = Target::Osd;
+    cmd.signature.parameters.clear();
+    let method = method(&cmd, &GeneratorOptions::default());
+    assert!(method.contains("pub fn osd_pool_create(&self, osd_id: u64) -> "));
+    assert!(method.contains("self.transport.osd_command(osd_id, &cmd, &[])"));
+}
+
+#[test]
+fn test_rust_json_method() {
+    let cmds = ::parse_input("test",
+                             b"COMMAND(\"osd tree name=format,type=CephChoices,strings=plain|json,req=false\", \
+                               \"print OSD tree\", \"osd\", \"r\", \"cli,rest\")")
+        .unwrap();
+    let options = GeneratorOptions { json_output: true, ..GeneratorOptions::default() };
+    let method = method(&cmds[0], &options);
+    // format=None still gets json back, so the output can be decoded
+    assert!(method.contains("        if let Some(format) = format {\n"));
+    assert!(method.contains("        cmd.entry(\"format\").or_insert(serde_json::Value::from(\"json\"));\n"));
+    assert!(method.contains("serde_json::from_slice(&outbuf)"));
+}
+
+#[test]
+fn test_rust_tests() {
+    let tests = command_tests(&pool_create(), "OsdCommand", &GeneratorOptions::default());
+    assert!(tests.contains("        fn osd_pool_create() {\n            \
+                            let transport = MockTransport::new(r#\"{}\"#);\n            \
+                            OsdCommand::new(&transport).osd_pool_create(0, \"rbd\", Some(&[\"sample\"]), \
+                            Some(\"replicated\")).unwrap();\n            \
+                            transport.assert_sent(\"mon\", r#\"{\"crush-rule\":[\"sample\"],\"pg_num\":0,\"pool\":\"rbd\",\
+                            \"pool_type\":\"replicated\",\"prefix\":\"osd pool create\"}\"#);\n"));
+    assert!(tests.contains("match OsdCommand::new(&transport).osd_pool_create(-1, \"rbd\", None, None) {\n"));
+    assert!(tests.contains("match OsdCommand::new(&transport).osd_pool_create(0, \"rbd\", None, Some(\"not-a-choice\")) {\n"));
+    // Only checked parameters get a test with a bad value
+    assert!(!tests.contains("osd_pool_create_rejects_pool()"));
+}
+
+fn preamble() -> String {
+    let mut output = String::new();
+    output.push_str("// Generated by command_parser from the Ceph command tables.  Do not edit.\n");
+    output.push_str("// Needs the serde and serde_json crates.\n\n");
+    output.push_str("use std::error::Error;\n");
+    output.push_str("use std::fmt;\n\n");
+    output.push_str("/// Errors from running a Ceph command\n");
+    output.push_str("#[derive(Debug)]\n");
+    output.push_str("pub enum CephError {\n");
+    output.push_str("    /// An argument failed validation so the command was never sent\n");
+    output.push_str("    InvalidArgument(String),\n");
+    output.push_str("    /// The command failed.  Holds the errno and the status string\n");
+    output.push_str("    Command(i32, String),\n");
+    output.push_str("    /// The json output of the command couldn't be decoded\n");
+    output.push_str("    Decode(String),\n");
+    output.push_str("}\n\n");
+    output.push_str("impl fmt::Display for CephError {\n");
+    output.push_str("    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {\n");
+    output.push_str("        match *self {\n");
+    output.push_str("            CephError::InvalidArgument(ref e) => write!(f, \"invalid argument: {}\", e),\n");
+    output.push_str("            CephError::Command(errno, ref outs) => write!(f, \"command failed with {}: {}\", errno, outs),\n");
+    output.push_str("            CephError::Decode(ref e) => write!(f, \"failed to decode json output: {}\", e),\n");
+    output.push_str("        }\n");
+    output.push_str("    }\n");
+    output.push_str("}\n\n");
+    output.push_str("impl Error for CephError {}\n\n");
+    output.push_str("/// Sends a json command to a daemon and returns (outbuf, outs).  Implement\n");
+    output.push_str("/// this on top of librados.\n");
+    output.push_str("pub trait CephTransport {\n");
+    output.push_str("    fn mon_command(&self, cmd: &str, inbuf: &[u8]) -> Result<(Vec<u8>, String), CephError>;\n");
+    output.push_str("    fn mgr_command(&self, cmd: &str, inbuf: &[u8]) -> Result<(Vec<u8>, String), CephError>;\n");
+    output.push_str("    fn osd_command(&self, osd_id: u64, cmd: &str, inbuf: &[u8]) -> Result<(Vec<u8>, String), CephError>;\n");
+    output.push_str("    fn mds_command(&self, mds_id: &str, cmd: &str, inbuf: &[u8]) -> Result<(Vec<u8>, String), CephError>;\n");
+    output.push_str("}\n\n");
+    output.push_str("fn check_int_range(name: &str, value: i64, min: Option<i64>, max: Option<i64>) -> Result<(), CephError> {\n");
+    output.push_str("    if min.map_or(false, |m| value < m) || max.map_or(false, |m| value > m) {\n");
+    output.push_str("        return Err(CephError::InvalidArgument(format!(\"{} {} is out of range {:?}..{:?}\", name, value, min, max)));\n");
+    output.push_str("    }\n");
+    output.push_str("    Ok(())\n");
+    output.push_str("}\n\n");
+    output.push_str("fn check_float_range(name: &str, value: f64, min: Option<f64>, max: Option<f64>) -> Result<(), CephError> {\n");
+    output.push_str("    if min.map_or(false, |m| value < m) || max.map_or(false, |m| value > m) {\n");
+    output.push_str("        return Err(CephError::InvalidArgument(format!(\"{} {} is out of range {:?}..{:?}\", name, value, min, max)));\n");
+    output.push_str("    }\n");
+    output.push_str("    Ok(())\n");
+    output.push_str("}\n\n");
+    output.push_str("fn check_badchars(name: &str, value: &str, badchars: &str) -> Result<(), CephError> {\n");
+    output.push_str("    if value.chars().any(|c| badchars.contains(c)) {\n");
+    output.push_str("        return Err(CephError::InvalidArgument(format!(\"{} {} contains one of {:?}\", name, value, badchars)));\n");
+    output.push_str("    }\n");
+    output.push_str("    Ok(())\n");
+    output.push_str("}\n\n");
+    output.push_str("fn check_choice(name: &str, value: &str, choices: &[&str]) -> Result<(), CephError> {\n");
+    output.push_str("    if !choices.contains(&value) {\n");
+    output.push_str("        return Err(CephError::InvalidArgument(format!(\"{} {} is not one of {:?}\", name, value, choices)));\n");
+    output.push_str("    }\n");
+    output.push_str("    Ok(())\n");
+    output.push_str("}\n\n");
+    output
+}
+
+/// The Rust type a parameter is passed as
+fn rust_type(ceph_type: &CephType) -> String {
+    let base = match ceph_type.variant {
+        CephEnum::CephInt { .. } => "i64".to_string(),
+        CephEnum::CephFloat { .. } => "f64".to_string(),
+        CephEnum::CephBool => "bool".to_string(),
+        ref v if v.is_many() => "&[&str]".to_string(),
+        _ => "&str".to_string(),
+    };
+    if ceph_type.req {
+        base
+    } else {
+        format!("Option<{}>", base)
+    }
+}
+
+/// Checks for a single value of a parameter named name.  Empty if there's
+/// nothing to check.
+fn validate(variant: &CephEnum, name: &str, value: &str) -> String {
+    match variant {
+        &CephEnum::CephInt { min, max } => {
+            format!("check_int_range(\"{}\", {}, {:?}, {:?})?;",
+                    name,
+                    value,
+                    min,
+                    max)
+        }
+        &CephEnum::CephFloat { min, max } => {
+            format!("check_float_range(\"{}\", {}, {:?}, {:?})?;",
+                    name,
+                    value,
+                    min.map(|m| m as f64),
+                    max.map(|m| m as f64))
+        }
+        &CephEnum::CephString { badchars: Some(ref bad), .. } => {
+            format!("check_badchars(\"{}\", {}, {:?})?;", name, value, bad)
+        }
+        CephEnum::CephChoices { choices, .. } => {
+            let quoted: Vec<String> = choices.iter().map(|c| format!("{:?}", c)).collect();
+            format!("check_choice(\"{}\", {}, &[{}])?;", name, value, quoted.join(", "))
+        }
+        _ => String::new(),
+    }
+}
+
+/// Validation plus inserting a parameter into the cmd map
+fn add_param(output: &mut String, indent: &str, key: &str, ident: &str, ceph_type: &CephType) {
+    if ceph_type.variant.is_many() {
+        let check = validate(&ceph_type.variant, key, "v");
+        if !check.is_empty() {
+            output.push_str(&format!("{}for v in {}.iter().cloned() {{\n", indent, ident));
+            output.push_str(&format!("{}    {}\n", indent, check));
+            output.push_str(&format!("{}}}\n", indent));
+        }
+        output.push_str(&format!("{}cmd.insert(\"{}\".to_string(), serde_json::Value::from({}.to_vec()));\n",
+                                 indent,
+                                 key,
+                                 ident));
+    } else {
+        let check = validate(&ceph_type.variant, key, ident);
+        if !check.is_empty() {
+            output.push_str(&format!("{}{}\n", indent, check));
+        }
+        output.push_str(&format!("{}cmd.insert(\"{}\".to_string(), serde_json::Value::from({}));\n",
+                                 indent,
+                                 key,
+                                 ident));
+    }
+}
+
+/// Parameters in the order the method takes them: required parameters first
+/// then the optional ones, each sorted by name
+fn method_params(cmd: &Command) -> Vec<(&String, &CephType)> {
+    let mut params: Vec<(&String, &CephType)> = cmd.signature.parameters.iter().collect();
+    params.sort_by(|a, b| (!a.1.req, a.0).cmp(&(!b.1.req, b.0)));
+    params
+}
+
+fn method(cmd: &Command, options: &GeneratorOptions) -> String {
+    let decode_json = options.json_output && cmd.supports_json();
+    let mut output = String::new();
+    let params = method_params(cmd);
+
+    let mut args: Vec<String> = vec!["&self".to_string()];
+    match cmd.target {
+        Target::Osd => args.push("osd_id: u64".to_string()),
+        Target::Mds => args.push("mds_id: &str".to_string()),
+        _ => {}
+    }
+    for &(key, ceph_type) in params.iter() {
+        args.push(format!("{}: {}", rust_field_name(cmd.signature.api_name(key)), rust_type(ceph_type)));
+    }
+    let return_type = if decode_json {
+        match options.schemas.get(&cmd.signature.prefix) {
+            Some(shape) => shape.rust_type(),
+            None => "serde_json::Value".to_string(),
+        }
+    } else {
+        "(Vec<u8>, String)".to_string()
+    };
+
+    for line in cmd.helpstring.lines() {
+        output.push_str(&format!("    /// {}\n", line.trim()));
+    }
+    output.push_str(&format!("    pub fn {}({}) -> Result<{}, CephError> {{\n",
+                             rust_field_name(&cmd.method_name()),
+                             args.join(", "),
+                             return_type));
+    output.push_str("        let mut cmd = serde_json::Map::new();\n");
+    output.push_str(&format!("        cmd.insert(\"prefix\".to_string(), serde_json::Value::from({:?}));\n",
+                             cmd.signature.prefix));
+    for &(key, ceph_type) in params.iter() {
+        let ident = rust_field_name(cmd.signature.api_name(key));
+        if ceph_type.req {
+            add_param(&mut output, "        ", key, &ident, ceph_type);
+        } else {
+            output.push_str(&format!("        if let Some({}) = {} {{\n", ident, ident));
+            add_param(&mut output, "            ", key, &ident, ceph_type);
+            output.push_str("        }\n");
+        }
+    }
+    if decode_json {
+        // json unless the caller picked another format
+        output.push_str("        cmd.entry(\"format\").or_insert(serde_json::Value::from(\"json\"));\n");
+    }
+    output.push_str("        let cmd = serde_json::Value::Object(cmd).to_string();\n");
+    let call = match cmd.target {
+        Target::Mon => "self.transport.mon_command(&cmd, &[])",
+        Target::Mgr => "self.transport.mgr_command(&cmd, &[])",
+        Target::Osd => "self.transport.osd_command(osd_id, &cmd, &[])",
+        Target::Mds => "self.transport.mds_command(mds_id, &cmd, &[])",
+    };
+    if decode_json {
+        output.push_str(&format!("        let (outbuf, _) = {}?;\n", call));
+        output.push_str("        serde_json::from_slice(&outbuf).map_err(|e| CephError::Decode(e.to_string()))\n");
+    } else {
+        output.push_str(&format!("        {}\n", call));
+    }
+    output.push_str("    }\n");
+    output
+}
+
+/// Generate a Rust module for cmds with one struct per Module
+pub fn generate(cmds: &[Command], options: &GeneratorOptions) -> String {
+    let mut output = preamble();
+    if !options.schemas.is_empty() {
+        output.push_str("use serde::Deserialize;\n\n");
+        output.push_str(&options.schemas.to_rust());
+    }
+
+    let grouped = ceph_command::group_by_module(cmds, &Module::all());
+    for (module, module_commands) in grouped.iter() {
+        let name = module.clone().to_string();
+        output.push_str(&format!("pub struct {}<'a, T: CephTransport + 'a> {{\n", name));
+        output.push_str("    transport: &'a T,\n");
+        output.push_str("}\n\n");
+        output.push_str(&format!("impl<'a, T: CephTransport + 'a> {}<'a, T> {{\n", name));
+        output.push_str("    pub fn new(transport: &'a T) -> Self {\n");
+        output.push_str(&format!("        {} {{ transport: transport }}\n", name));
+        output.push_str("    }\n");
+        for cmd in module_commands.iter() {
+            output.push('\n');
+            output.push_str(&method(cmd, options));
+        }
+        output.push_str("}\n\n");
+    }
+    output.push_str(&tests(&grouped, options));
+    output
+}
+
+/// A Rust argument holding a sample value
+fn rust_literal(value: &Json) -> String {
+    match value {
+        &Json::F64(f) => format!("{:?}", f),
+        Json::String(s) => format!("{:?}", s),
+        Json::Array(items) => format!("&[{}]", items.iter().map(rust_literal).collect::<Vec<String>>().join(", ")),
+        _ => value.to_string(),
+    }
+}
+
+/// A value the generated Rust checks reject.  Only ranges, badchars and
+/// choices are checked; anything else is left to the daemon.
+fn rust_bad_sample(variant: &CephEnum) -> Option<Json> {
+    match variant {
+        &CephEnum::CephInt { min: None, max: None } |
+        &CephEnum::CephFloat { min: None, max: None } => None,
+        &CephEnum::CephInt { .. } | &CephEnum::CephFloat { .. } => variant.bad_sample().filter(|b| b.is_number()),
+        &CephEnum::CephString { badchars: Some(ref bad), .. } if !bad.is_empty() => variant.bad_sample(),
+        &CephEnum::CephChoices { .. } => variant.bad_sample(),
+        _ => None,
+    }
+}
+
+/// Arguments for calling cmd's method with samples, bad replacing the value
+/// of one parameter.  Optional parameters are only set when bad is None.
+fn sample_args(cmd: &Command, bad: Option<(&str, &Json)>) -> Vec<String> {
+    let mut args = Vec::new();
+    match cmd.target {
+        Target::Osd => args.push("0".to_string()),
+        Target::Mds => args.push("\"a\"".to_string()),
+        _ => {}
+    }
+    for (key, ceph_type) in method_params(cmd) {
+        let value = match bad {
+            Some((bad_key, value)) if bad_key == key => Some(value.clone()),
+            Some(_) if !ceph_type.req => None,
+            _ => ceph_type.variant.sample(),
+        };
+        args.push(match value {
+            Some(ref v) if ceph_type.req => rust_literal(v),
+            Some(ref v) => format!("Some({})", rust_literal(v)),
+            None => "None".to_string(),
+        });
+    }
+    args
+}
+
+/// A test sending cmd with every parameter set to a sample and checking the
+/// exact json that went out, then a test per checked parameter that a bad
+/// value is rejected before anything is sent
+fn command_tests(cmd: &Command, struct_name: &str, options: &GeneratorOptions) -> String {
+    let decode_json = options.json_output && cmd.supports_json();
+    let method = rust_field_name(&cmd.method_name());
+    let target = match cmd.target {
+        Target::Mon => "mon",
+        Target::Mgr => "mgr",
+        Target::Osd => "osd.0",
+        Target::Mds => "mds.a",
+    };
+    let outbuf = match options.schemas.get(&cmd.signature.prefix) {
+        Some(shape) if decode_json => shape.sample().to_string(),
+        _ => "{}".to_string(),
+    };
+    let mut expected = BTreeMap::new();
+    expected.insert("prefix".to_string(), Json::String(cmd.signature.prefix.clone()));
+    for (key, ceph_type) in method_params(cmd) {
+        if let Some(sample) = ceph_type.variant.sample() {
+            expected.insert(key.clone(), sample);
+        }
+    }
+    if decode_json && !cmd.signature.parameters.contains_key("format") {
+        expected.insert("format".to_string(), Json::String("json".to_string()));
+    }
+
+    let mut output = String::new();
+    output.push_str("\n        #[test]\n");
+    output.push_str(&format!("        fn {}() {{\n", method));
+    output.push_str(&format!("            let transport = MockTransport::new(r#\"{}\"#);\n", outbuf));
+    output.push_str(&format!("            {}::new(&transport).{}({}).unwrap();\n",
+                             struct_name,
+                             method,
+                             sample_args(cmd, None).join(", ")));
+    output.push_str(&format!("            transport.assert_sent(\"{}\", r#\"{}\"#);\n",
+                             target,
+                             Json::Object(expected)));
+    output.push_str("        }\n");
+    for (key, ceph_type) in method_params(cmd) {
+        if let Some(bad) = rust_bad_sample(&ceph_type.variant) {
+            output.push_str("\n        #[test]\n");
+            output.push_str(&format!("        fn {}_rejects_{}() {{\n", method, rust_field_name(cmd.signature.api_name(key))));
+            output.push_str("            let transport = MockTransport::new(r#\"{}\"#);\n");
+            output.push_str(&format!("            match {}::new(&transport).{}({}) {{\n",
+                                     struct_name,
+                                     method,
+                                     sample_args(cmd, Some((key, &bad))).join(", ")));
+            output.push_str("                Err(CephError::InvalidArgument(_)) => {}\n");
+            output.push_str(&format!("                _ => panic!(\"{} {} wasn't rejected\"),\n",
+                                     key,
+                                     bad.to_string().replace('"', "\\\"")));
+            output.push_str("            }\n");
+            output.push_str("            assert!(transport.sent.borrow().is_empty());\n");
+            output.push_str("        }\n");
+        }
+    }
+    output
+}
+
+/// A test module with a transport that records commands instead of sending
+/// them and a submodule of tests for each struct
+fn tests(grouped: &[(Module, Vec<Command>)], options: &GeneratorOptions) -> String {
+    let mut output = String::new();
+    output.push_str("#[cfg(test)]\n");
+    output.push_str("mod tests {\n");
+    output.push_str("    use super::*;\n");
+    output.push_str("    use std::cell::RefCell;\n\n");
+    output.push_str("    /// Records the target and json of each command and answers with outbuf\n");
+    output.push_str("    struct MockTransport {\n");
+    output.push_str("        outbuf: Vec<u8>,\n");
+    output.push_str("        sent: RefCell<Vec<(String, serde_json::Value)>>,\n");
+    output.push_str("    }\n\n");
+    output.push_str("    impl MockTransport {\n");
+    output.push_str("        fn new(outbuf: &str) -> MockTransport {\n");
+    output.push_str("            MockTransport { outbuf: outbuf.as_bytes().to_vec(), sent: RefCell::new(Vec::new()) }\n");
+    output.push_str("        }\n\n");
+    output.push_str("        fn record(&self, target: String, cmd: &str) -> Result<(Vec<u8>, String), CephError> {\n");
+    output.push_str("            self.sent.borrow_mut().push((target, serde_json::from_str(cmd).unwrap()));\n");
+    output.push_str("            Ok((self.outbuf.clone(), String::new()))\n");
+    output.push_str("        }\n\n");
+    output.push_str("        /// Check cmd was the only command and it went to target\n");
+    output.push_str("        fn assert_sent(&self, target: &str, cmd: &str) {\n");
+    output.push_str("            let expected: serde_json::Value = serde_json::from_str(cmd).unwrap();\n");
+    output.push_str("            assert_eq!(*self.sent.borrow(), vec![(target.to_string(), expected)]);\n");
+    output.push_str("        }\n");
+    output.push_str("    }\n\n");
+    output.push_str("    impl CephTransport for MockTransport {\n");
+    output.push_str("        fn mon_command(&self, cmd: &str, _inbuf: &[u8]) -> Result<(Vec<u8>, String), CephError> {\n");
+    output.push_str("            self.record(\"mon\".to_string(), cmd)\n");
+    output.push_str("        }\n\n");
+    output.push_str("        fn mgr_command(&self, cmd: &str, _inbuf: &[u8]) -> Result<(Vec<u8>, String), CephError> {\n");
+    output.push_str("            self.record(\"mgr\".to_string(), cmd)\n");
+    output.push_str("        }\n\n");
+    output.push_str("        fn osd_command(&self, osd_id: u64, cmd: &str, _inbuf: &[u8]) -> Result<(Vec<u8>, String), CephError> {\n");
+    output.push_str("            self.record(format!(\"osd.{}\", osd_id), cmd)\n");
+    output.push_str("        }\n\n");
+    output.push_str("        fn mds_command(&self, mds_id: &str, cmd: &str, _inbuf: &[u8]) -> Result<(Vec<u8>, String), CephError> {\n");
+    output.push_str("            self.record(format!(\"mds.{}\", mds_id), cmd)\n");
+    output.push_str("        }\n");
+    output.push_str("    }\n");
+    for (module, module_commands) in grouped.iter() {
+        let tested: Vec<&Command> = module_commands.iter().filter(|c| c.samplable()).collect();
+        if tested.is_empty() {
+            continue;
+        }
+        output.push_str(&format!("\n    mod {} {{\n", module.name().replace('-', "_")));
+        output.push_str("        use super::*;\n");
+        for cmd in tested {
+            output.push_str(&command_tests(cmd, &module.clone().to_string(), options));
+        }
+        output.push_str("    }\n");
+    }
+    output.push_str("}\n");
+    output
+}
+
+/// The Rust crate module
+pub struct Rust;
+
+impl Generator for Rust {
+    fn name(&self) -> &'static str {
+        "rust"
+    }
+
+    fn files(&self, cmds: &[Command], options: &GeneratorOptions) -> Result<Vec<(String, String)>, String> {
+        Ok(vec![("ceph_command.rs".to_string(), generate(cmds, options))])
+    }
+}
@@ -0,0 +1,466 @@
+extern crate rustc_serialize;
+
+use rustc_serialize::json::Json;
+
+use std::collections::{BTreeMap, HashMap};
+
+use ceph_command::Command;
+use code_writer::CodeWriter;
+
+#[test]
+fn test_parse_registry() {
+    let input = r#"{
+        "pg stat": {
+            "name": "PgStat",
+            "fields": {"num_pgs": "int", "num_bytes": "int", "raw_bytes_used": "float"}
+        },
+        "osd pool ls detail": {
+            "list": {
+                "name": "PoolDetail",
+                "fields": {"pool": "int", "pool_name": "string", "application_metadata": "any"}
+            }
+        },
+        "osd ls": {"list": "int"}
+    }"#;
+    let registry = SchemaRegistry::parse(input).unwrap();
+
+    assert_eq!(Some(&Shape::List(Box::new(Shape::Int))), registry.get("osd ls"));
+    let mut fields = BTreeMap::new();
+    fields.insert("num_bytes".to_string(), Shape::Int);
+    fields.insert("num_pgs".to_string(), Shape::Int);
+    fields.insert("raw_bytes_used".to_string(), Shape::Float);
+    assert_eq!(Some(&Shape::Object { name: "PgStat".to_string(), fields }),
+               registry.get("pg stat"));
+    assert_eq!(vec!["PgStat", "PoolDetail"],
+               registry.objects().iter().map(|o| o.0).collect::<Vec<&str>>());
+}
+
+#[test]
+fn test_parse_registry_errors() {
+    assert!(SchemaRegistry::parse("[]").is_err());
+    assert!(SchemaRegistry::parse(r#"{"pg stat": "int64"}"#).is_err());
+    assert!(SchemaRegistry::parse(r#"{"pg stat": {"fields": {}}}"#).is_err());
+    assert_eq!(Err("pg stat: PgStat: \"hit-set\" and \"hit_set\" are both named hit_set".to_string()),
+               SchemaRegistry::parse(r#"{"pg stat": {"name": "PgStat", "fields": {"hit-set": "int", "hit_set": "int"}}}"#)
+                   .map(|_| ()));
+    assert!(SchemaRegistry::parse(r#"{"pg stat": {"name": "PgStat", "fields": {"type": "int", "type_": "int"}}}"#)
+        .is_err());
+}
+
+#[test]
+fn test_rust_field_name() {
+    for keyword in &["as", "if", "else", "for", "let", "return", "true", "false", "break", "continue", "const",
+                     "mut", "pub", "super", "self", "Self", "crate", "dyn", "async", "await", "try", "yield"] {
+        assert_eq!(format!("{}_", keyword), rust_field_name(keyword));
+    }
+    assert_eq!("hit_set", rust_field_name("hit-set"));
+    assert_eq!("union", rust_field_name("union"));
+}
+
+#[test]
+fn test_python_and_rust_models() {
+    let input = r#"{
+        "osd pool ls detail": {
+            "list": {
+                "name": "PoolDetail",
+                "fields": {"pool": "int", "type": "int", "hit-set": {"name": "HitSet", "fields": {"period": "int"}}}
+            }
+        }
+    }"#;
+    let registry = SchemaRegistry::parse(input).unwrap();
+    let mut w = CodeWriter::python();
+    registry.write_python(&mut w);
+    let python = w.into_string();
+    assert!(python.contains("@dataclass\nclass PoolDetail:\n"));
+    assert!(python.contains("    hit_set: Optional[HitSet]\n"));
+    assert!(python.contains("            hit_set=HitSet.from_json(data.get('hit-set')) if data.get('hit-set') is not None else None,\n"));
+    assert_eq!("[PoolDetail.from_json(i) for i in decode_json_result(cmd, outbuf)]",
+        registry.get("osd pool ls detail").unwrap().python_decode("decode_json_result(cmd, outbuf)"));
+
+    let rust = registry.to_rust();
+    assert!(rust.contains("pub struct PoolDetail {"));
+    assert!(rust.contains("    #[serde(rename = \"type\")]\n    pub type_: i64,"));
+    assert!(rust.contains("    #[serde(rename = \"hit-set\")]\n    pub hit_set: Option<HitSet>,"));
+
+    assert!(registry.to_typescript().contains("export interface PoolDetail {\n  \"hit-set\"?: HitSet;\n  pool: number;\n  \
+                                               type: number;\n}\n"));
+    assert_eq!("PoolDetail[]", registry.get("osd pool ls detail").unwrap().typescript_type());
+}
+
+/// The shape of a json value returned by a command.  In the registry file a
+/// shape is either one of the strings "int", "float", "string", "bool" or
+/// "any", {"list": shape} or {"name": "ClassName", "fields": {"key": shape}}
+#[derive(Clone, Debug, PartialEq)]
+pub enum Shape {
+    Int,
+    Float,
+    String,
+    Bool,
+    /// Anything at all.  Passed through undecoded
+    Any,
+    List(Box<Shape>),
+    Object {
+        name: String,
+        fields: BTreeMap<String, Shape>,
+    },
+}
+
+impl Shape {
+    fn from_json(json: &Json) -> Result<Shape, String> {
+        match json {
+            Json::String(s) => {
+                match &s[..] {
+                    "int" => Ok(Shape::Int),
+                    "float" => Ok(Shape::Float),
+                    "string" => Ok(Shape::String),
+                    "bool" => Ok(Shape::Bool),
+                    "any" => Ok(Shape::Any),
+                    _ => Err(format!("unknown type {:?}", s)),
+                }
+            }
+            Json::Object(obj) => {
+                if let Some(items) = obj.get("list") {
+                    return Ok(Shape::List(Box::new(Shape::from_json(items)?)));
+                }
+                let name = match obj.get("name") {
+                    Some(Json::String(name)) => name.clone(),
+                    _ => return Err("object is missing a \"name\"".to_string()),
+                };
+                let mut fields = BTreeMap::new();
+                match obj.get("fields") {
+                    Some(Json::Object(f)) => {
+                        for (key, value) in f.iter() {
+                            let shape = Shape::from_json(value)
+                                .map_err(|e| format!("{}.{}: {}", name, key, e))?;
+                            fields.insert(key.clone(), shape);
+                        }
+                    }
+                    _ => return Err(format!("{} is missing \"fields\"", name)),
+                }
+                // Two keys that only differ in punctuation would become the
+                // same attribute in the generated classes
+                let mut names: BTreeMap<String, &str> = BTreeMap::new();
+                for key in fields.keys() {
+                    for ident in &[python_field_name(key), rust_field_name(key)] {
+                        if let Some(other) = names.insert(ident.clone(), key) {
+                            if other != key {
+                                return Err(format!("{}: {:?} and {:?} are both named {}", name, other, key, ident));
+                            }
+                        }
+                    }
+                }
+                Ok(Shape::Object {
+                    name,
+                    fields,
+                })
+            }
+            _ => Err(format!("expected a type name or an object, found {}", json)),
+        }
+    }
+
+    pub fn python_type(&self) -> String {
+        match self {
+            &Shape::Int => "int".to_string(),
+            &Shape::Float => "float".to_string(),
+            &Shape::String => "str".to_string(),
+            &Shape::Bool => "bool".to_string(),
+            &Shape::Any => "Any".to_string(),
+            Shape::List(items) => format!("List[{}]", items.python_type()),
+            Shape::Object { name, .. } => name.clone(),
+        }
+    }
+
+    /// The type of an object field.  A nested object may be left out of the
+    /// json altogether so it's optional; everything else is required.
+    pub fn python_field_type(&self) -> String {
+        match self {
+            Shape::Object { name, .. } => format!("Optional[{}]", name),
+            _ => self.python_type(),
+        }
+    }
+
+    /// Python expression that turns the decoded json in value into this shape
+    pub fn python_decode(&self, value: &str) -> String {
+        match self {
+            Shape::List(items) => {
+                match **items {
+                    Shape::Object { .. } | Shape::List(_) => {
+                        format!("[{} for i in {}]", items.python_decode("i"), value)
+                    }
+                    _ => value.to_string(),
+                }
+            }
+            Shape::Object { name, .. } => format!("{}.from_json({})", name, value),
+            _ => value.to_string(),
+        }
+    }
+
+    pub fn typescript_type(&self) -> String {
+        match self {
+            &Shape::Int | &Shape::Float => "number".to_string(),
+            &Shape::String => "string".to_string(),
+            &Shape::Bool => "boolean".to_string(),
+            &Shape::Any => "unknown".to_string(),
+            Shape::List(items) => format!("{}[]", items.typescript_type()),
+            Shape::Object { name, .. } => name.clone(),
+        }
+    }
+
+    pub fn rust_type(&self) -> String {
+        match self {
+            &Shape::Int => "i64".to_string(),
+            &Shape::Float => "f64".to_string(),
+            &Shape::String => "String".to_string(),
+            &Shape::Bool => "bool".to_string(),
+            &Shape::Any => "serde_json::Value".to_string(),
+            Shape::List(items) => format!("Vec<{}>", items.rust_type()),
+            Shape::Object { name, .. } => name.clone(),
+        }
+    }
+
+    /// Like python_field_type: a nested object is an Option
+    pub fn rust_field_type(&self) -> String {
+        match self {
+            Shape::Object { name, .. } => format!("Option<{}>", name),
+            _ => self.rust_type(),
+        }
+    }
+
+    /// A json value of this shape for the generated tests to decode.  Lists
+    /// get one item so the item decoding is covered too.
+    pub fn sample(&self) -> Json {
+        match self {
+            &Shape::Int => Json::I64(0),
+            &Shape::Float => Json::F64(0.0),
+            &Shape::String => Json::String(String::new()),
+            &Shape::Bool => Json::Boolean(false),
+            &Shape::Any => Json::Null,
+            Shape::List(items) => Json::Array(vec![items.sample()]),
+            Shape::Object { fields, .. } => {
+                Json::Object(fields.iter().map(|(key, shape)| (key.clone(), shape.sample())).collect())
+            }
+        }
+    }
+
+    fn collect_objects<'a>(&'a self, objects: &mut BTreeMap<&'a str, &'a BTreeMap<String, Shape>>) {
+        match self {
+            Shape::List(items) => items.collect_objects(objects),
+            Shape::Object { name, fields } => {
+                objects.insert(name, fields);
+                for field in fields.values() {
+                    field.collect_objects(objects);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Turn a json key into something usable as a Python or Rust identifier
+fn field_name(key: &str) -> String {
+    key.replace(" ", "_").replace("-", "_").replace(".", "_")
+}
+
+/// Like field_name but also steers clear of Rust keywords, the reserved ones
+/// included.  A trailing underscore rather than r# because self, Self, super
+/// and crate can't be raw identifiers.
+pub fn rust_field_name(key: &str) -> String {
+    let name = field_name(key);
+    match &name[..] {
+        "_" | "as" | "async" | "await" | "break" | "const" | "continue" | "crate" | "dyn" | "else" |
+        "enum" | "extern" | "false" | "fn" | "for" | "if" | "impl" | "in" | "let" | "loop" | "match" |
+        "mod" | "move" | "mut" | "pub" | "ref" | "return" | "self" | "Self" | "static" | "struct" |
+        "super" | "trait" | "true" | "type" | "unsafe" | "use" | "where" | "while" | "abstract" |
+        "become" | "box" | "do" | "final" | "gen" | "macro" | "override" | "priv" | "try" | "typeof" |
+        "unsized" | "virtual" | "yield" => format!("{}_", name),
+        _ => name,
+    }
+}
+
+/// Like field_name but also steers clear of Python keywords and the names
+/// the generated methods use themselves
+pub fn python_field_name(key: &str) -> String {
+    let name = field_name(key);
+    match &name[..] {
+        "False" | "None" | "True" | "and" | "as" | "assert" | "async" | "await" | "break" | "class" |
+        "continue" | "def" | "del" | "elif" | "else" | "except" | "finally" | "for" | "from" | "global" |
+        "if" | "import" | "in" | "is" | "lambda" | "nonlocal" | "not" | "or" | "pass" | "raise" | "return" |
+        "try" | "while" | "with" | "yield" | "self" | "cmd" => format!("{}_", name),
+        _ => name,
+    }
+}
+
+/// Like field_name but also steers clear of C++ keywords and json, which the
+/// generated builders use for the command they're building
+pub fn cpp_field_name(key: &str) -> String {
+    let name = field_name(key);
+    match &name[..] {
+        "auto" | "bool" | "break" | "case" | "catch" | "char" | "class" | "const" | "continue" | "default" |
+        "delete" | "do" | "double" | "else" | "enum" | "explicit" | "export" | "extern" | "false" | "float" |
+        "for" | "friend" | "goto" | "if" | "inline" | "int" | "long" | "mutable" | "namespace" | "new" |
+        "operator" | "private" | "protected" | "public" | "register" | "return" | "short" | "signed" |
+        "sizeof" | "static" | "struct" | "switch" | "template" | "this" | "throw" | "true" | "try" |
+        "typedef" | "typename" | "union" | "unsigned" | "using" | "virtual" | "void" | "volatile" | "while" |
+        "and" | "or" | "not" | "xor" | "json" => format!("{}_", name),
+        _ => name,
+    }
+}
+
+/// A TypeScript property name for a json key.  JSON.parse keeps the keys as
+/// they are so anything that isn't a plain identifier is quoted.
+pub fn typescript_property_name(key: &str) -> String {
+    let is_identifier = key.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '$') &&
+                        key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$');
+    if is_identifier {
+        key.to_string()
+    } else {
+        Json::String(key.to_string()).to_string()
+    }
+}
+
+/// Words split on anything that isn't a letter or digit, joined in CamelCase
+pub fn camel_case(name: &str) -> String {
+    name.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
+
+/// Response shapes keyed by Signature.prefix
+#[derive(Clone, Debug, Default)]
+pub struct SchemaRegistry {
+    schemas: HashMap<String, Shape>,
+}
+
+impl SchemaRegistry {
+    /// Parse a json registry file.  The top level object maps a command prefix
+    /// to the shape of its decoded json output.
+    pub fn parse(input: &str) -> Result<SchemaRegistry, String> {
+        let json = Json::from_str(input).map_err(|e| e.to_string())?;
+        let obj = match json {
+            Json::Object(obj) => obj,
+            _ => return Err("schema registry must be a json object keyed by command prefix".to_string()),
+        };
+        let mut schemas = HashMap::new();
+        for (prefix, value) in obj.iter() {
+            let shape = Shape::from_json(value).map_err(|e| format!("{}: {}", prefix, e))?;
+            schemas.insert(prefix.clone(), shape);
+        }
+        Ok(SchemaRegistry { schemas })
+    }
+
+    pub fn get(&self, prefix: &str) -> Option<&Shape> {
+        self.schemas.get(prefix)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.schemas.is_empty()
+    }
+
+    /// Prefixes of the json capable commands that have no schema yet
+    pub fn missing<'a>(&self, cmds: &'a [Command]) -> Vec<&'a str> {
+        let mut missing: Vec<&str> = cmds.iter()
+            .filter(|c| c.supports_json() && !self.schemas.contains_key(&c.signature.prefix))
+            .map(|c| &c.signature.prefix[..])
+            .collect();
+        missing.sort();
+        missing.dedup();
+        missing
+    }
+
+    /// Every named object in the registry, sorted by name
+    fn objects(&self) -> Vec<(&str, &BTreeMap<String, Shape>)> {
+        let mut objects = BTreeMap::new();
+        for shape in self.schemas.values() {
+            shape.collect_objects(&mut objects);
+        }
+        objects.into_iter().collect()
+    }
+
+    /// Class names of every object, sorted
+    pub fn object_names(&self) -> Vec<&str> {
+        self.objects().into_iter().map(|(name, _)| name).collect()
+    }
+
+    /// Python dataclasses with a from_json classmethod for every object.  The
+    /// module has to import dataclass, Any, List and Optional.
+    pub fn write_python(&self, w: &mut CodeWriter) {
+        for (name, fields) in self.objects() {
+            w.blank_lines(2);
+            w.line("@dataclass");
+            w.block(&format!("class {}:", name), |w| {
+                for (key, shape) in fields.iter() {
+                    w.line(&format!("{}: {}", python_field_name(key), shape.python_field_type()));
+                }
+                w.blank();
+                w.line("@classmethod");
+                w.block("def from_json(cls, data):", |w| {
+                    // Arguments go one indent further in if they don't fit on one line
+                    let room = w.available().saturating_sub(5);
+                    let args: Vec<String> = fields.iter()
+                        .map(|(key, shape)| {
+                            let value = match shape {
+                                &Shape::List(_) => format!("data.get('{}', [])", key),
+                                _ => format!("data.get('{}')", key),
+                            };
+                            let name = python_field_name(key);
+                            let arg = match shape {
+                                Shape::Object { .. } => {
+                                    format!("{}={} if {} is not None else None", name, shape.python_decode(&value), value)
+                                }
+                                _ => format!("{}={}", name, shape.python_decode(&value)),
+                            };
+                            // A comprehension that's too long continues under its bracket
+                            match arg.find(" for ") {
+                                Some(i) if arg.len() > room => {
+                                    format!("{}\n{}{}", &arg[..i], " ".repeat(name.len() + 2), &arg[i + 1..])
+                                }
+                                _ => arg,
+                            }
+                        })
+                        .collect();
+                    w.collection("return cls(", &args, ")");
+                });
+            });
+        }
+    }
+
+    /// TypeScript interfaces for every object
+    pub fn to_typescript(&self) -> String {
+        let mut output = String::new();
+        for (name, fields) in self.objects() {
+            output.push_str(&format!("export interface {} {{\n", name));
+            for (key, shape) in fields.iter() {
+                let optional = if let Shape::Object { .. } = shape { "?" } else { "" };
+                output.push_str(&format!("  {}{}: {};\n", typescript_property_name(key), optional,
+                                         shape.typescript_type()));
+            }
+            output.push_str("}\n\n");
+        }
+        output
+    }
+
+    /// Rust structs deserializable with serde for every object
+    pub fn to_rust(&self) -> String {
+        let mut output = String::new();
+        for (name, fields) in self.objects() {
+            output.push_str("#[derive(Clone, Debug, Deserialize)]\n");
+            output.push_str(&format!("pub struct {} {{\n", name));
+            for (key, shape) in fields.iter() {
+                let rust_name = rust_field_name(key);
+                if &rust_name != key {
+                    output.push_str(&format!("    #[serde(rename = \"{}\")]\n", key));
+                }
+                output.push_str(&format!("    pub {}: {},\n", rust_name, shape.rust_field_type()));
+            }
+            output.push_str("}\n\n");
+        }
+        output
+    }
+}
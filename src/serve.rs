@@ -0,0 +1,358 @@
+//! A stand-in for the monitors so generated clients can be tested without a
+//! cluster.  Commands are checked against the command table and answered from
+//! a directory of fixtures.
+
+use rustc_serialize::json::Json;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::thread;
+
+use caps::Caps;
+use ceph_command::{Command, Target};
+use validate;
+
+const EIO: i64 = 5;
+const EACCES: i64 = 13;
+const EINVAL: i64 = 22;
+
+/// Largest HTTP body read.  Commands are a few hundred bytes.
+const MAX_BODY: usize = 1 << 20;
+/// Largest HTTP request line and headers read, all together
+const MAX_HEADER: u64 = 8 << 10;
+
+#[cfg(test)]
+fn monitor(name: &str, caps: Option<Caps>) -> FakeMonitor {
+    let cmds = ::parse_input("test",
+                             b"COMMAND(\"osd pool get name=pool,type=CephPoolname \" \\\n\
+                               \"name=var,type=CephChoices,strings=size|pg_num\", \"get pool\", \"osd\", \"r\", \"cli,rest\")\n\
+                               COMMAND(\"osd pool rm name=pool,type=CephPoolname\", \"rm pool\", \"osd\", \"rw\", \"cli,rest\")")
+        .unwrap();
+    let dir = ::std::env::temp_dir().join(format!("command_parser_serve_{}_{}", name, ::std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("osd_pool_get.json"),
+              r#"[{"when": {"pool": "missing"}, "ret": -2, "outs": "unrecognized pool 'missing'"},
+                  {"outb": {"pool": "rbd", "size": 3}}]"#)
+        .unwrap();
+    FakeMonitor::new(cmds, Some(dir), caps)
+}
+
+#[test]
+fn test_handle() {
+    let monitor = monitor("handle", None);
+    let request = |json: &str| monitor.handle(&Json::from_str(json).unwrap());
+
+    assert_eq!(Response { ret: 0, outb: "{\"pool\":\"rbd\",\"size\":3}".to_string(), outs: String::new() },
+               request(r#"{"prefix": "osd pool get", "pool": "rbd", "var": "size"}"#));
+    assert_eq!(Response { ret: -2, outb: String::new(), outs: "unrecognized pool 'missing'".to_string() },
+               request(r#"{"prefix": "osd pool get", "pool": "missing", "var": "size"}"#));
+    // No fixture means success with no output
+    assert_eq!(Response { ret: 0, outb: String::new(), outs: String::new() },
+               request(r#"{"daemon": "mon", "cmd": {"prefix": "osd pool rm", "pool": "rbd"}}"#));
+
+    assert_eq!(-EINVAL, request(r#"{"prefix": "osd pool destroy"}"#).ret);
+    assert_eq!("var: \"min_size\" is not one of size|pg_num",
+               request(r#"{"prefix": "osd pool get", "pool": "rbd", "var": "min_size"}"#).outs);
+    assert_eq!("osd pool rm is a mon command, not osd",
+               request(r#"{"daemon": "osd", "id": 0, "cmd": {"prefix": "osd pool rm", "pool": "rbd"}}"#).outs);
+    fs::remove_dir_all(monitor.fixtures.unwrap()).unwrap();
+}
+
+#[test]
+fn test_caps() {
+    let caps = Caps { mon: Some("allow r".to_string()), mgr: None, osd: None, mds: None };
+    let monitor = monitor("caps", Some(caps));
+    let request = |json: &str| monitor.handle(&Json::from_str(json).unwrap());
+    assert_eq!(0, request(r#"{"prefix": "osd pool get", "pool": "rbd", "var": "size"}"#).ret);
+    assert_eq!(-EACCES, request(r#"{"prefix": "osd pool rm", "pool": "rbd"}"#).ret);
+    fs::remove_dir_all(monitor.fixtures.unwrap()).unwrap();
+}
+
+#[test]
+fn test_serve() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let monitor = monitor("serve", None);
+    let dir = monitor.fixtures.clone().unwrap();
+    thread::spawn(move || monitor.serve(listener));
+
+    // A line of json per request
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream.write_all(b"{\"prefix\": \"osd pool rm\", \"pool\": \"rbd\"}\nnot json\n").unwrap();
+    let mut lines = BufReader::new(stream).lines();
+    assert_eq!("{\"outb\":\"\",\"outs\":\"\",\"ret\":0}", lines.next().unwrap().unwrap());
+    assert!(lines.next().unwrap().unwrap().starts_with("{\"outb\":\"\",\"outs\":\"invalid json"));
+
+    // Or HTTP
+    let body = "{\"prefix\": \"osd pool rm\"}";
+    let mut stream = TcpStream::connect(addr).unwrap();
+    write!(stream, "POST / HTTP/1.1\r\nHost: localhost\r\nContent-Length: {}\r\n\r\n{}", body.len(), body).unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(response.ends_with("\r\n\r\n{\"outb\":\"\",\"outs\":\"missing required parameter pool\",\"ret\":-22}"));
+
+    // The body isn't read, let alone allocated, if it's too big
+    let mut stream = TcpStream::connect(addr).unwrap();
+    write!(stream, "POST / HTTP/1.1\r\nContent-Length: 99999999999\r\n\r\n").unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    assert!(response.starts_with("HTTP/1.1 413 Payload Too Large\r\n"));
+
+    // Nor is it guessed at if the length is missing or bad
+    for length in &["", "Content-Length: lots\r\n", "Content-Length: -1\r\n"] {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        write!(stream, "POST / HTTP/1.1\r\n{}\r\n", length).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        assert_eq!("HTTP/1.1 400 Bad Request\r\nContent-Length: 0\r\nConnection: close\r\n\r\n", response);
+    }
+
+    // Headers are capped like the body.  Exactly the cap is sent so nothing
+    // is left unread when the connection is closed.
+    let head = "POST / HTTP/1.1\r\nX-Padding: ";
+    let mut stream = TcpStream::connect(addr).unwrap();
+    write!(stream, "{}{}", head, "a".repeat(MAX_HEADER as usize - head.len())).unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    assert!(response.starts_with("HTTP/1.1 431 Request Header Fields Too Large\r\n"));
+    fs::remove_dir_all(dir).unwrap();
+}
+
+/// What a daemon answered, shaped like the TypeScript client's CephResponse
+#[derive(Clone, Debug, PartialEq)]
+pub struct Response {
+    /// 0 or a negative errno
+    pub ret: i64,
+    pub outb: String,
+    pub outs: String,
+}
+
+impl Response {
+    /// Success with no output
+    fn ok() -> Response {
+        Response {
+            ret: 0,
+            outb: String::new(),
+            outs: String::new(),
+        }
+    }
+
+    fn error(errno: i64, outs: String) -> Response {
+        Response {
+            ret: -errno,
+            outb: String::new(),
+            outs,
+        }
+    }
+
+    pub fn to_json(&self) -> Json {
+        let mut obj = BTreeMap::new();
+        obj.insert("ret".to_string(), Json::I64(self.ret));
+        obj.insert("outb".to_string(), Json::String(self.outb.clone()));
+        obj.insert("outs".to_string(), Json::String(self.outs.clone()));
+        Json::Object(obj)
+    }
+
+    /// A response from a fixture.  Every key is optional and outb that isn't
+    /// a string is sent as json.
+    fn from_fixture(fixture: &Json) -> Result<Response, String> {
+        let ret = match fixture.find("ret") {
+            Some(ret) => ret.as_i64().ok_or(format!("ret should be an int, found {}", ret))?,
+            None => 0,
+        };
+        let outb = match fixture.find("outb") {
+            Some(Json::String(s)) => s.clone(),
+            Some(json) => json.to_string(),
+            None => String::new(),
+        };
+        let outs = match fixture.find("outs") {
+            Some(outs) => outs.as_string().ok_or(format!("outs should be a string, found {}", outs))?.to_string(),
+            None => String::new(),
+        };
+        Ok(Response {
+            ret,
+            outb,
+            outs,
+        })
+    }
+}
+
+/// Does every key in the fixture's "when" have the same value in args?
+fn matches(fixture: &Json, args: &Json) -> bool {
+    match fixture.find("when").and_then(|w| w.as_object()) {
+        Some(when) => when.iter().all(|(key, value)| args.find(key) == Some(value)),
+        None => true,
+    }
+}
+
+/// Answers commands the way a monitor would
+pub struct FakeMonitor {
+    cmds: Vec<Command>,
+    /// Where the canned responses are read from
+    fixtures: Option<PathBuf>,
+    /// The caps of whoever is connecting.  None allows everything.
+    caps: Option<Caps>,
+}
+
+/// An HTTP response with no body
+fn status_line(status: &str) -> String {
+    format!("HTTP/1.1 {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n", status)
+}
+
+impl FakeMonitor {
+    pub fn new(cmds: Vec<Command>, fixtures: Option<PathBuf>, caps: Option<Caps>) -> FakeMonitor {
+        FakeMonitor {
+            cmds,
+            fixtures,
+            caps,
+        }
+    }
+
+    /// Answer a request.  It's either a json command or, like the TypeScript
+    /// client sends, {"daemon": "osd", "id": 0, "cmd": {...}}.  Commands that
+    /// don't fit the table get EINVAL and ones the caps don't allow EACCES.
+    pub fn handle(&self, request: &Json) -> Response {
+        let (daemon, args) = match request.find("cmd") {
+            Some(args) => (request.find("daemon").and_then(|d| d.as_string()), args),
+            None => (None, request),
+        };
+        let cmd = match validate::validate(&self.cmds, args) {
+            Ok(cmd) => cmd,
+            Err(problems) => return Response::error(EINVAL, problems.join("; ")),
+        };
+        if let Some(daemon) = daemon {
+            if Target::from_str(daemon).as_ref() != Some(&cmd.target) {
+                return Response::error(EINVAL,
+                                       format!("{} is a {} command, not {}",
+                                               cmd.signature.prefix,
+                                               cmd.target.name(),
+                                               daemon));
+            }
+        }
+        if let Some(ref caps) = self.caps {
+            if !caps.allows(cmd) {
+                return Response::error(EACCES, "access denied".to_string());
+            }
+        }
+        self.fixture(cmd, args).unwrap_or_else(|e| Response::error(EIO, e))
+    }
+
+    /// The canned response for args.  <fixtures>/osd_pool_get.json answers
+    /// osd pool get.  It holds a response, {"ret": 0, "outb": ..., "outs":
+    /// ...}, or a list of them where the first whose "when" matches the
+    /// arguments wins.  Without one the command succeeds with no output.
+    fn fixture(&self, cmd: &Command, args: &Json) -> Result<Response, String> {
+        let path = match self.fixtures {
+            Some(ref dir) => dir.join(format!("{}.json", cmd.signature.prefix.replace(' ', "_"))),
+            None => return Ok(Response::ok()),
+        };
+        if !path.exists() {
+            return Ok(Response::ok());
+        }
+        let contents = fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let fixture = Json::from_str(&contents).map_err(|e| format!("Failed to parse {}: {}", path.display(), e))?;
+        let responses = match fixture {
+            Json::Array(responses) => responses,
+            response => vec![response],
+        };
+        match responses.iter().find(|r| matches(r, args)) {
+            Some(response) => Response::from_fixture(response).map_err(|e| format!("{}: {}", path.display(), e)),
+            None => Ok(Response::ok()),
+        }
+    }
+
+    fn answer(&self, request: &str) -> Response {
+        let response = match Json::from_str(request) {
+            Ok(json) => self.handle(&json),
+            Err(e) => Response::error(EINVAL, format!("invalid json: {}", e)),
+        };
+        info!("{} -> {}", request.trim(), response.ret);
+        response
+    }
+
+    /// Newline delimited json: a request per line, a response per line
+    fn json_lines(&self, reader: BufReader<TcpStream>, mut writer: TcpStream) -> io::Result<()> {
+        for line in reader.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            writeln!(writer, "{}", self.answer(&line).to_json())?;
+        }
+        Ok(())
+    }
+
+    /// A single HTTP request with the json request as its body
+    fn http(&self, mut reader: BufReader<TcpStream>, mut writer: TcpStream) -> io::Result<()> {
+        let mut head = (&mut reader).take(MAX_HEADER);
+        let mut request_line = String::new();
+        head.read_line(&mut request_line)?;
+        // None if it's missing or isn't a number
+        let mut length: Option<usize> = None;
+        let mut complete = request_line.ends_with('\n');
+        while complete {
+            let mut header = String::new();
+            head.read_line(&mut header)?;
+            complete = header.ends_with('\n');
+            if !complete || header.trim().is_empty() {
+                break;
+            }
+            let mut parts = header.splitn(2, ':');
+            let name = parts.next().unwrap_or("").trim().to_lowercase();
+            if name == "content-length" {
+                length = parts.next().unwrap_or("").trim().parse().ok();
+            }
+        }
+        if !complete {
+            // Either the headers are too long or the client hung up
+            return write!(writer, "{}", status_line("431 Request Header Fields Too Large"));
+        }
+        if !request_line.starts_with("POST ") {
+            return write!(writer,
+                          "HTTP/1.1 405 Method Not Allowed\r\nAllow: POST\r\nContent-Length: 0\r\nConnection: close\r\n\r\n");
+        }
+        let length = match length {
+            Some(length) if length > MAX_BODY => return write!(writer, "{}", status_line("413 Payload Too Large")),
+            Some(length) => length,
+            None => return write!(writer, "{}", status_line("400 Bad Request")),
+        };
+        let mut body = vec![0; length];
+        reader.read_exact(&mut body)?;
+        let response = self.answer(&String::from_utf8_lossy(&body)).to_json().to_string();
+        write!(writer,
+               "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+               response.len(),
+               response)
+    }
+
+    fn connection(&self, stream: TcpStream) -> io::Result<()> {
+        let mut reader = BufReader::new(stream.try_clone()?);
+        // HTTP starts with a method, json with a brace
+        let is_http = reader.fill_buf()?.first().is_some_and(|c| c.is_ascii_alphabetic());
+        if is_http {
+            self.http(reader, stream)
+        } else {
+            self.json_lines(reader, stream)
+        }
+    }
+
+    /// Answer connections on listener until it fails, each on a thread of its
+    /// own.  A connection can speak newline delimited json or HTTP.
+    pub fn serve(self, listener: TcpListener) -> io::Result<()> {
+        let monitor = Arc::new(self);
+        for stream in listener.incoming() {
+            let stream = stream?;
+            let monitor = monitor.clone();
+            thread::spawn(move || {
+                if let Err(e) = monitor.connection(stream) {
+                    warn!("Connection failed: {}", e);
+                }
+            });
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,241 @@
+//! Generator for in-house clients.  The parsed commands are handed to a
+//! minijinja template, so a new language is a template rather than a fork.
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::PathBuf;
+
+use minijinja::{Environment, UndefinedBehavior, Value};
+use rustc_serialize::json::Json;
+
+use ceph_command::{self, Command, Flag, GeneratorOptions, Module};
+use model;
+
+#[cfg(test)]
+use test_fixtures::pool_create;
+
+#[test]
+fn test_render() {
+    let template = r#"{% for module in modules %}
+class {{ module.class_name }}:
+{% for cmd in module.commands %}
+    def {{ cmd.method_name }}(self{% for p in cmd.params %}, {{ p.api_name }}{% endfor %}):
+        """{{ cmd.helpstring | wrap(20) }}{{ " (deprecated)" if cmd.deprecated }}"""
+        cmd = {'prefix': {{ cmd.prefix | quote }}}
+{% for p in cmd.params %}
+        # {{ p.name }}: {{ p.type }} req={{ p.req }} many={{ p.many }} min={{ p.min | default("-") }}
+{% endfor %}
+        # {{ cmd.flags | join(",") }} read={{ cmd.perms.read }} write={{ cmd.perms.write }}
+{% endfor %}
+{% endfor %}
+"#;
+    let output = render(template, None, &[pool_create()], &GeneratorOptions::default()).unwrap();
+    assert_eq!(r#"class OsdCommand:
+    def osd_pool_create(self, pool, pg_num, pool_type, crush-rule):
+        """create pool (deprecated)"""
+        cmd = {'prefix': "osd pool create"}
+        # pool: CephPoolname req=True many=False min=-
+        # pg_num: CephInt req=True many=False min=0
+        # pool_type: CephChoices req=False many=False min=-
+        # crush-rule: CephString req=False many=True min=-
+        # DEPRECATED read=True write=True
+"#,
+               output);
+
+    // Unlike the other generators a template gets the commands of unknown modules too
+    let output = render("{% for module in modules %}{{ module.name }} {% endfor %}", None,
+                        &::test_fixtures::commands(), &GeneratorOptions::default()).unwrap();
+    assert_eq!("pg mds osd mgr unknown ", output);
+
+    let err = render("{{ commands[0].nope }}", None, &[pool_create()], &GeneratorOptions::default()).unwrap_err();
+    assert!(err.contains("undefined"), "{}", err);
+}
+
+#[test]
+fn test_filters() {
+    assert_eq!("osd_pool_create", snake_case("osd pool create"));
+    assert_eq!("osd_pool_create", snake_case("OsdPoolCreate"));
+    assert_eq!("pg_num_max", snake_case("pg-num-max"));
+    assert_eq!("http_server2", snake_case("HTTPServer2"));
+    assert_eq!("osdPoolCreate", camel_case("osd pool create"));
+    assert_eq!("pgNum", camel_case("PG_NUM"));
+    assert_eq!("\"say \\\"hi\\\"\"", quote("say \"hi\""));
+    assert_eq!("show the\nstatus of\nthe\ncluster", wrap("show the status of the cluster", Some(9)));
+    assert_eq!("a\nverylongword\nb", wrap("a verylongword b", Some(5)));
+}
+
+/// Words in a name.  Anything that isn't a letter or digit separates words,
+/// as does a change from lower to upper case.
+fn words(name: &str) -> Vec<String> {
+    let chars: Vec<char> = name.chars().collect();
+    let mut words: Vec<String> = Vec::new();
+    let mut word = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        if !c.is_alphanumeric() {
+            if !word.is_empty() {
+                words.push(word.clone());
+                word.clear();
+            }
+            continue;
+        }
+        if c.is_uppercase() && !word.is_empty() {
+            let prev = chars[i - 1];
+            let next_is_lower = chars.get(i + 1).is_some_and(|n| n.is_lowercase());
+            if prev.is_lowercase() || prev.is_numeric() || (prev.is_uppercase() && next_is_lower) {
+                words.push(word.clone());
+                word.clear();
+            }
+        }
+        word.extend(c.to_lowercase());
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+}
+
+fn snake_case(name: &str) -> String {
+    words(name).join("_")
+}
+
+fn camel_case(name: &str) -> String {
+    words(name)
+        .iter()
+        .enumerate()
+        .map(|(i, word)| {
+            if i == 0 {
+                return word.clone();
+            }
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
+
+/// A double quoted string with json escapes
+fn quote(s: &str) -> String {
+    Json::String(s.to_string()).to_string()
+}
+
+/// Fill text into lines of at most width characters.  Words longer than that
+/// get a line to themselves.
+fn wrap(text: &str, width: Option<usize>) -> String {
+    let width = width.unwrap_or(79);
+    let mut lines: Vec<String> = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > width {
+            lines.push(line.clone());
+            line.clear();
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines.join("\n")
+}
+
+fn to_value(json: &Json) -> Value {
+    match json {
+        &Json::I64(n) => Value::from(n),
+        &Json::U64(n) => Value::from(n),
+        &Json::F64(f) => Value::from(f),
+        Json::String(s) => Value::from(s.clone()),
+        &Json::Boolean(b) => Value::from(b),
+        Json::Array(items) => Value::from(items.iter().map(to_value).collect::<Vec<Value>>()),
+        Json::Object(obj) => {
+            Value::from(obj.iter().map(|(k, v)| (k.clone(), to_value(v))).collect::<BTreeMap<String, Value>>())
+        }
+        &Json::Null => Value::from(()),
+    }
+}
+
+/// The dump of a command plus the things every generator works out for
+/// itself: names, split permissions and which params are lists
+fn command_value(cmd: &Command) -> Value {
+    let mut obj = match model::command_to_json(cmd) {
+        Json::Object(obj) => obj,
+        _ => unreachable!(),
+    };
+    obj.insert("method_name".to_string(), Json::String(cmd.method_name()));
+    obj.insert("deprecated".to_string(),
+               Json::Boolean(cmd.flags.as_ref().is_some_and(|f| f.contains(&Flag::Deprecated))));
+    obj.insert("supports_json".to_string(), Json::Boolean(cmd.supports_json()));
+    let mut perms = BTreeMap::new();
+    perms.insert("read".to_string(), Json::Boolean(cmd.permissions.read));
+    perms.insert("write".to_string(), Json::Boolean(cmd.permissions.write));
+    perms.insert("execute".to_string(), Json::Boolean(cmd.permissions.execute));
+    obj.insert("perms".to_string(), Json::Object(perms));
+    if let Some(&mut Json::Array(ref mut params)) = obj.get_mut("params") {
+        for (param, (name, ceph_type)) in params.iter_mut().zip(cmd.signature.ordered_parameters()) {
+            if let &mut Json::Object(ref mut param) = param {
+                param.insert("api_name".to_string(), Json::String(cmd.signature.api_name(name).to_string()));
+                param.insert("many".to_string(), Json::Boolean(ceph_type.variant.is_many()));
+            }
+        }
+    }
+    to_value(&Json::Object(obj))
+}
+
+/// Render a template over cmds.  Templates see `commands`, every command in
+/// the order they were read, and `modules`, each with a `name`, `class_name`
+/// and its `commands`.  `{% include %}` and `{% import %}` look in dir.
+pub fn render(template: &str,
+              dir: Option<PathBuf>,
+              cmds: &[Command],
+              options: &GeneratorOptions)
+              -> Result<String, String> {
+    let mut env = Environment::new();
+    env.set_undefined_behavior(UndefinedBehavior::Strict);
+    env.set_keep_trailing_newline(true);
+    env.set_trim_blocks(true);
+    env.set_lstrip_blocks(true);
+    env.add_filter("snake_case", |s: &str| snake_case(s));
+    env.add_filter("camelCase", |s: &str| camel_case(s));
+    env.add_filter("quote", |s: &str| quote(s));
+    env.add_filter("wrap", |s: &str, width: Option<usize>| wrap(s, width));
+    if let Some(dir) = dir {
+        env.set_loader(move |name| {
+            let path = dir.join(name);
+            if !path.is_file() {
+                return Ok(None);
+            }
+            let mut contents = String::new();
+            File::open(&path).and_then(|mut f| f.read_to_string(&mut contents)).map_err(|e| {
+                minijinja::Error::new(minijinja::ErrorKind::InvalidOperation,
+                                      format!("Failed to read {}: {}", path.display(), e))
+            })?;
+            Ok(Some(contents))
+        });
+    }
+
+    let mut modules: Vec<Value> = Vec::new();
+    // Templates see every command, the ones from modules the other generators leave out included
+    let mut all_modules = Module::all();
+    all_modules.push(Module::Unknown);
+    for (module, module_commands) in ceph_command::group_by_module(cmds, &all_modules) {
+        let mut obj: BTreeMap<String, Value> = BTreeMap::new();
+        obj.insert("name".to_string(), Value::from(module.name()));
+        obj.insert("class_name".to_string(), Value::from(module.clone().to_string()));
+        obj.insert("commands".to_string(),
+                   Value::from(module_commands.iter().map(command_value).collect::<Vec<Value>>()));
+        modules.push(Value::from(obj));
+    }
+
+    let mut context: BTreeMap<String, Value> = BTreeMap::new();
+    context.insert("commands".to_string(), Value::from(cmds.iter().map(command_value).collect::<Vec<Value>>()));
+    context.insert("modules".to_string(), Value::from(modules));
+    context.insert("json_output".to_string(), Value::from(options.json_output));
+    context.insert("model_version".to_string(), Value::from(model::MODEL_VERSION));
+
+    let tmpl = env.template_from_named_str("template", template).map_err(|e| format!("{:#}", e))?;
+    tmpl.render(Value::from(context)).map_err(|e| format!("{:#}", e))
+}
@@ -0,0 +1,27 @@
+//! The command table in fixtures/test/MonCommands.h that the unit tests build
+//! their commands from.
+
+use ceph_command::{Command, Target};
+
+/// Every command in the table
+pub fn commands() -> Vec<Command> {
+    ::parse_input("fixtures/test/MonCommands.h", include_bytes!("../fixtures/test/MonCommands.h")).unwrap()
+}
+
+/// The first command with prefix
+pub fn command(prefix: &str) -> Command {
+    commands().into_iter()
+        .find(|c| c.signature.prefix == prefix)
+        .unwrap_or_else(|| panic!("no {:?} in fixtures/test/MonCommands.h", prefix))
+}
+
+pub fn pool_create() -> Command {
+    command("osd pool create")
+}
+
+/// The first command with prefix, sent to target
+pub fn sent_to(prefix: &str, target: Target) -> Command {
+    let mut cmd = command(prefix);
+    cmd.target = target;
+    cmd
+}
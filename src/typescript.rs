@@ -0,0 +1,321 @@
+use rustc_serialize::json::Json;
+
+use ceph_command::{self, CephEnum, Command, Flag, GeneratorOptions, Module, Target};
+use generator::Generator;
+#[cfg(test)]
+use schema::SchemaRegistry;
+use schema::{camel_case, typescript_property_name};
+
+#[cfg(test)]
+use test_fixtures::pool_create;
+
+#[test]
+fn test_typescript_interface() {
+    let mut cmd = pool_create();
+    cmd.helpstring = "create pool */ now".to_string();
+    let interface = interface(&cmd);
+    assert!(interface.starts_with("/**\n * create pool *\\/ now\n *\n * @deprecated\n */\n"));
+    assert!(interface.contains("export interface OsdPoolCreate {\n  prefix: \"osd pool create\";\n"));
+    assert!(interface.contains("  pool: string;\n  /** From 0 to 65536 */\n  pg_num: number;\n"));
+    assert!(interface.contains("  pool_type?: \"replicated\" | \"erasure\";\n  \"crush-rule\"?: string[];\n}\n"));
+}
+
+#[test]
+fn test_typescript_tell() {
+    let mut cmd = pool_create();
+    cmd.target = Target::Osd;
+    cmd.signature.duplicate = true;
+    cmd.signature.parameters.clear();
+    let interface = interface(&cmd);
+    assert!(interface.contains("export interface OsdPoolCreate2 {\n"));
+    assert!(interface.contains("  /** The osd to send the command to */\n  osd_id: number;\n}\n"));
+
+    let output = generate(&[cmd], &GeneratorOptions::default());
+    assert!(output.contains("export type CephCommand =\n  | OsdPoolCreate2;\n"));
+    assert!(output.contains("  \"osd pool create\": \"osd\",\n"));
+}
+
+#[test]
+fn test_typescript_json() {
+    let schemas = SchemaRegistry::parse(r#"{"osd pool ls": {"list": {"name": "Pool", "fields": {"pool-id": "int"}}}}"#)
+        .unwrap();
+    let list = ::parse_input("test", b"COMMAND(\"osd pool ls\", \"list pools\", \"osd\", \"r\", \"cli,rest\")")
+        .unwrap()
+        .remove(0);
+    let cmds = vec![pool_create(), list];
+
+    let plain = generate(&cmds, &GeneratorOptions { schemas: schemas.clone(), ..GeneratorOptions::default() });
+    assert!(plain.contains("export interface Pool {\n  \"pool-id\": number;\n}\n"));
+    assert!(plain.contains("execute<C extends CephCommand>(cmd: C): Promise<CephResponse> {\n"));
+    assert!(!plain.contains("JSON_COMMANDS"));
+
+    let json = generate(&cmds, &GeneratorOptions { json_output: true, schemas, ..GeneratorOptions::default() });
+    assert!(json.contains("const JSON_COMMANDS: ReadonlySet<string> = new Set([\n  \"osd pool ls\",\n]);\n"));
+    assert!(json.contains("export interface CephResults {\n  \"osd pool ls\": Pool[];\n}\n"));
+    assert!(json.contains("execute<C extends CephCommand>(cmd: C): Promise<CephResult<C>> {\n"));
+    assert!(json.contains("    if (json && args.format === undefined) {\n      args.format = \"json\";\n"));
+    assert!(json.contains("      return JSON.parse(response.outb) as CephResult<C>;\n"));
+}
+
+fn preamble() -> String {
+    let mut output = String::new();
+    output.push_str("// Generated by command_parser from the Ceph command tables.  Do not edit.\n\n");
+    output.push_str("/** The kind of daemon a command is sent to */\n");
+    output.push_str("export type CephDaemon = \"mon\" | \"mgr\" | \"osd\" | \"mds\";\n\n");
+    output.push_str("/** A command on its way to a daemon */\n");
+    output.push_str("export interface CephRequest {\n");
+    output.push_str("  daemon: CephDaemon;\n");
+    output.push_str("  /** The osd or mds to send the command to.  Unset for mon and mgr commands */\n");
+    output.push_str("  id?: number | string;\n");
+    output.push_str("  /** The json command including its prefix */\n");
+    output.push_str("  cmd: { [key: string]: unknown };\n");
+    output.push_str("}\n\n");
+    output.push_str("/** What a daemon answered */\n");
+    output.push_str("export interface CephResponse {\n");
+    output.push_str("  /** 0 or a negative errno */\n");
+    output.push_str("  ret: number;\n");
+    output.push_str("  /** The output buffer */\n");
+    output.push_str("  outb: string;\n");
+    output.push_str("  /** The status string */\n");
+    output.push_str("  outs: string;\n");
+    output.push_str("}\n\n");
+    output.push_str("/**\n");
+    output.push_str(" * Sends a request and resolves with the answer.  Usually a small wrapper\n");
+    output.push_str(" * around fetch() posting to the mgr restful module.\n");
+    output.push_str(" */\n");
+    output.push_str("export type CephTransport = (request: CephRequest) => Promise<CephResponse>;\n\n");
+    output.push_str("/** A command that came back with a non-zero return code */\n");
+    output.push_str("export class CephError extends Error {\n");
+    output.push_str("  constructor(readonly cmd: { [key: string]: unknown }, readonly ret: number, readonly outs: string) {\n");
+    output.push_str("    super(`${String(cmd.prefix)} failed with ${ret}: ${outs}`);\n");
+    output.push_str("    this.name = \"CephError\";\n");
+    output.push_str("  }\n");
+    output.push_str("}\n\n");
+    output
+}
+
+fn client(json_output: bool) -> String {
+    let mut output = String::new();
+    output.push_str("/** Runs commands over a transport */\n");
+    output.push_str("export class CephClient {\n");
+    output.push_str("  constructor(private readonly transport: CephTransport) {}\n\n");
+    output.push_str("  /**\n");
+    if json_output {
+        output.push_str("   * Send a command and resolve with the answer.  Commands that support json\n");
+        output.push_str("   * are sent format=json unless they set a format and resolve with the\n");
+        output.push_str("   * decoded output.  Rejects with a CephError if the return code isn't 0\n");
+        output.push_str("   * or the output isn't valid json.\n");
+        output.push_str("   */\n");
+        output.push_str("  async execute<C extends CephCommand>(cmd: C): Promise<CephResult<C>> {\n");
+        output.push_str("    const args: { [key: string]: unknown } = { ...cmd };\n");
+        output.push_str("    const json = JSON_COMMANDS.has(cmd.prefix);\n");
+        output.push_str("    if (json && args.format === undefined) {\n");
+        output.push_str("      args.format = \"json\";\n");
+        output.push_str("    }\n");
+    } else {
+        output.push_str("   * Send a command and resolve with the answer.  Rejects with a CephError\n");
+        output.push_str("   * if the return code isn't 0.\n");
+        output.push_str("   */\n");
+        output.push_str("  async execute<C extends CephCommand>(cmd: C): Promise<CephResponse> {\n");
+        output.push_str("    const args: { [key: string]: unknown } = { ...cmd };\n");
+    }
+    output.push_str("    const daemon = DAEMONS[cmd.prefix];\n");
+    output.push_str("    let id: number | string | undefined;\n");
+    output.push_str("    if (daemon === \"osd\" || daemon === \"mds\") {\n");
+    output.push_str("      id = args[`${daemon}_id`] as number | string;\n");
+    output.push_str("      delete args[`${daemon}_id`];\n");
+    output.push_str("    }\n");
+    output.push_str("    const response = await this.transport({ daemon: daemon, id: id, cmd: args });\n");
+    output.push_str("    if (response.ret !== 0) {\n");
+    output.push_str("      throw new CephError(args, response.ret, response.outs);\n");
+    output.push_str("    }\n");
+    if json_output {
+        output.push_str("    if (!json) {\n");
+        output.push_str("      return response as CephResult<C>;\n");
+        output.push_str("    }\n");
+        output.push_str("    try {\n");
+        output.push_str("      return JSON.parse(response.outb) as CephResult<C>;\n");
+        output.push_str("    } catch (e) {\n");
+        output.push_str("      throw new CephError(args, -22, `Failed to decode json output: ${String(e)}`);\n");
+        output.push_str("    }\n");
+    } else {
+        output.push_str("    return response;\n");
+    }
+    output.push_str("  }\n");
+    output.push_str("}\n");
+    output
+}
+
+fn string_literal(s: &str) -> String {
+    Json::String(s.to_string()).to_string()
+}
+
+/// Name of the interface for a command, the method name in CamelCase
+fn interface_name(cmd: &Command) -> String {
+    camel_case(&cmd.method_name())
+}
+
+/// The TypeScript type of a parameter's value
+fn ts_type(variant: &CephEnum) -> String {
+    let base = match variant {
+        &CephEnum::CephInt { .. } => "number".to_string(),
+        &CephEnum::CephFloat { .. } => "number".to_string(),
+        &CephEnum::CephBool => "boolean".to_string(),
+        CephEnum::CephChoices { choices, .. } if !choices.is_empty() => {
+            let literals: Vec<String> = choices.iter().map(|c| string_literal(c)).collect();
+            literals.join(" | ")
+        }
+        _ => "string".to_string(),
+    };
+    if !variant.is_many() {
+        base
+    } else if base.contains('|') {
+        format!("({})[]", base)
+    } else {
+        format!("{}[]", base)
+    }
+}
+
+/// What a TypeScript type can't say about a parameter.  Empty if nothing.
+fn property_doc(variant: &CephEnum) -> String {
+    match *variant {
+        CephEnum::CephInt { min: Some(min), max: Some(max) } => format!("From {} to {}", min, max),
+        CephEnum::CephInt { min: Some(min), max: None } => format!("At least {}", min),
+        CephEnum::CephInt { min: None, max: Some(max) } => format!("At most {}", max),
+        CephEnum::CephFloat { min: Some(min), max: Some(max) } => format!("From {} to {}", min, max),
+        CephEnum::CephFloat { min: Some(min), max: None } => format!("At least {}", min),
+        CephEnum::CephFloat { min: None, max: Some(max) } => format!("At most {}", max),
+        CephEnum::CephString { goodchars: Some(ref good), .. } => format!("Only the characters {}", good),
+        CephEnum::CephString { badchars: Some(ref bad), .. } => format!("None of the characters {}", bad),
+        CephEnum::CephPgid => "A placement group id such as 1.2f".to_string(),
+        CephEnum::CephTimespan => "A duration such as 30, 5m or 1h30m".to_string(),
+        _ => String::new(),
+    }
+}
+
+/// TSDoc text can't contain the end of the comment
+fn doc_text(text: &str) -> String {
+    text.trim().replace("*/", "*\\/")
+}
+
+/// The interface for the json object of a command
+fn interface(cmd: &Command) -> String {
+    let mut output = String::new();
+    output.push_str("/**\n");
+    for line in cmd.helpstring.lines() {
+        output.push_str(&format!(" * {}\n", doc_text(line)).replace(" * \n", " *\n"));
+    }
+    let deprecated = cmd.flags.as_ref().is_some_and(|flags| flags.contains(&Flag::Deprecated));
+    if deprecated {
+        output.push_str(" *\n * @deprecated\n");
+    }
+    output.push_str(" */\n");
+    output.push_str(&format!("export interface {} {{\n", interface_name(cmd)));
+    output.push_str(&format!("  prefix: {};\n", string_literal(&cmd.signature.prefix)));
+    for (key, ceph_type) in cmd.signature.ordered_parameters() {
+        let doc = property_doc(&ceph_type.variant);
+        if !doc.is_empty() {
+            output.push_str(&format!("  /** {} */\n", doc_text(&doc)));
+        }
+        output.push_str(&format!("  {}{}: {};\n",
+                                 typescript_property_name(key),
+                                 if ceph_type.req { "" } else { "?" },
+                                 ts_type(&ceph_type.variant)));
+    }
+    match cmd.target {
+        Target::Osd => output.push_str("  /** The osd to send the command to */\n  osd_id: number;\n"),
+        Target::Mds => output.push_str("  /** The mds to send the command to */\n  mds_id: string;\n"),
+        _ => {}
+    }
+    output.push_str("}\n");
+    output
+}
+
+/// The decoded result of every command that supports json, typed by its
+/// schema if there is one
+fn results(cmds: &[Command], options: &GeneratorOptions) -> String {
+    let mut prefixes: Vec<&String> = cmds.iter()
+        .filter(|c| c.supports_json())
+        .map(|c| &c.signature.prefix)
+        .collect();
+    prefixes.sort();
+    prefixes.dedup();
+
+    let mut output = String::new();
+    output.push_str("/** The commands that answer in json */\n");
+    output.push_str("const JSON_COMMANDS: ReadonlySet<string> = new Set([\n");
+    for prefix in prefixes.iter() {
+        output.push_str(&format!("  {},\n", string_literal(prefix)));
+    }
+    output.push_str("]);\n\n");
+    output.push_str("/** The decoded output of the commands that answer in json */\n");
+    output.push_str("export interface CephResults {\n");
+    for prefix in prefixes.iter() {
+        let result = options.schemas.get(prefix).map_or("unknown".to_string(), |shape| shape.typescript_type());
+        output.push_str(&format!("  {}: {};\n", string_literal(prefix), result));
+    }
+    output.push_str("}\n\n");
+    output.push_str("/** What execute() resolves with for a command */\n");
+    output.push_str("export type CephResult<C extends CephCommand> =\n");
+    output.push_str("  C[\"prefix\"] extends keyof CephResults ? CephResults[C[\"prefix\"]] : CephResponse;\n\n");
+    output
+}
+
+/// Generate a TypeScript module for cmds with an interface per command and a
+/// client to run them.  With json_output the client decodes json answers,
+/// typed by options.schemas where there's a schema.
+pub fn generate(cmds: &[Command], options: &GeneratorOptions) -> String {
+    let mut output = preamble();
+    if !options.schemas.is_empty() {
+        output.push_str("// Responses\n\n");
+        output.push_str(&options.schemas.to_typescript());
+    }
+    let mut names: Vec<String> = Vec::new();
+    let mut daemons: Vec<(String, &'static str)> = Vec::new();
+
+    for (module, module_commands) in ceph_command::group_by_module(cmds, &Module::all()) {
+        output.push_str(&format!("// {} commands\n", module.name()));
+        for cmd in module_commands.iter() {
+            output.push('\n');
+            output.push_str(&interface(cmd));
+            names.push(interface_name(cmd));
+            if !daemons.iter().any(|(prefix, _)| *prefix == cmd.signature.prefix) {
+                daemons.push((cmd.signature.prefix.clone(), cmd.target.name()));
+            }
+        }
+        output.push('\n');
+    }
+
+    output.push_str("/** Every command */\n");
+    if names.is_empty() {
+        output.push_str("export type CephCommand = never;\n\n");
+    } else {
+        output.push_str("export type CephCommand =\n");
+        output.push_str(&names.iter().map(|n| format!("  | {}", n)).collect::<Vec<String>>().join("\n"));
+        output.push_str(";\n\n");
+    }
+    output.push_str("/** The daemon each command is sent to */\n");
+    output.push_str("const DAEMONS: { [prefix: string]: CephDaemon } = {\n");
+    for &(ref prefix, daemon) in daemons.iter() {
+        output.push_str(&format!("  {}: \"{}\",\n", string_literal(prefix), daemon));
+    }
+    output.push_str("};\n\n");
+    if options.json_output {
+        output.push_str(&results(cmds, options));
+    }
+    output.push_str(&client(options.json_output));
+    output
+}
+
+/// The TypeScript client
+pub struct TypeScript;
+
+impl Generator for TypeScript {
+    fn name(&self) -> &'static str {
+        "typescript"
+    }
+
+    fn files(&self, cmds: &[Command], options: &GeneratorOptions) -> Result<Vec<(String, String)>, String> {
+        Ok(vec![("ceph_command.ts".to_string(), generate(cmds, options))])
+    }
+}
@@ -0,0 +1,363 @@
+//! Checks json commands, the way they're sent to the daemons, against the
+//! parsed command table.
+
+use rustc_serialize::json::Json;
+use std::collections::BTreeMap;
+use std::io::BufRead;
+use std::net::{IpAddr, SocketAddr};
+use std::str::FromStr;
+
+use ceph_command::{CephEnum, CephType, Command, Flag};
+
+#[cfg(test)]
+use test_fixtures::commands;
+
+#[test]
+fn test_check_value() {
+    let int = CephType::new(true, CephEnum::CephInt { min: Some(0), max: Some(64) });
+    assert_eq!(Ok(()), check_value(&int, &Json::I64(8)));
+    assert_eq!(Err("100 is out of range 0..64".to_string()), check_value(&int, &Json::I64(100)));
+    assert_eq!(Err("expected an int, found \"8\"".to_string()), check_value(&int, &Json::String("8".to_string())));
+
+    let names = CephType::new(true, CephEnum::CephString {
+        goodchars: Some("A-Za-z0-9-_.".to_string()), badchars: None, allowed_repeats: ::ceph_command::AllowedRepeats::Many });
+    let list = Json::from_str("[\"a.b\", \"c d\"]").unwrap();
+    assert_eq!(Err("\"c d\" has characters outside [A-Za-z0-9-_.]".to_string()), check_value(&names, &list));
+    assert_eq!(Err("expected a list, found \"a\"".to_string()), check_value(&names, &Json::String("a".to_string())));
+
+    let pgid = CephType::new(true, CephEnum::CephPgid);
+    assert_eq!(Ok(()), check_value(&pgid, &Json::String("1.2f".to_string())));
+    assert!(check_value(&pgid, &Json::String("1".to_string())).is_err());
+
+    let uuid = CephType::new(true, CephEnum::CephUUID);
+    assert_eq!(Ok(()), check_value(&uuid, &Json::String("5a8f6bd2-7c4e-4d3b-9f1a-2e6c8b0d4f17".to_string())));
+    assert!(check_value(&uuid, &Json::String("5a8f6bd2".to_string())).is_err());
+
+    let addr = CephType::new(true, CephEnum::CephEntityAddr);
+    assert_eq!(Ok(()), check_value(&addr, &Json::String("v2:[::1]:3300/0".to_string())));
+    assert!(check_value(&addr, &Json::String("nope/0".to_string())).is_err());
+}
+
+#[test]
+fn test_validate() {
+    let cmds = commands();
+    let pool_create: Vec<&Command> = cmds.iter().filter(|c| c.signature.prefix == "osd pool create").collect();
+    let cmd = Json::from_str(r#"{"prefix": "osd pool create", "pool": "rbd", "pg_num": 8}"#).unwrap();
+    assert_eq!(Ok(pool_create[0]), validate(&cmds, &cmd));
+    // The second overload matches
+    let cmd = Json::from_str(r#"{"prefix": "osd pool create", "pool": "rbd", "size": 3, "format": "json"}"#).unwrap();
+    assert_eq!(Ok(pool_create[1]), validate(&cmds, &cmd));
+
+    let cmd = Json::from_str(r#"{"prefix": "osd pool create", "pg_num": 100000, "pool_type": "ec", "bogus": 1}"#)
+        .unwrap();
+    assert_eq!(Err(vec!["missing required parameter pool".to_string(),
+                        "pg_num: 100000 is out of range 0..65536".to_string(),
+                        "pool_type: \"ec\" is not one of replicated|erasure".to_string(),
+                        "unknown parameter bogus".to_string()]),
+               validate(&cmds, &cmd));
+
+    let cmd = Json::from_str(r#"{"prefix": "osd pool destroy"}"#).unwrap();
+    assert_eq!(Err(vec!["unknown prefix \"osd pool destroy\"".to_string()]), validate(&cmds, &cmd));
+    assert_eq!(Err(vec!["command has no prefix".to_string()]), validate(&cmds, &Json::Object(BTreeMap::new())));
+}
+
+#[test]
+fn test_check_log() {
+    let log = b"{\"prefix\": \"pg repeer\", \"pgid\": \"1.0\"}\n\
+                \n\
+                {\"prefix\": \"pg repeer\", \"pgid\": \"one\"}\n\
+                {\"prefix\": \"osd pool set-quota\", \"pool\": \"rbd\"}\n\
+                {\"prefix\": \"osd crush rule create-legacy\"}\n\
+                {\"prefix\": \"osd pool delete\"}\n\
+                {\"prefix\": \n";
+    let report = check_log(&commands(), "audit.log", &log[..], Some("reef")).unwrap();
+    assert_eq!(vec!["audit.log:3: pg repeer: pgid: \"one\" isn't a valid CephPgid".to_string(),
+                    "audit.log:4: osd pool set-quota: deprecated in reef".to_string(),
+                    "audit.log:5: osd crush rule create-legacy: obsolete in reef".to_string(),
+                    "audit.log:6: unknown prefix \"osd pool delete\"".to_string(),
+                    "audit.log:7: invalid json: SyntaxError(\"EOF While parsing value\", 1, 12)".to_string()],
+               report.problems);
+    assert_eq!((6, 4, 1), (report.checked, report.invalid, report.deprecated));
+}
+
+/// Is c in a regex character class such as A-Za-z0-9-_.  A - between two
+/// characters is a range, anywhere else it's a literal.
+fn in_class(class: &str, c: char) -> bool {
+    let chars: Vec<char> = class.chars().collect();
+    let mut i = 0;
+    while i < chars.len() {
+        if i + 2 < chars.len() && chars[i + 1] == '-' {
+            if chars[i] <= c && c <= chars[i + 2] {
+                return true;
+            }
+            i += 3;
+        } else {
+            if chars[i] == c {
+                return true;
+            }
+            i += 1;
+        }
+    }
+    false
+}
+
+fn is_hex(s: &str) -> bool {
+    !s.is_empty() && s.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// n.xxx where n is a pool id and xxx the pg number in hex
+fn is_pgid(s: &str) -> bool {
+    let mut parts = s.splitn(2, '.');
+    match (parts.next(), parts.next()) {
+        (Some(pool), Some(pg)) => u64::from_str(pool).is_ok() && is_hex(pg),
+        _ => false,
+    }
+}
+
+fn is_uuid(s: &str) -> bool {
+    let groups: Vec<&str> = s.split('-').collect();
+    let lengths: Vec<usize> = groups.iter().map(|g| g.len()).collect();
+    lengths == vec![8, 4, 4, 4, 12] && groups.iter().all(|g| is_hex(g))
+}
+
+/// An ip address with an optional port and msgr protocol, like v2:[::1]:3300
+fn is_addr(s: &str) -> bool {
+    let s = s.trim_start_matches("any:").trim_start_matches("v1:").trim_start_matches("v2:");
+    IpAddr::from_str(s).is_ok() || SocketAddr::from_str(s).is_ok()
+}
+
+/// A single value of a parameter
+fn check_one(ceph_type: &CephType, value: &Json) -> Result<(), String> {
+    let string = value.as_string();
+    let expected = |what: &str| Err(format!("expected {}, found {}", what, value));
+    match ceph_type.variant {
+        CephEnum::CephInt { min, max } => {
+            let n = match value.as_i64() {
+                Some(n) => n,
+                None => return expected("an int"),
+            };
+            if min.is_some_and(|m| n < m) || max.is_some_and(|m| n > m) {
+                return Err(format!("{} is out of range {}..{}",
+                                   n,
+                                   min.map_or(String::new(), |m| m.to_string()),
+                                   max.map_or(String::new(), |m| m.to_string())));
+            }
+        }
+        CephEnum::CephFloat { min, max } => {
+            let f = match value.as_f64() {
+                Some(f) => f,
+                None => return expected("a float"),
+            };
+            if min.is_some_and(|m| f < m as f64) || max.is_some_and(|m| f > m as f64) {
+                return Err(format!("{} is out of range {}..{}",
+                                   f,
+                                   min.map_or(String::new(), |m| m.to_string()),
+                                   max.map_or(String::new(), |m| m.to_string())));
+            }
+        }
+        CephEnum::CephBool => {
+            if !value.is_boolean() {
+                return expected("true or false");
+            }
+        }
+        CephEnum::CephTimespan => {
+            if string.is_none() && !value.is_i64() && !value.is_u64() {
+                return expected("a duration");
+            }
+        }
+        CephEnum::CephOsdName => {
+            // ceph_argparse sends the bare id
+            let ok = match string {
+                Some(s) => s == "*" || u64::from_str(s.trim_start_matches("osd.")).is_ok(),
+                None => value.is_u64(),
+            };
+            if !ok {
+                return expected("an osd id or osd.<id>");
+            }
+        }
+        ref variant => {
+            let s = match string {
+                Some(s) => s,
+                None => return expected("a string"),
+            };
+            if s.is_empty() && !ceph_type.allow_empty {
+                return Err("empty string".to_string());
+            }
+            let ok = match variant {
+                CephEnum::CephString { goodchars, badchars, .. } => {
+                    if let Some(good) = goodchars {
+                        if !good.is_empty() && !s.chars().all(|c| in_class(good, c)) {
+                            return Err(format!("{} has characters outside [{}]", value, good));
+                        }
+                    }
+                    if let Some(bad) = badchars {
+                        if s.chars().any(|c| bad.contains(c)) {
+                            return Err(format!("{} contains one of {}", value, bad));
+                        }
+                    }
+                    true
+                }
+                CephEnum::CephChoices { choices, .. } => {
+                    if !choices.iter().any(|c| c == s) {
+                        return Err(format!("{} is not one of {}", value, choices.join("|")));
+                    }
+                    true
+                }
+                &CephEnum::CephPgid | &CephEnum::CephPgidList => is_pgid(s),
+                &CephEnum::CephUUID => is_uuid(s),
+                &CephEnum::CephIPAddr => is_addr(s),
+                &CephEnum::CephEntityAddr => {
+                    let mut parts = s.splitn(2, '/');
+                    is_addr(parts.next().unwrap_or("")) && parts.next().is_none_or(|n| u64::from_str(n).is_ok())
+                }
+                &CephEnum::CephName => s == "*" || s.splitn(2, '.').count() == 2,
+                &CephEnum::CephFragment => {
+                    let mut parts = s.splitn(2, '/');
+                    match (parts.next(), parts.next()) {
+                        (Some(val), Some(bits)) => val.starts_with("0x") && is_hex(&val[2..]) && u32::from_str(bits).is_ok(),
+                        _ => false,
+                    }
+                }
+                // Paths and names can only be checked by the daemon
+                _ => true,
+            };
+            if !ok {
+                return Err(format!("{} isn't a valid {}", value, variant.type_name()));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Check the value sent for a parameter.  Parameters taking many values are
+/// sent as a list.
+pub fn check_value(ceph_type: &CephType, value: &Json) -> Result<(), String> {
+    if ceph_type.variant.is_many() {
+        match value {
+            Json::Array(items) => {
+                for item in items.iter() {
+                    check_one(ceph_type, item)?;
+                }
+                Ok(())
+            }
+            _ => Err(format!("expected a list, found {}", value)),
+        }
+    } else {
+        check_one(ceph_type, value)
+    }
+}
+
+/// Everything wrong with the arguments sent for cmd.  format can be sent to
+/// any command.
+pub fn check_args(cmd: &Command, args: &BTreeMap<String, Json>) -> Vec<String> {
+    let mut problems = Vec::new();
+    for (name, ceph_type) in cmd.signature.ordered_parameters() {
+        match args.get(name) {
+            None if ceph_type.req => problems.push(format!("missing required parameter {}", name)),
+            None => {}
+            Some(value) => {
+                if let Err(e) = check_value(ceph_type, value) {
+                    problems.push(format!("{}: {}", name, e));
+                }
+            }
+        }
+    }
+    for key in args.keys() {
+        if key != "prefix" && key != "format" && !cmd.signature.parameters.contains_key(key) {
+            problems.push(format!("unknown parameter {}", key));
+        }
+    }
+    problems
+}
+
+/// Find the command json is an instance of.  When several commands share a
+/// prefix the first one the arguments fit wins, otherwise the problems with
+/// the closest one are returned.
+pub fn validate<'a>(cmds: &'a [Command], json: &Json) -> Result<&'a Command, Vec<String>> {
+    let args = match json.as_object() {
+        Some(args) => args,
+        None => return Err(vec!["command should be a json object".to_string()]),
+    };
+    let prefix = match args.get("prefix").and_then(|p| p.as_string()) {
+        Some(prefix) => prefix,
+        None => return Err(vec!["command has no prefix".to_string()]),
+    };
+    let mut closest: Option<Vec<String>> = None;
+    for cmd in cmds.iter().filter(|c| c.signature.prefix == prefix) {
+        let problems = check_args(cmd, args);
+        if problems.is_empty() {
+            return Ok(cmd);
+        }
+        if closest.as_ref().is_none_or(|c| problems.len() < c.len()) {
+            closest = Some(problems);
+        }
+    }
+    Err(closest.unwrap_or(vec![format!("unknown prefix {:?}", prefix)]))
+}
+
+/// What check_log found
+#[derive(Debug, Default)]
+pub struct LogReport {
+    /// One line per problem, prefixed with file:line
+    pub problems: Vec<String>,
+    pub checked: usize,
+    /// Commands the daemons would refuse, obsolete ones included
+    pub invalid: usize,
+    /// Commands that work but are deprecated
+    pub deprecated: usize,
+}
+
+/// Is the command with this prefix flagged in the table?
+fn flagged(cmds: &[Command], prefix: &str, flag: Flag) -> bool {
+    cmds.iter()
+        .filter(|c| c.signature.prefix == prefix)
+        .any(|c| c.flags.as_ref().is_some_and(|flags| flags.contains(&flag)))
+}
+
+/// Check newline delimited json commands, such as an audit log of what was
+/// sent to the monitors, against cmds.  Commands that are deprecated or
+/// obsolete in cmds are reported as such, release names the table.
+pub fn check_log<R: BufRead>(cmds: &[Command], name: &str, reader: R, release: Option<&str>) -> Result<LogReport, String> {
+    let release = release.map_or(String::new(), |r| format!(" in {}", r));
+    let mut report = LogReport::default();
+    for (i, line) in reader.lines().enumerate() {
+        let line = line.map_err(|e| format!("Failed to read {}: {}", name, e))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        report.checked += 1;
+        let location = format!("{}:{}", name, i + 1);
+        let json = match Json::from_str(&line) {
+            Ok(json) => json,
+            Err(e) => {
+                report.invalid += 1;
+                report.problems.push(format!("{}: invalid json: {}", location, e));
+                continue;
+            }
+        };
+        let prefix = match validate(cmds, &json) {
+            Ok(cmd) => cmd.signature.prefix.clone(),
+            Err(problems) => {
+                report.invalid += 1;
+                // Argument problems are only useful next to the command
+                let known = json.find("prefix")
+                    .and_then(|p| p.as_string())
+                    .filter(|p| cmds.iter().any(|c| c.signature.prefix == *p));
+                let location = match known {
+                    Some(prefix) => format!("{}: {}", location, prefix),
+                    None => location,
+                };
+                report.problems.push(format!("{}: {}", location, problems.join("; ")));
+                continue;
+            }
+        };
+        if flagged(cmds, &prefix, Flag::Obsolete) {
+            report.invalid += 1;
+            report.problems.push(format!("{}: {}: obsolete{}", location, prefix, release));
+        } else if flagged(cmds, &prefix, Flag::Deprecated) {
+            report.deprecated += 1;
+            report.problems.push(format!("{}: {}: deprecated{}", location, prefix, release));
+        }
+    }
+    Ok(report)
+}